@@ -0,0 +1,226 @@
+//! Precomputed attack tables. Leaper tables come straight from the offset
+//! tables; sliders use magic bitboards whose multipliers are found by a
+//! seeded random search the first time any table is used.
+
+use std::sync::OnceLock;
+
+use crate::{Bitboard, PieceColor, Square};
+
+pub const BISHOP_OFFSETS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+pub const ROOK_OFFSETS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+pub const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+];
+pub const KING_OFFSETS: [(i32, i32); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        let hash = (occupied.0 & self.mask).wrapping_mul(self.magic) >> self.shift;
+        self.offset + hash as usize
+    }
+}
+
+struct AttackTables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    pawn: [[Bitboard; 64]; 2],
+    bishop: [Magic; 64],
+    rook: [Magic; 64],
+    sliders: Vec<Bitboard>,
+}
+
+static TABLES: OnceLock<AttackTables> = OnceLock::new();
+
+fn tables() -> &'static AttackTables {
+    TABLES.get_or_init(AttackTables::new)
+}
+
+pub fn knight_attacks(square: Square) -> Bitboard {
+    tables().knight[square.index()]
+}
+
+pub fn king_attacks(square: Square) -> Bitboard {
+    tables().king[square.index()]
+}
+
+/// Squares a pawn of `color` on `square` attacks.
+pub fn pawn_attacks(color: PieceColor, square: Square) -> Bitboard {
+    tables().pawn[color as usize][square.index()]
+}
+
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.sliders[tables.bishop[square.index()].index(occupied)]
+}
+
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.sliders[tables.rook[square.index()].index(occupied)]
+}
+
+pub fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(square, occupied) | rook_attacks(square, occupied)
+}
+
+impl AttackTables {
+    fn new() -> Self {
+        let mut tables = Self {
+            knight: [Bitboard::EMPTY; 64],
+            king: [Bitboard::EMPTY; 64],
+            pawn: [[Bitboard::EMPTY; 64]; 2],
+            bishop: [Magic::default(); 64],
+            rook: [Magic::default(); 64],
+            sliders: Vec::new(),
+        };
+        for square in Square::all() {
+            let i = square.index();
+            tables.knight[i] = step_attacks(square, &KNIGHT_OFFSETS);
+            tables.king[i] = step_attacks(square, &KING_OFFSETS);
+            for color in [PieceColor::White, PieceColor::Black] {
+                let dy = color.pawn_direction();
+                tables.pawn[color as usize][i] = step_attacks(square, &[(-1, dy), (1, dy)]);
+            }
+            tables.bishop[i] = find_magic(square, &BISHOP_OFFSETS, &mut tables.sliders);
+            tables.rook[i] = find_magic(square, &ROOK_OFFSETS, &mut tables.sliders);
+        }
+
+        tables
+    }
+}
+
+fn step_attacks(square: Square, offsets: &[(i32, i32)]) -> Bitboard {
+    offsets
+        .iter()
+        .filter_map(|&(dx, dy)| square.offset(dx, dy))
+        .fold(Bitboard::EMPTY, |bb, sq| bb | Bitboard::from(sq))
+}
+
+/// Reference slider attacks, walking each ray until it hits a blocker.
+fn ray_attacks(square: Square, occupied: Bitboard, offsets: &[(i32, i32)]) -> Bitboard {
+    let mut attacks = Bitboard::EMPTY;
+    for &(dx, dy) in offsets {
+        let mut step = 1;
+        while let Some(sq) = square.offset(dx * step, dy * step) {
+            attacks.insert(sq);
+            if occupied.contains(sq) {
+                break;
+            }
+            step += 1;
+        }
+    }
+    attacks
+}
+
+/// Squares whose occupancy can change the slider's attacks: every ray square
+/// except the last one before the edge.
+fn relevant_mask(square: Square, offsets: &[(i32, i32)]) -> Bitboard {
+    let mut mask = Bitboard::EMPTY;
+    for &(dx, dy) in offsets {
+        let mut step = 1;
+        while let Some(sq) = square.offset(dx * step, dy * step) {
+            if square.offset(dx * (step + 1), dy * (step + 1)).is_none() {
+                break;
+            }
+            mask.insert(sq);
+            step += 1;
+        }
+    }
+    mask
+}
+
+/// Searches for a multiplier that maps every relevant occupancy of `square`
+/// to a slot holding its attack set, and appends that slot table to `table`.
+fn find_magic(square: Square, offsets: &[(i32, i32)], table: &mut Vec<Bitboard>) -> Magic {
+    let mask = relevant_mask(square, offsets);
+    let bits = mask.count();
+    let size = 1 << bits;
+
+    // Enumerate all subsets of the mask (Carry-Rippler).
+    let mut occupancies = Vec::with_capacity(size);
+    let mut subset = 0u64;
+    loop {
+        occupancies.push(Bitboard(subset));
+        subset = subset.wrapping_sub(mask.0) & mask.0;
+        if subset == 0 {
+            break;
+        }
+    }
+    let references: Vec<Bitboard> = occupancies
+        .iter()
+        .map(|&occ| ray_attacks(square, occ, offsets))
+        .collect();
+
+    // Slots are tagged with the attempt that filled them, so a failed
+    // attempt's leftovers never need clearing.
+    let mut slots = vec![(0u32, Bitboard::EMPTY); size];
+    let mut attempt = 0;
+    let mut rng = XorShift(MAGIC_SEEDS[square.rank() as usize]);
+    loop {
+        let magic = Magic {
+            mask: mask.0,
+            magic: rng.next() & rng.next() & rng.next(),
+            shift: 64 - bits,
+            offset: table.len(),
+        };
+        // Cheap rejection of multipliers that spread the mask's high bits poorly.
+        if (mask.0.wrapping_mul(magic.magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        attempt += 1;
+        let collision_free = occupancies.iter().zip(&references).all(|(&occ, &attacks)| {
+            let slot = &mut slots[magic.index(occ) - magic.offset];
+            if slot.0 != attempt {
+                *slot = (attempt, attacks);
+                true
+            } else {
+                slot.1 == attacks
+            }
+        });
+
+        if collision_free {
+            table.extend(slots.iter().map(|&(_, attacks)| attacks));
+            return magic;
+        }
+    }
+}
+
+/// Per-rank PRNG seeds (as used by Stockfish) that reach a working magic
+/// after few attempts, keeping table construction in the milliseconds.
+const MAGIC_SEEDS: [u64; 8] = [728, 10316, 55013, 32803, 12281, 15100, 16645, 255];
+
+/// xorshift64* generator, shared with the Zobrist key table.
+pub(crate) struct XorShift(pub(crate) u64);
+
+impl XorShift {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
@@ -0,0 +1,91 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+use crate::Square;
+
+/// A set of squares, one bit per square in [`Square::index`] order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Self = Self(0);
+    pub const FULL: Self = Self(!0);
+
+    pub fn from_square(square: Square) -> Self {
+        Self(1 << square.index())
+    }
+
+    pub fn contains(self, square: Square) -> bool {
+        self.0 & (1 << square.index()) != 0
+    }
+
+    pub fn insert(&mut self, square: Square) {
+        self.0 |= 1 << square.index();
+    }
+
+    pub fn remove(&mut self, square: Square) {
+        self.0 &= !(1 << square.index());
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// The lowest-indexed square in the set.
+    pub fn first(self) -> Option<Square> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(Square::from_index(self.0.trailing_zeros() as usize))
+        }
+    }
+}
+
+impl Iterator for Bitboard {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        let square = self.first()?;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+impl From<Square> for Bitboard {
+    fn from(square: Square) -> Self {
+        Self::from_square(square)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+macro_rules! impl_bit_op {
+    ($op:ident, $fn:ident, $assign_op:ident, $assign_fn:ident) => {
+        impl $op for Bitboard {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                Self($op::$fn(self.0, rhs.0))
+            }
+        }
+
+        impl $assign_op for Bitboard {
+            fn $assign_fn(&mut self, rhs: Self) {
+                $assign_op::$assign_fn(&mut self.0, rhs.0);
+            }
+        }
+    };
+}
+
+impl_bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_bit_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
@@ -0,0 +1,241 @@
+//! Chess clocks: a base time per player plus a Fischer increment or a
+//! Bronstein or simple delay. The clock is driven by [`ChessClock::tick`], so
+//! it runs on whatever time source the caller has.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::PieceColor;
+
+/// What a player gets back for each move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeBonus {
+    #[default]
+    None,
+    /// Fischer increment: added after every move.
+    Increment(Duration),
+    /// Bronstein delay: after a move, the time it took is given back, up to
+    /// this much.
+    Bronstein(Duration),
+    /// Simple (US) delay: each turn, the clock waits this long before it
+    /// starts counting down.
+    Delay(Duration),
+}
+
+/// Base time per player and the per-move bonus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub bonus: TimeBonus,
+}
+
+impl TimeControl {
+    pub fn new(base: Duration, bonus: TimeBonus) -> Self {
+        Self { base, bonus }
+    }
+
+    /// `minutes` per player plus `increment` seconds per move.
+    pub fn fischer(minutes: u64, increment: u64) -> Self {
+        Self::new(
+            Duration::from_secs(minutes * 60),
+            TimeBonus::Increment(Duration::from_secs(increment)),
+        )
+    }
+}
+
+/// Written as base minutes (or seconds with an `s`) followed by `+` and the
+/// increment, `b` and the Bronstein delay, or `d` and the simple delay, in
+/// seconds: `5+3`, `15d5`, `90s+1`.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.base.as_secs();
+        if secs.is_multiple_of(60) {
+            write!(f, "{}", secs / 60)?;
+        } else {
+            write!(f, "{secs}s")?;
+        }
+        match self.bonus {
+            TimeBonus::None => Ok(()),
+            TimeBonus::Increment(d) => write!(f, "+{}", d.as_secs()),
+            TimeBonus::Bronstein(d) => write!(f, "b{}", d.as_secs()),
+            TimeBonus::Delay(d) => write!(f, "d{}", d.as_secs()),
+        }
+    }
+}
+
+/// A time control string that is not in the form [`TimeControl`] displays.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeControlError(pub String);
+
+impl fmt::Display for TimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid time control '{}', expected e.g. 5+3, 15d5 or 90s+1",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for TimeControlError {}
+
+impl FromStr for TimeControl {
+    type Err = TimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, TimeControlError> {
+        let error = || TimeControlError(s.to_string());
+        let split = s.find(['+', 'b', 'd']);
+        let (base, bonus) = match split {
+            Some(i) => (&s[..i], Some((&s[i..i + 1], &s[i + 1..]))),
+            None => (s, None),
+        };
+
+        let base = match base.strip_suffix('s') {
+            Some(secs) => Duration::from_secs(secs.parse().map_err(|_| error())?),
+            None => Duration::from_secs(base.parse::<u64>().map_err(|_| error())? * 60),
+        };
+        if base.is_zero() {
+            return Err(error());
+        }
+
+        let bonus = match bonus {
+            None => TimeBonus::None,
+            Some((kind, secs)) => {
+                let secs = Duration::from_secs(secs.parse().map_err(|_| error())?);
+                match kind {
+                    "+" => TimeBonus::Increment(secs),
+                    "b" => TimeBonus::Bronstein(secs),
+                    _ => TimeBonus::Delay(secs),
+                }
+            }
+        };
+        Ok(Self::new(base, bonus))
+    }
+}
+
+/// A two-sided chess clock. At most one side runs at a time; a side whose
+/// time reaches zero has flagged and the clock stops for good.
+#[derive(Clone, Debug)]
+pub struct ChessClock {
+    control: TimeControl,
+    /// Time left at the start of each side's current turn, White first.
+    remaining: [Duration; 2],
+    running: Option<PieceColor>,
+    /// Time the running side has spent on its current turn.
+    elapsed: Duration,
+    flagged: Option<PieceColor>,
+}
+
+fn index(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    }
+}
+
+impl ChessClock {
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            remaining: [control.base; 2],
+            running: None,
+            elapsed: Duration::ZERO,
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    pub fn running(&self) -> Option<PieceColor> {
+        self.running
+    }
+
+    /// The side that ran out of time, if one has.
+    pub fn flagged(&self) -> Option<PieceColor> {
+        self.flagged
+    }
+
+    /// Time left for `color`, counting its current turn if it is running.
+    pub fn remaining(&self, color: PieceColor) -> Duration {
+        let remaining = self.remaining[index(color)];
+        if self.running == Some(color) {
+            remaining.saturating_sub(self.charged())
+        } else {
+            remaining
+        }
+    }
+
+    /// Sets the time `color` has left, e.g. to what a server says it is. A
+    /// running side's turn starts counting again from now.
+    pub fn set_remaining(&mut self, color: PieceColor, time: Duration) {
+        self.remaining[index(color)] = time;
+        if self.running == Some(color) {
+            self.elapsed = Duration::ZERO;
+        }
+    }
+
+    /// The part of the current turn that comes off the clock: all of it,
+    /// except the delay under a simple delay.
+    fn charged(&self) -> Duration {
+        match self.control.bonus {
+            TimeBonus::Delay(delay) => self.elapsed.saturating_sub(delay),
+            _ => self.elapsed,
+        }
+    }
+
+    /// Starts `color`'s clock, stopping the other side's without a bonus.
+    /// Used to start the game or to follow a take-back.
+    pub fn start(&mut self, color: PieceColor) {
+        self.end_turn(false);
+        if self.flagged.is_none() {
+            self.running = Some(color);
+        }
+    }
+
+    /// The running side has moved: its bonus is added and the other side's
+    /// clock starts.
+    pub fn press(&mut self) {
+        if let Some(color) = self.running {
+            self.end_turn(true);
+            self.running = Some(color.opposite());
+        }
+    }
+
+    /// Stops both clocks, e.g. when the game ends.
+    pub fn pause(&mut self) {
+        self.end_turn(false);
+    }
+
+    /// Advances the running side's clock by `delta` and returns the side that
+    /// has run out of time, if any.
+    pub fn tick(&mut self, delta: Duration) -> Option<PieceColor> {
+        if let Some(color) = self.running {
+            self.elapsed += delta;
+            if self.remaining(color).is_zero() {
+                self.flagged = Some(color);
+                self.end_turn(false);
+            }
+        }
+        self.flagged
+    }
+
+    fn end_turn(&mut self, moved: bool) {
+        let Some(color) = self.running else {
+            return;
+        };
+        let mut remaining = self.remaining(color);
+        if moved {
+            remaining += match self.control.bonus {
+                TimeBonus::None | TimeBonus::Delay(_) => Duration::ZERO,
+                TimeBonus::Increment(increment) => increment,
+                TimeBonus::Bronstein(delay) => self.elapsed.min(delay),
+            };
+        }
+        self.remaining[index(color)] = remaining;
+        self.running = None;
+        self.elapsed = Duration::ZERO;
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use crate::{Bitboard, PieceColor, PieceType, Position};
+
+/// Light squares; a1 is dark.
+const LIGHT_SQUARES: Bitboard = Bitboard(0x55AA_55AA_55AA_55AA);
+
+/// Why a game is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DrawReason {
+    Stalemate,
+    /// Neither side has enough material left to deliver mate.
+    InsufficientMaterial,
+    /// One side ran out of time, but the other could not have mated it.
+    TimeoutVsInsufficientMaterial,
+    /// The same position occurred three times; claimable.
+    ThreefoldRepetition,
+    /// The same position occurred five times; automatic.
+    FivefoldRepetition,
+    /// Fifty moves by each side without a capture or pawn move; claimable.
+    FiftyMoveRule,
+    /// Seventy-five moves by each side without a capture or pawn move; automatic.
+    SeventyFiveMoveRule,
+    /// The players agreed to a draw.
+    Agreement,
+}
+
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DrawReason::Stalemate => "stalemate",
+            DrawReason::InsufficientMaterial => "insufficient material",
+            DrawReason::TimeoutVsInsufficientMaterial => "timeout vs insufficient material",
+            DrawReason::ThreefoldRepetition => "threefold repetition",
+            DrawReason::FivefoldRepetition => "fivefold repetition",
+            DrawReason::FiftyMoveRule => "the fifty-move rule",
+            DrawReason::SeventyFiveMoveRule => "the seventy-five-move rule",
+            DrawReason::Agreement => "agreement",
+        })
+    }
+}
+
+impl Position {
+    /// Neither side can mate by any sequence of moves: bare kings, a single
+    /// minor piece, or only bishops that all stand on one square color.
+    pub fn has_insufficient_material(&self) -> bool {
+        let heavy = self.by_kind(PieceType::Pawn)
+            | self.by_kind(PieceType::Rook)
+            | self.by_kind(PieceType::Queen);
+        if !heavy.is_empty() {
+            return false;
+        }
+
+        let knights = self.by_kind(PieceType::Knight);
+        let bishops = self.by_kind(PieceType::Bishop);
+        if knights.count() + bishops.count() <= 1 {
+            return true;
+        }
+        knights.is_empty()
+            && ((bishops & LIGHT_SQUARES).is_empty() || (bishops & !LIGHT_SQUARES).is_empty())
+    }
+
+    /// Whether `color` could still mate by some sequence of legal moves,
+    /// which decides whether running out of time against it loses or draws.
+    /// Against a bare king, a single minor piece or bishops on one square
+    /// color cannot; with the defender's own pieces to block its king, they
+    /// can.
+    pub fn has_mating_material(&self, color: PieceColor) -> bool {
+        let king = |color| self.pieces_of(color, PieceType::King);
+        let attackers = self.by_color(color) & !king(color);
+        let defenders = self.by_color(color.opposite()) & !king(color.opposite());
+        if attackers.is_empty() {
+            return false;
+        }
+        if !defenders.is_empty() {
+            return true;
+        }
+
+        let knights = self.pieces_of(color, PieceType::Knight);
+        let bishops = self.pieces_of(color, PieceType::Bishop);
+        if attackers != knights | bishops {
+            return true;
+        }
+        if attackers.count() == 1 {
+            return false;
+        }
+        !knights.is_empty()
+            || !((bishops & LIGHT_SQUARES).is_empty() || (bishops & !LIGHT_SQUARES).is_empty())
+    }
+
+    /// A draw that ends the game without either player claiming it. A move
+    /// that delivers checkmate takes precedence over the seventy-five-move
+    /// rule, so check [`Position::is_checkmate`] first.
+    pub fn automatic_draw(&self) -> Option<DrawReason> {
+        if self.is_stalemate() {
+            Some(DrawReason::Stalemate)
+        } else if self.has_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.repetition_count() >= 5 {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.halfmove_clock() >= 150 {
+            Some(DrawReason::SeventyFiveMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// A draw the side to move may claim.
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetition_count() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock() >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Static evaluation: material plus piece-square tables, with the king table
+//! blended between middlegame and endgame by the material left on the board.
+
+use crate::{PieceColor, PieceType, Position, Square};
+
+/// Centipawn values indexed by [`PieceType`]. The king is never traded, so it
+/// counts for nothing.
+const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+/// Game phase weights of knights, bishops, rooks and queens; 24 is the
+/// starting total, 0 a pawn or bare-king ending.
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+// Tables are written from White's point of view with rank 8 on top, as they
+// would appear on a diagram.
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// Centipawn value of a piece, used for evaluation and capture ordering.
+pub fn piece_value(kind: PieceType) -> i32 {
+    PIECE_VALUES[kind as usize]
+}
+
+/// Static score of `position` in centipawns from the point of view of the
+/// side to move.
+pub fn evaluate(position: &Position) -> i32 {
+    let phase = position
+        .pieces()
+        .map(|(_, piece)| PHASE_WEIGHTS[piece.kind as usize])
+        .sum::<i32>()
+        .min(MAX_PHASE);
+
+    let mut score = 0;
+    for (square, piece) in position.pieces() {
+        let index = table_index(square, piece.color);
+        let positional = match piece.kind {
+            PieceType::Pawn => PAWN_TABLE[index],
+            PieceType::Knight => KNIGHT_TABLE[index],
+            PieceType::Bishop => BISHOP_TABLE[index],
+            PieceType::Rook => ROOK_TABLE[index],
+            PieceType::Queen => QUEEN_TABLE[index],
+            PieceType::King => {
+                (KING_MIDDLEGAME_TABLE[index] * phase
+                    + KING_ENDGAME_TABLE[index] * (MAX_PHASE - phase))
+                    / MAX_PHASE
+            }
+        };
+        let value = piece_value(piece.kind) + positional;
+        score += match piece.color {
+            PieceColor::White => value,
+            PieceColor::Black => -value,
+        };
+    }
+
+    match position.side_to_move() {
+        PieceColor::White => score,
+        PieceColor::Black => -score,
+    }
+}
+
+/// Index into a diagram-ordered table, mirrored vertically for Black.
+fn table_index(square: Square, color: PieceColor) -> usize {
+    let rank = match color {
+        PieceColor::White => 7 - square.rank(),
+        PieceColor::Black => square.rank(),
+    };
+    rank as usize * 8 + square.file() as usize
+}
@@ -0,0 +1,337 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{CastlingRights, Piece, PieceColor, PieceType, Position, Square};
+
+/// FEN of the standard starting position.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Why a FEN string could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// FEN needs four to six space-separated fields.
+    FieldCount(usize),
+    /// Piece placement must describe exactly eight ranks.
+    RankCount(usize),
+    /// A rank in the placement field does not add up to eight squares.
+    RankLength {
+        rank: u8,
+        squares: u32,
+    },
+    InvalidPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    /// A castling right is set but the king or rook is not on its home square.
+    InconsistentCastling(char),
+    /// Not a square an opponent's pawn could just have skipped over.
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    /// Each side needs exactly one king; holds the count found.
+    KingCount {
+        color: PieceColor,
+        count: u32,
+    },
+    /// A pawn stands on its first or last rank.
+    PawnOnBackRank(Square),
+    /// The side that just moved is in check.
+    OpponentInCheck,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "expected 4 to 6 fields, found {n}"),
+            FenError::RankCount(n) => write!(f, "expected 8 ranks, found {n}"),
+            FenError::RankLength { rank, squares } => {
+                write!(f, "rank {rank} describes {squares} squares instead of 8")
+            }
+            FenError::InvalidPiece(c) => write!(f, "invalid piece character '{c}'"),
+            FenError::InvalidSideToMove(s) => {
+                write!(f, "side to move must be 'w' or 'b', found '{s}'")
+            }
+            FenError::InvalidCastling(s) => write!(f, "invalid castling field '{s}'"),
+            FenError::InconsistentCastling(c) => write!(
+                f,
+                "castling right '{c}' requires king and rook on their home squares"
+            ),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en-passant square '{s}'"),
+            FenError::InvalidHalfmoveClock(s) => write!(f, "invalid halfmove clock '{s}'"),
+            FenError::InvalidFullmoveNumber(s) => write!(f, "invalid fullmove number '{s}'"),
+            FenError::KingCount { color, count } => {
+                write!(f, "{color:?} must have exactly one king, found {count}")
+            }
+            FenError::PawnOnBackRank(sq) => write!(f, "pawn on back rank at {sq}"),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl Position {
+    /// Parses Forsyth–Edwards Notation. The halfmove and fullmove fields may
+    /// be omitted and default to `0 1`.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if !(4..=6).contains(&fields.len()) {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let mut position = Position::empty();
+        parse_placement(&mut position, fields[0])?;
+
+        position.set_side_to_move(match fields[1] {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_string())),
+        });
+
+        position.set_castling_rights(parse_castling(&position, fields[2])?);
+
+        let en_passant = match fields[3] {
+            "-" => None,
+            s => {
+                let sq = Square::from_algebraic(s)
+                    .ok_or_else(|| FenError::InvalidEnPassant(s.to_string()))?;
+                // The opponent's pawn just went from `origin` past `sq` to
+                // `landed`, so both squares it crossed are empty.
+                let (expected_rank, landed, origin) = match position.side_to_move() {
+                    PieceColor::White => (5, 4, 6),
+                    PieceColor::Black => (2, 3, 1),
+                };
+                let pawn = Piece::new(position.side_to_move().opposite(), PieceType::Pawn);
+                let possible = sq.rank() == expected_rank
+                    && position.piece_at(sq).is_none()
+                    && position.piece_at(Square::new(sq.file(), origin)).is_none()
+                    && position.piece_at(Square::new(sq.file(), landed)) == Some(pawn);
+                if !possible {
+                    return Err(FenError::InvalidEnPassant(s.to_string()));
+                }
+                Some(sq)
+            }
+        };
+        position.set_en_passant(en_passant);
+
+        if let Some(s) = fields.get(4) {
+            let clock = s
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(s.to_string()))?;
+            position.set_halfmove_clock(clock);
+        }
+        if let Some(s) = fields.get(5) {
+            let number = s
+                .parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| FenError::InvalidFullmoveNumber(s.to_string()))?;
+            position.set_fullmove_number(number);
+        }
+
+        validate(&position)?;
+        Ok(position)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.piece_at(Square::new(file, rank)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_char(piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.side_to_move() {
+            PieceColor::White => 'w',
+            PieceColor::Black => 'b',
+        });
+
+        fen.push(' ');
+        let rights = self.castling_rights();
+        let before = fen.len();
+        for (right, c) in CASTLING_CHARS {
+            if rights.contains(right) {
+                fen.push(c);
+            }
+        }
+        if fen.len() == before {
+            fen.push('-');
+        }
+
+        fen.push(' ');
+        match self.en_passant() {
+            Some(sq) => fen.push_str(&sq.to_string()),
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock(),
+            self.fullmove_number()
+        ));
+        fen
+    }
+}
+
+impl FromStr for Position {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, FenError> {
+        Position::from_fen(s)
+    }
+}
+
+const CASTLING_CHARS: [(CastlingRights, char); 4] = [
+    (CastlingRights::WHITE_KINGSIDE, 'K'),
+    (CastlingRights::WHITE_QUEENSIDE, 'Q'),
+    (CastlingRights::BLACK_KINGSIDE, 'k'),
+    (CastlingRights::BLACK_QUEENSIDE, 'q'),
+];
+
+fn parse_placement(position: &mut Position, placement: &str) -> Result<(), FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::RankCount(ranks.len()));
+    }
+
+    for (row, pieces) in ranks.into_iter().enumerate() {
+        let rank = 7 - row as u8;
+        let mut file = 0u32;
+        for c in pieces.chars() {
+            if let Some(skip) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                file += skip;
+                continue;
+            }
+            let piece = piece_from_char(c).ok_or(FenError::InvalidPiece(c))?;
+            if file < 8 {
+                position.set_piece(Square::new(file as u8, rank), Some(piece));
+            }
+            file += 1;
+        }
+        if file != 8 {
+            return Err(FenError::RankLength {
+                rank: rank + 1,
+                squares: file,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn parse_castling(position: &Position, field: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::NONE;
+    if field == "-" {
+        return Ok(rights);
+    }
+
+    for c in field.chars() {
+        let (right, _) = CASTLING_CHARS
+            .into_iter()
+            .find(|&(_, ch)| ch == c)
+            .ok_or_else(|| FenError::InvalidCastling(field.to_string()))?;
+        if rights.contains(right) {
+            return Err(FenError::InvalidCastling(field.to_string()));
+        }
+        rights.insert(right);
+    }
+
+    for (right, c) in CASTLING_CHARS {
+        if !rights.contains(right) {
+            continue;
+        }
+        let color = if c.is_ascii_uppercase() {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        let rank = color.back_rank();
+        let rook_file = if right == CastlingRights::kingside(color) {
+            7
+        } else {
+            0
+        };
+        if position.piece_at(Square::new(4, rank)) != Some(Piece::new(color, PieceType::King))
+            || position.piece_at(Square::new(rook_file, rank))
+                != Some(Piece::new(color, PieceType::Rook))
+        {
+            return Err(FenError::InconsistentCastling(c));
+        }
+    }
+    Ok(rights)
+}
+
+fn validate(position: &Position) -> Result<(), FenError> {
+    for color in [PieceColor::White, PieceColor::Black] {
+        let count = position.pieces_of(color, PieceType::King).count();
+        if count != 1 {
+            return Err(FenError::KingCount { color, count });
+        }
+    }
+
+    if let Some(sq) = position
+        .by_kind(PieceType::Pawn)
+        .find(|sq| sq.rank() == 0 || sq.rank() == 7)
+    {
+        return Err(FenError::PawnOnBackRank(sq));
+    }
+
+    let opponent = position.side_to_move().opposite();
+    if position
+        .king_square(opponent)
+        .is_some_and(|sq| position.is_square_attacked(sq, position.side_to_move()))
+    {
+        return Err(FenError::OpponentInCheck);
+    }
+
+    Ok(())
+}
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    let kind = match c.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+    let color = if c.is_ascii_uppercase() {
+        PieceColor::White
+    } else {
+        PieceColor::Black
+    };
+    Some(Piece::new(color, kind))
+}
+
+fn piece_char(piece: Piece) -> char {
+    let c = match piece.kind {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+    match piece.color {
+        PieceColor::White => c.to_ascii_uppercase(),
+        PieceColor::Black => c,
+    }
+}
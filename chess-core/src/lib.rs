@@ -0,0 +1,47 @@
+//! Bevy-free chess rules: board representation, move generation and move
+//! application. The game client renders a [`Position`]; tools, bots and tests
+//! can use it directly without spinning up an `App`.
+
+mod attacks;
+mod bitboard;
+mod clock;
+mod draw;
+mod eval;
+mod fen;
+mod moves;
+mod net;
+mod perft;
+mod pgn;
+mod piece;
+mod position;
+mod result;
+mod rules;
+mod san;
+mod search;
+mod square;
+mod uci;
+mod zobrist;
+
+pub use attacks::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks,
+};
+pub use bitboard::Bitboard;
+pub use clock::{ChessClock, TimeBonus, TimeControl, TimeControlError};
+pub use draw::DrawReason;
+pub use eval::{evaluate, piece_value};
+pub use fen::{FenError, STARTING_FEN};
+pub use moves::{Move, MoveKind};
+pub use net::{
+    DEFAULT_PORT, NetError, PROTOCOL_VERSION, PeerConnection, PeerListener, PeerMessage,
+};
+pub use pgn::{PgnError, PgnErrorKind, PgnGame, PgnMove};
+pub use piece::{Piece, PieceColor, PieceType};
+pub use position::{CastlingRights, Position};
+pub use result::{GameResult, Termination};
+pub use san::SanError;
+pub use search::{MATE_SCORE, SearchInfo, SearchLimits, Searcher};
+pub use square::Square;
+pub use uci::{
+    EngineMessage, GoParams, GuiCommand, GuiCommandError, UciInfo, UciScore, go_command, info_line,
+    position_command,
+};
@@ -0,0 +1,87 @@
+use std::fmt;
+
+use crate::{PieceType, Square};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    Quiet,
+    DoublePush,
+    Capture,
+    EnPassant,
+    KingsideCastle,
+    QueensideCastle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub kind: MoveKind,
+    pub promotion: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square, kind: MoveKind) -> Self {
+        Self {
+            from,
+            to,
+            kind,
+            promotion: None,
+        }
+    }
+
+    pub fn with_promotion(self, promotion: PieceType) -> Self {
+        Self {
+            promotion: Some(promotion),
+            ..self
+        }
+    }
+
+    pub fn is_capture(self) -> bool {
+        matches!(self.kind, MoveKind::Capture | MoveKind::EnPassant)
+    }
+
+    pub fn is_castle(self) -> bool {
+        matches!(
+            self.kind,
+            MoveKind::KingsideCastle | MoveKind::QueensideCastle
+        )
+    }
+
+    /// Square of the piece removed by this move. For en passant this is the
+    /// square the victim pawn stands on, not the destination.
+    pub fn captured_square(self) -> Option<Square> {
+        match self.kind {
+            MoveKind::Capture => Some(self.to),
+            MoveKind::EnPassant => Some(Square::new(self.to.file(), self.from.rank())),
+            _ => None,
+        }
+    }
+
+    /// Rook `(from, to)` squares for a castling move.
+    pub fn castling_rook(self) -> Option<(Square, Square)> {
+        let rank = self.from.rank();
+        match self.kind {
+            MoveKind::KingsideCastle => Some((Square::new(7, rank), Square::new(5, rank))),
+            MoveKind::QueensideCastle => Some((Square::new(0, rank), Square::new(3, rank))),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the move in long algebraic (UCI) notation, e.g. `e2e4` or `e7e8q`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(kind) = self.promotion {
+            let c = match kind {
+                PieceType::Knight => 'n',
+                PieceType::Bishop => 'b',
+                PieceType::Rook => 'r',
+                _ => 'q',
+            };
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+//! The line protocol two clients use to play each other over TCP. One side
+//! hosts: it listens for the other, tells it which color it plays and sends
+//! it the game so far. From then on each side sends the moves it plays, and
+//! the receiver checks every move against its own position. The host's game
+//! is the reference; whenever the boards disagree the guest asks for it
+//! again.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::{FenError, Move, PieceColor, Position};
+
+/// The protocol version sent in `hello`. Peers speaking another version are
+/// turned away.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The port a host listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// How long connecting to a host may take before giving up.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Why talking to the other player failed.
+#[derive(Debug)]
+pub enum NetError {
+    /// Listening, connecting or writing failed.
+    Io(io::Error),
+    /// The other side closed the connection.
+    Disconnected,
+    /// A line that is not part of the protocol.
+    Malformed(String),
+    /// A `sync` whose starting position is not valid FEN.
+    InvalidFen(FenError),
+    /// A `sync` listing a move that is not legal where it is played.
+    IllegalMove(String),
+    /// The other side said hello with a different protocol version.
+    Version(u32),
+    /// The other side closed the connection with an `error` message.
+    Refused(String),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(err) => write!(f, "network error: {err}"),
+            NetError::Disconnected => write!(f, "the other player disconnected"),
+            NetError::Malformed(line) => write!(f, "malformed message '{line}'"),
+            NetError::InvalidFen(err) => write!(f, "invalid FEN: {err}"),
+            NetError::IllegalMove(mv) => write!(f, "illegal move '{mv}'"),
+            NetError::Version(version) => write!(
+                f,
+                "the other player speaks protocol version {version}, not {PROTOCOL_VERSION}"
+            ),
+            NetError::Refused(reason) => write!(f, "the other player refused: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+/// One line of the protocol.
+#[derive(Clone, Debug)]
+pub enum PeerMessage {
+    /// The first line from each side: `hello <version>`.
+    Hello {
+        version: u32,
+    },
+    /// The color the guest plays, sent by the host: `color white`.
+    Color(PieceColor),
+    /// A move in UCI long algebraic notation: `move e7e8q`. It stays text
+    /// until checked against a position with [`Position::parse_uci`].
+    Move(String),
+    Resign,
+    /// `abort`: calls the game off, or abandons it once both sides have
+    /// moved.
+    Abort,
+    /// `draw offer`, `draw accept` and `draw decline`.
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    /// `draw claim`: ends the game by threefold repetition or the fifty-move
+    /// rule.
+    ClaimDraw,
+    /// The whole game, sent by the host: `sync <fen> moves <uci>...`, the
+    /// starting position and the moves played from it.
+    Sync {
+        initial: Box<Position>,
+        moves: Vec<Move>,
+    },
+    /// Asks the host for a `sync`: `resync`.
+    RequestSync,
+    /// Sent before closing the connection on purpose: `error <reason>`.
+    Error(String),
+}
+
+impl PeerMessage {
+    pub fn parse(line: &str) -> Result<Self, NetError> {
+        let malformed = || NetError::Malformed(line.to_string());
+        let mut tokens = line.split_whitespace();
+        let message = match tokens.next() {
+            Some("hello") => PeerMessage::Hello {
+                version: tokens
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(malformed)?,
+            },
+            Some("color") => PeerMessage::Color(match tokens.next() {
+                Some("white") => PieceColor::White,
+                Some("black") => PieceColor::Black,
+                _ => return Err(malformed()),
+            }),
+            Some("move") => PeerMessage::Move(tokens.next().ok_or_else(malformed)?.to_string()),
+            Some("resign") => PeerMessage::Resign,
+            Some("abort") => PeerMessage::Abort,
+            Some("draw") => match tokens.next() {
+                Some("offer") => PeerMessage::OfferDraw,
+                Some("accept") => PeerMessage::AcceptDraw,
+                Some("decline") => PeerMessage::DeclineDraw,
+                Some("claim") => PeerMessage::ClaimDraw,
+                _ => return Err(malformed()),
+            },
+            Some("sync") => {
+                let fen: Vec<&str> = tokens.by_ref().take_while(|&t| t != "moves").collect();
+                let initial = Position::from_fen(&fen.join(" ")).map_err(NetError::InvalidFen)?;
+                // `take_while` consumed the `moves` keyword.
+                let mut position = initial.clone();
+                let mut moves = Vec::new();
+                for uci in tokens {
+                    let mv = position
+                        .parse_uci(uci)
+                        .ok_or_else(|| NetError::IllegalMove(uci.to_string()))?;
+                    position.make_move(mv);
+                    moves.push(mv);
+                }
+                return Ok(PeerMessage::Sync {
+                    initial: Box::new(initial),
+                    moves,
+                });
+            }
+            Some("resync") => PeerMessage::RequestSync,
+            Some("error") => PeerMessage::Error(tokens.by_ref().collect::<Vec<_>>().join(" ")),
+            _ => return Err(malformed()),
+        };
+        match tokens.next() {
+            Some(_) => Err(malformed()),
+            None => Ok(message),
+        }
+    }
+}
+
+impl fmt::Display for PeerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerMessage::Hello { version } => write!(f, "hello {version}"),
+            PeerMessage::Color(PieceColor::White) => f.write_str("color white"),
+            PeerMessage::Color(PieceColor::Black) => f.write_str("color black"),
+            PeerMessage::Move(mv) => write!(f, "move {mv}"),
+            PeerMessage::Resign => f.write_str("resign"),
+            PeerMessage::Abort => f.write_str("abort"),
+            PeerMessage::OfferDraw => f.write_str("draw offer"),
+            PeerMessage::AcceptDraw => f.write_str("draw accept"),
+            PeerMessage::DeclineDraw => f.write_str("draw decline"),
+            PeerMessage::ClaimDraw => f.write_str("draw claim"),
+            PeerMessage::Sync { initial, moves } => {
+                write!(f, "sync {} moves", initial.to_fen())?;
+                for mv in moves {
+                    write!(f, " {mv}")?;
+                }
+                Ok(())
+            }
+            PeerMessage::RequestSync => f.write_str("resync"),
+            PeerMessage::Error(reason) => write!(f, "error {reason}"),
+        }
+    }
+}
+
+/// A TCP connection to the other player. Dropping it closes the connection.
+pub struct PeerConnection {
+    stream: TcpStream,
+    lines: Receiver<String>,
+}
+
+impl PeerConnection {
+    /// Connects to a host listening at `address`, such as `192.168.1.20:7878`.
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, NetError> {
+        let mut last_error = None;
+        for address in address.to_socket_addrs().map_err(NetError::Io)? {
+            match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(stream) => return Self::new(stream),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(NetError::Io(last_error.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no address to connect to")
+        })))
+    }
+
+    /// Wraps a connected stream.
+    pub fn new(stream: TcpStream) -> Result<Self, NetError> {
+        stream.set_nonblocking(false).map_err(NetError::Io)?;
+        stream.set_nodelay(true).map_err(NetError::Io)?;
+        let reader = stream.try_clone().map_err(NetError::Io)?;
+
+        // Lines arrive whenever the other player acts; a reader thread turns
+        // them into a channel that can be polled without blocking.
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { stream, lines })
+    }
+
+    /// The address of the other player.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.peer_addr().ok()
+    }
+
+    /// Writes one message line.
+    pub fn send(&mut self, message: &PeerMessage) -> Result<(), NetError> {
+        writeln!(self.stream, "{message}")
+            .and_then(|()| self.stream.flush())
+            .map_err(NetError::Io)
+    }
+
+    /// The next message if one has arrived, without waiting. A line that
+    /// does not parse is returned as an error; the connection stays usable.
+    pub fn try_recv(&mut self) -> Result<Option<PeerMessage>, NetError> {
+        match self.lines.try_recv() {
+            Ok(line) => PeerMessage::parse(&line).map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(NetError::Disconnected),
+        }
+    }
+
+    /// The next message, waiting at most `timeout` for it; `None` if none
+    /// arrived in time.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<PeerMessage>, NetError> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => PeerMessage::parse(&line).map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(NetError::Disconnected),
+        }
+    }
+}
+
+impl Drop for PeerConnection {
+    fn drop(&mut self) {
+        // Also ends the reader thread, which is blocked on the other half.
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// A host waiting for players to connect.
+pub struct PeerListener {
+    listener: TcpListener,
+}
+
+impl PeerListener {
+    /// Listens at `address`, such as `0.0.0.0:7878`; port 0 picks a free one.
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self, NetError> {
+        let listener = TcpListener::bind(address).map_err(NetError::Io)?;
+        listener.set_nonblocking(true).map_err(NetError::Io)?;
+        Ok(Self { listener })
+    }
+
+    /// The address actually listened at.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+
+    /// A player who has connected since the last call, without waiting.
+    pub fn accept(&self) -> Result<Option<PeerConnection>, NetError> {
+        match self.listener.accept() {
+            Ok((stream, _)) => PeerConnection::new(stream).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(NetError::Io(err)),
+        }
+    }
+}
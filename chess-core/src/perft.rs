@@ -0,0 +1,38 @@
+use crate::{Move, Position};
+
+impl Position {
+    /// Counts the leaf nodes of the legal move tree `depth` plies deep.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for mv in moves {
+            self.make_move(mv);
+            nodes += self.perft(depth - 1);
+            self.unmake_move();
+        }
+        nodes
+    }
+
+    /// Perft split by root move, in move generation order. Compare against
+    /// another engine's output to find which move's subtree diverges.
+    pub fn divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        let depth = depth.max(1);
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| {
+                self.make_move(mv);
+                let nodes = self.perft(depth - 1);
+                self.unmake_move();
+                (mv, nodes)
+            })
+            .collect()
+    }
+}
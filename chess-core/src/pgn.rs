@@ -0,0 +1,698 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{FenError, Move, PieceColor, Position, STARTING_FEN, SanError};
+
+/// The Seven Tag Roster, in export order, with the value written when a tag
+/// is missing.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Export keeps movetext lines shorter than this, as the PGN standard asks.
+const MAX_LINE_LENGTH: usize = 80;
+
+/// A single game in Portable Game Notation: tag pairs, the main line with
+/// its annotations and variations, and the result.
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    initial_position: Position,
+    position: Position,
+    moves: Vec<PgnMove>,
+    result: String,
+}
+
+/// A move in PGN movetext together with the annotations attached to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnMove {
+    pub mv: Move,
+    pub san: String,
+    /// Numeric annotation glyphs; `!` and `?` suffixes are stored as NAGs 1–6.
+    pub nags: Vec<u8>,
+    /// A comment written before the move, e.g. at the start of a variation.
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    /// Alternatives to this move, each played from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    fn new(mv: Move, san: String) -> Self {
+        Self {
+            mv,
+            san,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// Why PGN text could not be read, with the 1-based line and column where
+/// the problem starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PgnErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PgnErrorKind {
+    UnexpectedChar(char),
+    UnterminatedString,
+    UnterminatedComment,
+    /// A tag pair is not of the form `[Name "value"]`.
+    MalformedTag,
+    InvalidNag(String),
+    /// A token that cannot appear at this point of the movetext.
+    UnexpectedToken(String),
+    /// A `(` that is not closed before the game ends.
+    UnclosedVariation,
+    /// A `)` without a matching `(`.
+    UnmatchedParen,
+    /// A variation or annotation before any move it could belong to.
+    NothingToAnnotate,
+    InvalidFen(FenError),
+    IllegalMove(SanError),
+    /// The text holds no game at all.
+    NoGame,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl fmt::Display for PgnErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{c}'"),
+            PgnErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            PgnErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            PgnErrorKind::MalformedTag => write!(f, "tag pairs must look like [Name \"value\"]"),
+            PgnErrorKind::InvalidNag(s) => write!(f, "invalid annotation glyph '{s}'"),
+            PgnErrorKind::UnexpectedToken(s) => write!(f, "unexpected '{s}'"),
+            PgnErrorKind::UnclosedVariation => write!(f, "variation is never closed"),
+            PgnErrorKind::UnmatchedParen => write!(f, "')' without a matching '('"),
+            PgnErrorKind::NothingToAnnotate => write!(f, "annotation before the first move"),
+            PgnErrorKind::InvalidFen(err) => write!(f, "invalid FEN tag: {err}"),
+            PgnErrorKind::IllegalMove(err) => write!(f, "{err}"),
+            PgnErrorKind::NoGame => write!(f, "no game found"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl PgnGame {
+    /// An empty game starting from `initial_position`.
+    pub fn new(initial_position: Position) -> Self {
+        Self {
+            tags: Vec::new(),
+            position: initial_position.clone(),
+            initial_position,
+            moves: Vec::new(),
+            result: "*".to_string(),
+        }
+    }
+
+    /// Parses the first game in `pgn`.
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnError> {
+        Self::parse_all(pgn)?.into_iter().next().ok_or(PgnError {
+            line: 1,
+            column: 1,
+            kind: PgnErrorKind::NoGame,
+        })
+    }
+
+    /// Parses every game in a PGN database.
+    pub fn parse_all(pgn: &str) -> Result<Vec<Self>, PgnError> {
+        let mut parser = Parser {
+            tokens: lex(pgn)?,
+            next: 0,
+        };
+        let mut games = Vec::new();
+        while parser.peek().is_some() {
+            games.push(parser.game()?);
+        }
+        Ok(games)
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        if name == "Result" {
+            return Some(&self.result);
+        }
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a tag, replacing an existing value. The `Result` tag follows
+    /// [`PgnGame::set_result`] instead.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        if name == "Result" {
+            self.set_result(value);
+        } else if let Some((_, old)) = self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            *old = value.to_string();
+        } else {
+            self.tags.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    pub fn initial_position(&self) -> &Position {
+        &self.initial_position
+    }
+
+    /// The position after the last move of the main line.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The main line.
+    pub fn moves(&self) -> &[PgnMove] {
+        &self.moves
+    }
+
+    /// The main line, for editing annotations.
+    pub fn moves_mut(&mut self) -> &mut [PgnMove] {
+        &mut self.moves
+    }
+
+    /// Appends `mv`, which must be legal in [`PgnGame::position`], to the
+    /// main line and returns it for annotation.
+    pub fn push(&mut self, mv: Move) -> &mut PgnMove {
+        let san = self.position.san(mv);
+        self.position.make_move(mv);
+        self.moves.push(PgnMove::new(mv, san));
+        self.moves.last_mut().unwrap()
+    }
+
+    /// `1-0`, `0-1`, `1/2-1/2`, or `*` for a game still in progress.
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+
+    /// Sets the result; anything but the four PGN result tokens is stored
+    /// as `*`.
+    pub fn set_result(&mut self, result: &str) {
+        self.result = if RESULTS.contains(&result) {
+            result.to_string()
+        } else {
+            "*".to_string()
+        };
+    }
+
+    /// Exports the game: the Seven Tag Roster first, then any other tags,
+    /// then the movetext wrapped below 80 columns.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+
+        for (name, default) in SEVEN_TAG_ROSTER {
+            push_tag(&mut pgn, name, self.tag(name).unwrap_or(default));
+        }
+        for (name, value) in &self.tags {
+            let is_roster = SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == name);
+            if !is_roster && name != "SetUp" && name != "FEN" {
+                push_tag(&mut pgn, name, value);
+            }
+        }
+        let fen = self.initial_position.to_fen();
+        if fen != STARTING_FEN {
+            push_tag(&mut pgn, "SetUp", "1");
+            push_tag(&mut pgn, "FEN", &fen);
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        movetext_tokens(
+            &self.moves,
+            self.initial_position.fullmove_number(),
+            self.initial_position.side_to_move(),
+            &mut tokens,
+        );
+        tokens.push(self.result.clone());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() >= MAX_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+}
+
+impl FromStr for PgnGame {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, PgnError> {
+        PgnGame::from_pgn(s)
+    }
+}
+
+fn push_tag(pgn: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+}
+
+/// Splits `moves` into whitespace-separated movetext tokens. A Black move
+/// gets its own `12...` number at the start of a line of play and after a
+/// comment or variation interrupts the move pair.
+fn movetext_tokens(
+    moves: &[PgnMove],
+    mut number: u32,
+    mut side: PieceColor,
+    tokens: &mut Vec<String>,
+) {
+    let mut needs_number = true;
+    for pgn_move in moves {
+        if let Some(comment) = &pgn_move.comment_before {
+            tokens.push(format!("{{{comment}}}"));
+            needs_number = true;
+        }
+        match side {
+            PieceColor::White => tokens.push(format!("{number}. {}", pgn_move.san)),
+            PieceColor::Black if needs_number => {
+                tokens.push(format!("{number}... {}", pgn_move.san))
+            }
+            PieceColor::Black => tokens.push(pgn_move.san.clone()),
+        }
+        needs_number = false;
+
+        for nag in &pgn_move.nags {
+            tokens.push(format!("${nag}"));
+        }
+        if let Some(comment) = &pgn_move.comment {
+            tokens.push(format!("{{{comment}}}"));
+            needs_number = true;
+        }
+        for variation in pgn_move.variations.iter().filter(|v| !v.is_empty()) {
+            let start = tokens.len();
+            movetext_tokens(variation, number, side, tokens);
+            tokens[start].insert(0, '(');
+            tokens.last_mut().unwrap().push(')');
+            needs_number = true;
+        }
+
+        if side == PieceColor::Black {
+            number += 1;
+        }
+        side = side.opposite();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Str(String),
+    Symbol(String),
+    Nag(u8),
+    Comment(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::LBracket => "[".to_string(),
+            Token::RBracket => "]".to_string(),
+            Token::LParen => "(".to_string(),
+            Token::RParen => ")".to_string(),
+            Token::Str(s) => format!("\"{s}\""),
+            Token::Symbol(s) => s.clone(),
+            Token::Nag(n) => format!("${n}"),
+            Token::Comment(_) => "{".to_string(),
+        }
+    }
+}
+
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+impl Spanned {
+    fn error(&self, kind: PgnErrorKind) -> PgnError {
+        PgnError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn take_while(&mut self, mut pred: impl FnMut(char) -> bool) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek().filter(|&c| pred(c)) {
+            s.push(c);
+            self.bump();
+        }
+        s
+    }
+}
+
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_+#=:-/".contains(c)
+}
+
+fn lex(text: &str) -> Result<Vec<Spanned>, PgnError> {
+    let mut lexer = Lexer {
+        chars: text.trim_start_matches('\u{feff}').chars().peekable(),
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+
+    while let Some(c) = lexer.peek() {
+        let (line, column) = (lexer.line, lexer.column);
+        let error = |kind| PgnError { line, column, kind };
+
+        let token = match c {
+            _ if c.is_whitespace() || c == '.' => {
+                lexer.bump();
+                continue;
+            }
+            '%' if column == 1 => {
+                lexer.take_while(|c| c != '\n');
+                continue;
+            }
+            ';' => {
+                lexer.bump();
+                Token::Comment(lexer.take_while(|c| c != '\n').trim().to_string())
+            }
+            '{' => {
+                lexer.bump();
+                let comment = lexer.take_while(|c| c != '}');
+                if lexer.bump().is_none() {
+                    return Err(error(PgnErrorKind::UnterminatedComment));
+                }
+                Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            '"' => {
+                lexer.bump();
+                let mut value = String::new();
+                loop {
+                    match lexer.bump() {
+                        Some('"') => break,
+                        Some('\\') => match lexer.bump() {
+                            Some(c @ ('"' | '\\')) => value.push(c),
+                            _ => return Err(error(PgnErrorKind::UnterminatedString)),
+                        },
+                        Some('\n') | None => {
+                            return Err(error(PgnErrorKind::UnterminatedString));
+                        }
+                        Some(c) => value.push(c),
+                    }
+                }
+                Token::Str(value)
+            }
+            '$' => {
+                lexer.bump();
+                let digits = lexer.take_while(|c| c.is_ascii_digit());
+                let nag = digits
+                    .parse()
+                    .map_err(|_| error(PgnErrorKind::InvalidNag(format!("${digits}"))))?;
+                Token::Nag(nag)
+            }
+            '!' | '?' => {
+                let glyph = lexer.take_while(|c| c == '!' || c == '?');
+                let nag = match glyph.as_str() {
+                    "!" => 1,
+                    "?" => 2,
+                    "!!" => 3,
+                    "??" => 4,
+                    "!?" => 5,
+                    "?!" => 6,
+                    _ => return Err(error(PgnErrorKind::InvalidNag(glyph))),
+                };
+                Token::Nag(nag)
+            }
+            '*' => {
+                lexer.bump();
+                Token::Symbol("*".to_string())
+            }
+            '[' | ']' | '(' | ')' => {
+                lexer.bump();
+                match c {
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    '(' => Token::LParen,
+                    _ => Token::RParen,
+                }
+            }
+            _ if c.is_ascii_alphanumeric() => Token::Symbol(lexer.take_while(is_symbol_char)),
+            _ => return Err(error(PgnErrorKind::UnexpectedChar(c))),
+        };
+        tokens.push(Spanned {
+            token,
+            line,
+            column,
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Spanned> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<&Spanned> {
+        let spanned = self.tokens.get(self.next);
+        self.next += 1;
+        spanned
+    }
+
+    fn game(&mut self) -> Result<PgnGame, PgnError> {
+        let mut tags = Vec::new();
+        let mut fen_tag = None;
+        while let Some(spanned) = self.peek()
+            && spanned.token == Token::LBracket
+        {
+            let (line, column) = (spanned.line, spanned.column);
+            let (name, value) = self.tag()?;
+            if name == "FEN" {
+                fen_tag = Some((value.clone(), line, column));
+            }
+            tags.push((name, value));
+        }
+
+        let initial_position = match fen_tag {
+            Some((fen, line, column)) => Position::from_fen(&fen).map_err(|err| PgnError {
+                line,
+                column,
+                kind: PgnErrorKind::InvalidFen(err),
+            })?,
+            None => Position::new(),
+        };
+
+        let mut game = PgnGame::new(initial_position);
+        let (moves, position) = self.line(game.initial_position.clone(), None)?;
+        game.moves = moves;
+        game.position = position;
+
+        let tag_result = tags
+            .iter()
+            .find(|(name, _)| name == "Result")
+            .map(|(_, value)| value.clone());
+        game.tags = tags;
+        match self.peek() {
+            Some(Spanned {
+                token: Token::Symbol(result),
+                ..
+            }) if RESULTS.contains(&result.as_str()) => {
+                let result = result.clone();
+                self.advance();
+                game.set_result(&result);
+            }
+            _ => game.set_result(tag_result.as_deref().unwrap_or("*")),
+        }
+        game.tags.retain(|(name, _)| name != "Result");
+        Ok(game)
+    }
+
+    fn tag(&mut self) -> Result<(String, String), PgnError> {
+        let start = self.advance().map(|t| (t.line, t.column));
+        let malformed = |spanned: Option<&Spanned>| {
+            let (line, column) = spanned.map_or(start.unwrap(), |t| (t.line, t.column));
+            PgnError {
+                line,
+                column,
+                kind: PgnErrorKind::MalformedTag,
+            }
+        };
+
+        let name = match self.advance() {
+            Some(Spanned {
+                token: Token::Symbol(name),
+                ..
+            }) => name.clone(),
+            other => return Err(malformed(other)),
+        };
+        let value = match self.advance() {
+            Some(Spanned {
+                token: Token::Str(value),
+                ..
+            }) => value.clone(),
+            other => return Err(malformed(other)),
+        };
+        match self.advance() {
+            Some(Spanned {
+                token: Token::RBracket,
+                ..
+            }) => Ok((name, value)),
+            other => Err(malformed(other)),
+        }
+    }
+
+    /// Parses moves from `position` until the result, the next game's tags
+    /// or, inside a variation opened at `open`, the closing `)`.
+    fn line(
+        &mut self,
+        mut position: Position,
+        open: Option<(usize, usize)>,
+    ) -> Result<(Vec<PgnMove>, Position), PgnError> {
+        let mut moves: Vec<PgnMove> = Vec::new();
+        let mut before_last: Option<Position> = None;
+        let mut pending_comment: Option<String> = None;
+        let unclosed = |(line, column)| PgnError {
+            line,
+            column,
+            kind: PgnErrorKind::UnclosedVariation,
+        };
+
+        loop {
+            let Some(spanned) = self.peek() else {
+                return match open {
+                    Some(at) => Err(unclosed(at)),
+                    None => Ok((moves, position)),
+                };
+            };
+
+            match &spanned.token {
+                Token::Symbol(s) if RESULTS.contains(&s.as_str()) => {
+                    return match open {
+                        Some(at) => Err(unclosed(at)),
+                        None => Ok((moves, position)),
+                    };
+                }
+                Token::LBracket => {
+                    return match open {
+                        Some(at) => Err(unclosed(at)),
+                        None => Ok((moves, position)),
+                    };
+                }
+                Token::Symbol(s) if s.chars().all(|c| c.is_ascii_digit()) => {}
+                Token::Symbol(san) => {
+                    let mv = position
+                        .parse_san(san)
+                        .map_err(|err| spanned.error(PgnErrorKind::IllegalMove(err)))?;
+                    let mut pgn_move = PgnMove::new(mv, position.san(mv));
+                    pgn_move.comment_before = pending_comment.take();
+                    before_last = Some(position.detached());
+                    position.make_move(mv);
+                    moves.push(pgn_move);
+                }
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(last) => last.nags.push(*nag),
+                    None => return Err(spanned.error(PgnErrorKind::NothingToAnnotate)),
+                },
+                Token::Comment(text) => {
+                    let slot = match moves.last_mut() {
+                        Some(last) => &mut last.comment,
+                        None => &mut pending_comment,
+                    };
+                    match slot {
+                        Some(existing) => {
+                            existing.push(' ');
+                            existing.push_str(text);
+                        }
+                        None => *slot = Some(text.clone()),
+                    }
+                }
+                Token::LParen => {
+                    let Some(before) = before_last.clone() else {
+                        return Err(spanned.error(PgnErrorKind::NothingToAnnotate));
+                    };
+                    let at = (spanned.line, spanned.column);
+                    self.advance();
+                    let (variation, _) = self.line(before, Some(at))?;
+                    moves.last_mut().unwrap().variations.push(variation);
+                    continue;
+                }
+                Token::RParen => {
+                    if open.is_none() {
+                        return Err(spanned.error(PgnErrorKind::UnmatchedParen));
+                    }
+                    self.advance();
+                    return Ok((moves, position));
+                }
+                Token::RBracket | Token::Str(_) => {
+                    return Err(
+                        spanned.error(PgnErrorKind::UnexpectedToken(spanned.token.describe()))
+                    );
+                }
+            }
+            self.advance();
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PieceColor {
+    White,
+    Black,
+}
+
+impl PieceColor {
+    pub fn opposite(self) -> Self {
+        match self {
+            PieceColor::White => PieceColor::Black,
+            PieceColor::Black => PieceColor::White,
+        }
+    }
+
+    /// Rank delta of a single pawn push.
+    pub fn pawn_direction(self) -> i32 {
+        match self {
+            PieceColor::White => 1,
+            PieceColor::Black => -1,
+        }
+    }
+
+    pub fn back_rank(self) -> u8 {
+        match self {
+            PieceColor::White => 0,
+            PieceColor::Black => 7,
+        }
+    }
+}
+
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PieceType {
+    Pawn = 0,
+    Knight = 1,
+    Bishop = 2,
+    Rook = 3,
+    Queen = 4,
+    King = 5,
+}
+
+impl PieceType {
+    pub const ALL: [PieceType; 6] = [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ];
+
+    /// Pieces a pawn may promote to, strongest first.
+    pub const PROMOTIONS: [PieceType; 4] = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Piece {
+    pub color: PieceColor,
+    pub kind: PieceType,
+}
+
+impl Piece {
+    pub fn new(color: PieceColor, kind: PieceType) -> Self {
+        Self { color, kind }
+    }
+}
@@ -0,0 +1,403 @@
+use crate::rules::{attackers_to, is_self_check, pseudo_legal_moves};
+use crate::{
+    Bitboard, Move, MoveKind, Piece, PieceColor, PieceType, Square, pawn_attacks, zobrist,
+};
+
+/// Castling availability as a set of four flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CastlingRights(u8);
+
+impl CastlingRights {
+    pub const NONE: Self = Self(0);
+    pub const WHITE_KINGSIDE: Self = Self(1);
+    pub const WHITE_QUEENSIDE: Self = Self(2);
+    pub const BLACK_KINGSIDE: Self = Self(4);
+    pub const BLACK_QUEENSIDE: Self = Self(8);
+    pub const ALL: Self = Self(15);
+
+    pub fn kingside(color: PieceColor) -> Self {
+        match color {
+            PieceColor::White => Self::WHITE_KINGSIDE,
+            PieceColor::Black => Self::BLACK_KINGSIDE,
+        }
+    }
+
+    pub fn queenside(color: PieceColor) -> Self {
+        match color {
+            PieceColor::White => Self::WHITE_QUEENSIDE,
+            PieceColor::Black => Self::BLACK_QUEENSIDE,
+        }
+    }
+
+    pub fn both(color: PieceColor) -> Self {
+        Self(Self::kingside(color).0 | Self::queenside(color).0)
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    pub(crate) fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Rights lost when a piece moves from or to `square` (king or rook home).
+    fn revoked_by(square: Square) -> Self {
+        match (square.file(), square.rank()) {
+            (4, 0) => Self::both(PieceColor::White),
+            (0, 0) => Self::WHITE_QUEENSIDE,
+            (7, 0) => Self::WHITE_KINGSIDE,
+            (4, 7) => Self::both(PieceColor::Black),
+            (0, 7) => Self::BLACK_QUEENSIDE,
+            (7, 7) => Self::BLACK_KINGSIDE,
+            _ => Self::NONE,
+        }
+    }
+}
+
+/// State needed to take a move back.
+#[derive(Clone, Copy, Debug)]
+struct Undo {
+    mv: Move,
+    captured: Option<Piece>,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    /// Zobrist key of the position before the move, for repetition checks.
+    key: u64,
+}
+
+/// A complete game position: piece placement, side to move, castling rights,
+/// en-passant target and move counters, plus the moves played to reach it so
+/// they can be unmade.
+#[derive(Clone, Debug)]
+pub struct Position {
+    board: [Option<Piece>; 64],
+    by_kind: [Bitboard; 6],
+    by_color: [Bitboard; 2],
+    side_to_move: PieceColor,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    /// Zobrist hash of placement, side to move and castling rights; the
+    /// en-passant part is added by [`Position::zobrist_key`].
+    hash: u64,
+    history: Vec<Undo>,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Position {
+    /// The standard starting position.
+    pub fn new() -> Self {
+        let mut position = Self::empty();
+        position.set_castling_rights(CastlingRights::ALL);
+        let back_rank = [
+            PieceType::Rook,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::King,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Rook,
+        ];
+
+        for (file, kind) in back_rank.into_iter().enumerate() {
+            let file = file as u8;
+            position.put(Square::new(file, 0), Piece::new(PieceColor::White, kind));
+            position.put(
+                Square::new(file, 1),
+                Piece::new(PieceColor::White, PieceType::Pawn),
+            );
+            position.put(
+                Square::new(file, 6),
+                Piece::new(PieceColor::Black, PieceType::Pawn),
+            );
+            position.put(Square::new(file, 7), Piece::new(PieceColor::Black, kind));
+        }
+
+        position
+    }
+
+    /// An empty board with White to move and no castling rights.
+    pub fn empty() -> Self {
+        Self {
+            board: [None; 64],
+            by_kind: [Bitboard::EMPTY; 6],
+            by_color: [Bitboard::EMPTY; 2],
+            side_to_move: PieceColor::White,
+            castling: CastlingRights::NONE,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Places or clears a piece when setting up a position. Unlike
+    /// [`Position::make_move`] this is not recorded and cannot be unmade.
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.take(square);
+        if let Some(piece) = piece {
+            self.put(square, piece);
+        }
+    }
+
+    pub fn set_side_to_move(&mut self, color: PieceColor) {
+        self.hash ^= zobrist::side(self.side_to_move) ^ zobrist::side(color);
+        self.side_to_move = color;
+    }
+
+    pub fn set_castling_rights(&mut self, rights: CastlingRights) {
+        self.hash ^= zobrist::castling(self.castling) ^ zobrist::castling(rights);
+        self.castling = rights;
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<Square>) {
+        self.en_passant = square;
+    }
+
+    pub fn set_halfmove_clock(&mut self, clock: u32) {
+        self.halfmove_clock = clock;
+    }
+
+    pub fn set_fullmove_number(&mut self, number: u32) {
+        self.fullmove_number = number;
+    }
+
+    /// A copy of the current state without the move history, for looking
+    /// ahead when only `&self` is available.
+    pub(crate) fn detached(&self) -> Self {
+        Self {
+            history: Vec::new(),
+            ..*self
+        }
+    }
+
+    fn put(&mut self, square: Square, piece: Piece) {
+        self.board[square.index()] = Some(piece);
+        self.by_kind[piece.kind as usize].insert(square);
+        self.by_color[piece.color as usize].insert(square);
+        self.hash ^= zobrist::piece(piece, square);
+    }
+
+    fn take(&mut self, square: Square) -> Option<Piece> {
+        let piece = self.board[square.index()].take()?;
+        self.by_kind[piece.kind as usize].remove(square);
+        self.by_color[piece.color as usize].remove(square);
+        self.hash ^= zobrist::piece(piece, square);
+        Some(piece)
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.index()]
+    }
+
+    /// Iterates over all occupied squares.
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        Square::all().filter_map(|sq| self.piece_at(sq).map(|piece| (sq, piece)))
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.by_color[0] | self.by_color[1]
+    }
+
+    pub fn by_color(&self, color: PieceColor) -> Bitboard {
+        self.by_color[color as usize]
+    }
+
+    pub fn by_kind(&self, kind: PieceType) -> Bitboard {
+        self.by_kind[kind as usize]
+    }
+
+    pub fn pieces_of(&self, color: PieceColor, kind: PieceType) -> Bitboard {
+        self.by_color(color) & self.by_kind(kind)
+    }
+
+    pub fn side_to_move(&self) -> PieceColor {
+        self.side_to_move
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// Square a pawn skipped over on the previous move, if any.
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// Half-moves since the last capture or pawn move.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn last_move(&self) -> Option<Move> {
+        self.history.last().map(|undo| undo.mv)
+    }
+
+    /// Zobrist key identifying the position for repetition detection and
+    /// transposition tables. The en-passant file only counts when a capture
+    /// there is actually possible, so positions that differ only by an
+    /// unusable en-passant square hash alike.
+    pub fn zobrist_key(&self) -> u64 {
+        let en_passant = self.en_passant.filter(|&sq| {
+            !(pawn_attacks(self.side_to_move.opposite(), sq)
+                & self.pieces_of(self.side_to_move, PieceType::Pawn))
+            .is_empty()
+        });
+        self.hash ^ en_passant.map_or(0, zobrist::en_passant)
+    }
+
+    /// How many times the current position has occurred in the moves played
+    /// through [`Position::make_move`], counting the current occurrence.
+    /// Only positions since the last capture or pawn move can repeat.
+    pub fn repetition_count(&self) -> usize {
+        let key = self.zobrist_key();
+        let reversible = (self.halfmove_clock as usize).min(self.history.len());
+        1 + self.history[self.history.len() - reversible..]
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|undo| undo.key == key)
+            .count()
+    }
+
+    pub fn king_square(&self, color: PieceColor) -> Option<Square> {
+        self.pieces_of(color, PieceType::King).first()
+    }
+
+    pub fn is_square_attacked(&self, square: Square, by: PieceColor) -> bool {
+        !(attackers_to(self, square, self.occupied()) & self.by_color(by)).is_empty()
+    }
+
+    /// Whether the side to move is in check.
+    pub fn in_check(&self) -> bool {
+        self.king_square(self.side_to_move)
+            .is_some_and(|sq| self.is_square_attacked(sq, self.side_to_move.opposite()))
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        pseudo_legal_moves(self, &mut moves);
+        moves.retain(|&mv| !is_self_check(self, mv));
+        moves
+    }
+
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.legal_moves().contains(&mv)
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check() && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check() && self.legal_moves().is_empty()
+    }
+
+    /// Plays `mv`, which must be one of [`Position::legal_moves`].
+    pub fn make_move(&mut self, mv: Move) {
+        let key = self.zobrist_key();
+        let piece = self
+            .take(mv.from)
+            .expect("make_move: no piece on the origin square");
+        let captured = mv.captured_square().and_then(|sq| self.take(sq));
+
+        self.history.push(Undo {
+            mv,
+            captured,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            key,
+        });
+
+        let kind = mv.promotion.unwrap_or(piece.kind);
+        self.put(mv.to, Piece::new(piece.color, kind));
+
+        if let Some((rook_from, rook_to)) = mv.castling_rook()
+            && let Some(rook) = self.take(rook_from)
+        {
+            self.put(rook_to, rook);
+        }
+
+        let mut castling = self.castling;
+        castling.remove(CastlingRights::revoked_by(mv.from));
+        castling.remove(CastlingRights::revoked_by(mv.to));
+        self.set_castling_rights(castling);
+
+        self.en_passant = match mv.kind {
+            MoveKind::DoublePush => mv.from.offset(0, piece.color.pawn_direction()),
+            _ => None,
+        };
+
+        if piece.kind == PieceType::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if self.side_to_move == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+        self.set_side_to_move(self.side_to_move.opposite());
+    }
+
+    /// Takes back the last move played, returning it.
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let undo = self.history.pop()?;
+        let mv = undo.mv;
+
+        self.set_side_to_move(self.side_to_move.opposite());
+        if self.side_to_move == PieceColor::Black {
+            self.fullmove_number -= 1;
+        }
+
+        let moved = self
+            .take(mv.to)
+            .expect("unmake_move: no piece on the destination square");
+        let kind = if mv.promotion.is_some() {
+            PieceType::Pawn
+        } else {
+            moved.kind
+        };
+        self.put(mv.from, Piece::new(moved.color, kind));
+
+        if let Some((rook_from, rook_to)) = mv.castling_rook()
+            && let Some(rook) = self.take(rook_to)
+        {
+            self.put(rook_from, rook);
+        }
+
+        if let (Some(sq), Some(captured)) = (mv.captured_square(), undo.captured) {
+            self.put(sq, captured);
+        }
+
+        self.set_castling_rights(undo.castling);
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+
+        Some(mv)
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use crate::{DrawReason, PieceColor};
+
+/// How a finished game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    /// A flag fell; a draw if the other side could not have mated.
+    Timeout,
+    /// The players agreed to a draw.
+    Agreement,
+    Stalemate,
+    /// Threefold or fivefold repetition.
+    Repetition,
+    /// The fifty- or seventy-five-move rule.
+    FiftyMoveRule,
+    InsufficientMaterial,
+    /// A player left the game after it had properly started.
+    Abandonment,
+}
+
+impl Termination {
+    /// The PGN `Termination` tag for this ending.
+    pub fn pgn_tag(self) -> &'static str {
+        match self {
+            Termination::Timeout => "time forfeit",
+            Termination::Abandonment => "abandoned",
+            _ => "normal",
+        }
+    }
+}
+
+impl From<DrawReason> for Termination {
+    fn from(reason: DrawReason) -> Self {
+        match reason {
+            DrawReason::Stalemate => Termination::Stalemate,
+            DrawReason::InsufficientMaterial => Termination::InsufficientMaterial,
+            DrawReason::TimeoutVsInsufficientMaterial => Termination::Timeout,
+            DrawReason::ThreefoldRepetition | DrawReason::FivefoldRepetition => {
+                Termination::Repetition
+            }
+            DrawReason::FiftyMoveRule | DrawReason::SeventyFiveMoveRule => {
+                Termination::FiftyMoveRule
+            }
+            DrawReason::Agreement => Termination::Agreement,
+        }
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Termination::Checkmate => "checkmate",
+            Termination::Resignation => "resignation",
+            Termination::Timeout => "timeout",
+            Termination::Agreement => "agreement",
+            Termination::Stalemate => "stalemate",
+            Termination::Repetition => "repetition",
+            Termination::FiftyMoveRule => "the fifty-move rule",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::Abandonment => "abandonment",
+        })
+    }
+}
+
+/// The outcome of a finished game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameResult {
+    WhiteWins(Termination),
+    BlackWins(Termination),
+    Draw(Termination),
+}
+
+impl GameResult {
+    /// A win for `winner`.
+    pub fn win(winner: PieceColor, termination: Termination) -> Self {
+        match winner {
+            PieceColor::White => GameResult::WhiteWins(termination),
+            PieceColor::Black => GameResult::BlackWins(termination),
+        }
+    }
+
+    /// The side that won, or `None` for a draw.
+    pub fn winner(self) -> Option<PieceColor> {
+        match self {
+            GameResult::WhiteWins(_) => Some(PieceColor::White),
+            GameResult::BlackWins(_) => Some(PieceColor::Black),
+            GameResult::Draw(_) => None,
+        }
+    }
+
+    pub fn termination(self) -> Termination {
+        match self {
+            GameResult::WhiteWins(termination)
+            | GameResult::BlackWins(termination)
+            | GameResult::Draw(termination) => termination,
+        }
+    }
+
+    /// The result as PGN writes it: `1-0`, `0-1` or `1/2-1/2`.
+    pub fn pgn_result(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins(_) => "1-0",
+            GameResult::BlackWins(_) => "0-1",
+            GameResult::Draw(_) => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResult::WhiteWins(termination) => write!(f, "White wins by {termination}"),
+            GameResult::BlackWins(termination) => write!(f, "Black wins by {termination}"),
+            GameResult::Draw(termination) => write!(f, "Draw by {termination}"),
+        }
+    }
+}
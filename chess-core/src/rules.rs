@@ -0,0 +1,220 @@
+use crate::attacks::{bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks};
+use crate::{
+    Bitboard, CastlingRights, Move, MoveKind, Piece, PieceColor, PieceType, Position, Square,
+};
+
+/// Appends every pseudo-legal move for the side to move. Moves may still
+/// leave the mover's own king in check; castling is already checked for
+/// passing through attacked squares.
+pub(crate) fn pseudo_legal_moves(position: &Position, moves: &mut Vec<Move>) {
+    let color = position.side_to_move();
+    let own = position.by_color(color);
+    let enemies = position.by_color(color.opposite());
+    let occupied = position.occupied();
+
+    for from in position.pieces_of(color, PieceType::Pawn) {
+        get_pawn_moves(moves, from, color, position);
+    }
+
+    for kind in [
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ] {
+        for from in position.pieces_of(color, kind) {
+            let targets = match kind {
+                PieceType::Knight => knight_attacks(from),
+                PieceType::Bishop => bishop_attacks(from, occupied),
+                PieceType::Rook => rook_attacks(from, occupied),
+                PieceType::Queen => bishop_attacks(from, occupied) | rook_attacks(from, occupied),
+                _ => king_attacks(from),
+            };
+            for to in targets & !own {
+                let kind = if enemies.contains(to) {
+                    MoveKind::Capture
+                } else {
+                    MoveKind::Quiet
+                };
+                moves.push(Move::new(from, to, kind));
+            }
+        }
+    }
+
+    if let Some(king) = position.king_square(color) {
+        get_castling_moves(moves, king, color, position);
+    }
+}
+
+fn get_pawn_moves(moves: &mut Vec<Move>, from: Square, color: PieceColor, position: &Position) {
+    let direction = color.pawn_direction();
+    let start_rank = match color {
+        PieceColor::White => 1,
+        PieceColor::Black => 6,
+    };
+    let occupied = position.occupied();
+
+    // 1. Single step forward
+    if let Some(to) = from.offset(0, direction)
+        && !occupied.contains(to)
+    {
+        push_pawn_move(moves, Move::new(from, to, MoveKind::Quiet));
+
+        // 2. Double step forward
+        if from.rank() == start_rank
+            && let Some(to2) = from.offset(0, 2 * direction)
+            && !occupied.contains(to2)
+        {
+            moves.push(Move::new(from, to2, MoveKind::DoublePush));
+        }
+    }
+
+    // 3. Captures
+    let attacks = pawn_attacks(color, from);
+    for to in attacks & position.by_color(color.opposite()) {
+        push_pawn_move(moves, Move::new(from, to, MoveKind::Capture));
+    }
+    if let Some(ep) = position.en_passant()
+        && attacks.contains(ep)
+    {
+        moves.push(Move::new(from, ep, MoveKind::EnPassant));
+    }
+}
+
+/// Pushes a pawn move, expanding it into one move per promotion piece when it
+/// reaches the last rank.
+fn push_pawn_move(moves: &mut Vec<Move>, mv: Move) {
+    if mv.to.rank() == 0 || mv.to.rank() == 7 {
+        for kind in PieceType::PROMOTIONS {
+            moves.push(mv.with_promotion(kind));
+        }
+    } else {
+        moves.push(mv);
+    }
+}
+
+fn get_castling_moves(moves: &mut Vec<Move>, from: Square, color: PieceColor, position: &Position) {
+    let rank = color.back_rank();
+    if from != Square::new(4, rank) {
+        return;
+    }
+
+    let opponent_color = color.opposite();
+    let rights = position.castling_rights();
+
+    // Cannot castle if King is currently in check
+    if position.is_square_attacked(from, opponent_color) {
+        return;
+    }
+
+    // Kingside (rook at x=7)
+    if rights.contains(CastlingRights::kingside(color))
+        && check_castling_path(
+            color,
+            position,
+            rank,
+            7,
+            &[5, 6], // empty squares between king(4) and rook(7)
+            &[5, 6], // squares king passes through/lands on must be safe
+        )
+    {
+        moves.push(Move::new(
+            from,
+            Square::new(6, rank),
+            MoveKind::KingsideCastle,
+        ));
+    }
+
+    // Queenside (rook at x=0)
+    if rights.contains(CastlingRights::queenside(color))
+        && check_castling_path(
+            color,
+            position,
+            rank,
+            0,
+            &[1, 2, 3], // empty squares between king(4) and rook(0)
+            &[2, 3],    // squares king passes through/lands on (4->3->2)
+        )
+    {
+        moves.push(Move::new(
+            from,
+            Square::new(2, rank),
+            MoveKind::QueensideCastle,
+        ));
+    }
+}
+
+fn check_castling_path(
+    color: PieceColor,
+    position: &Position,
+    rank: u8,
+    rook_x: u8,
+    empty_xs: &[u8],
+    safe_xs: &[u8],
+) -> bool {
+    // 1. Check Rook existence
+    if position.piece_at(Square::new(rook_x, rank)) != Some(Piece::new(color, PieceType::Rook)) {
+        return false;
+    }
+
+    // 2. Check empty squares
+    let occupied = position.occupied();
+    if empty_xs
+        .iter()
+        .any(|&x| occupied.contains(Square::new(x, rank)))
+    {
+        return false;
+    }
+
+    // 3. Check safe squares
+    !safe_xs
+        .iter()
+        .any(|&x| position.is_square_attacked(Square::new(x, rank), color.opposite()))
+}
+
+/// All pieces of either color attacking `square`, given an occupancy that may
+/// differ from the position's own (to test hypothetical moves).
+pub(crate) fn attackers_to(position: &Position, square: Square, occupied: Bitboard) -> Bitboard {
+    let diagonal = position.by_kind(PieceType::Bishop) | position.by_kind(PieceType::Queen);
+    let straight = position.by_kind(PieceType::Rook) | position.by_kind(PieceType::Queen);
+
+    (pawn_attacks(PieceColor::White, square)
+        & position.pieces_of(PieceColor::Black, PieceType::Pawn))
+        | (pawn_attacks(PieceColor::Black, square)
+            & position.pieces_of(PieceColor::White, PieceType::Pawn))
+        | (knight_attacks(square) & position.by_kind(PieceType::Knight))
+        | (king_attacks(square) & position.by_kind(PieceType::King))
+        | (bishop_attacks(square, occupied) & diagonal)
+        | (rook_attacks(square, occupied) & straight)
+}
+
+/// Returns whether playing `mv` would leave the mover's king in check.
+pub(crate) fn is_self_check(position: &Position, mv: Move) -> bool {
+    // Castling generation already rejects attacked transit squares.
+    if mv.is_castle() {
+        return false;
+    }
+
+    let Some(piece) = position.piece_at(mv.from) else {
+        return false;
+    };
+
+    let captured = mv
+        .captured_square()
+        .map_or(Bitboard::EMPTY, Bitboard::from_square);
+    let occupied =
+        (position.occupied() & !Bitboard::from(mv.from) & !captured) | Bitboard::from(mv.to);
+
+    let king_square = if piece.kind == PieceType::King {
+        mv.to
+    } else {
+        match position.king_square(piece.color) {
+            Some(sq) => sq,
+            None => return false,
+        }
+    };
+
+    let enemies = position.by_color(piece.color.opposite()) & !captured;
+    !(attackers_to(position, king_square, occupied) & enemies).is_empty()
+}
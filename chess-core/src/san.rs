@@ -0,0 +1,199 @@
+use std::fmt;
+
+use crate::{Move, MoveKind, PieceType, Position, Square};
+
+/// Why a SAN string could not be matched to a legal move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SanError {
+    /// The string is not well-formed SAN.
+    Syntax(String),
+    /// Well-formed, but no legal move matches.
+    Illegal(String),
+    /// More than one legal move matches; a disambiguating file or rank is missing.
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Syntax(san) => write!(f, "'{san}' is not valid SAN"),
+            SanError::Illegal(san) => write!(f, "'{san}' is not a legal move"),
+            SanError::Ambiguous(san) => write!(f, "'{san}' matches more than one legal move"),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl Position {
+    /// Standard Algebraic Notation for `mv`, which must be legal here,
+    /// including the `+` or `#` suffix.
+    pub fn san(&self, mv: Move) -> String {
+        let mut san = self.san_without_suffix(mv);
+
+        let mut after = self.detached();
+        after.make_move(mv);
+        if after.in_check() {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    /// Finds the legal move written as `san`. Check and annotation suffixes
+    /// (`+ # ! ?`) are ignored, as are a missing `x` or `=`.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let syntax = || SanError::Syntax(san.to_string());
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.legal_moves();
+
+        let castle = match text {
+            "O-O" | "0-0" => Some(MoveKind::KingsideCastle),
+            "O-O-O" | "0-0-0" => Some(MoveKind::QueensideCastle),
+            _ => None,
+        };
+        if let Some(kind) = castle {
+            return legal
+                .into_iter()
+                .find(|mv| mv.kind == kind)
+                .ok_or_else(|| SanError::Illegal(san.to_string()));
+        }
+
+        let mut chars: Vec<char> = text.chars().collect();
+        let kind = match chars.first().copied().and_then(piece_from_letter) {
+            Some(kind) => {
+                chars.remove(0);
+                kind
+            }
+            None => PieceType::Pawn,
+        };
+
+        let mut promotion = None;
+        if kind == PieceType::Pawn
+            && let Some(piece) = chars.last().copied().and_then(piece_from_letter)
+        {
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+            promotion = Some(piece);
+        }
+
+        if chars.len() < 2 {
+            return Err(syntax());
+        }
+        let dest: String = chars[chars.len() - 2..].iter().collect();
+        let to = Square::from_algebraic(&dest).ok_or_else(syntax)?;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for &c in &chars[..chars.len() - 2] {
+            match c {
+                'a'..='h' => from_file = Some(c as u8 - b'a'),
+                '1'..='8' => from_rank = Some(c as u8 - b'1'),
+                'x' | ':' | '-' => {}
+                _ => return Err(syntax()),
+            }
+        }
+
+        let mut candidates = legal.into_iter().filter(|mv| {
+            mv.to == to
+                && !mv.is_castle()
+                && mv.promotion == promotion
+                && self.piece_at(mv.from).is_some_and(|p| p.kind == kind)
+                && from_file.is_none_or(|f| mv.from.file() == f)
+                && from_rank.is_none_or(|r| mv.from.rank() == r)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (None, _) => Err(SanError::Illegal(san.to_string())),
+            (Some(_), Some(_)) => Err(SanError::Ambiguous(san.to_string())),
+        }
+    }
+
+    fn san_without_suffix(&self, mv: Move) -> String {
+        match mv.kind {
+            MoveKind::KingsideCastle => return "O-O".to_string(),
+            MoveKind::QueensideCastle => return "O-O-O".to_string(),
+            _ => {}
+        }
+
+        let kind = self
+            .piece_at(mv.from)
+            .map_or(PieceType::Pawn, |piece| piece.kind);
+        let mut san = String::new();
+
+        if kind == PieceType::Pawn {
+            if mv.is_capture() {
+                san.push((b'a' + mv.from.file()) as char);
+            }
+        } else {
+            san.push(piece_letter(kind));
+            san.push_str(&self.disambiguation(mv, kind));
+        }
+
+        if mv.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(piece_letter(promotion));
+        }
+        san
+    }
+
+    /// The from-file, from-rank or full square needed to tell `mv` apart
+    /// from other pieces of the same kind that can reach the same square.
+    fn disambiguation(&self, mv: Move, kind: PieceType) -> String {
+        let rivals: Vec<Square> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && self.piece_at(other.from).is_some_and(|p| p.kind == kind)
+            })
+            .map(|other| other.from)
+            .collect();
+
+        let file = (b'a' + mv.from.file()) as char;
+        let rank = (b'1' + mv.from.rank()) as char;
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|sq| sq.file() != mv.from.file()) {
+            file.to_string()
+        } else if rivals.iter().all(|sq| sq.rank() != mv.from.rank()) {
+            rank.to_string()
+        } else {
+            format!("{file}{rank}")
+        }
+    }
+}
+
+pub(crate) fn piece_letter(kind: PieceType) -> char {
+    match kind {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    }
+}
+
+fn piece_from_letter(c: char) -> Option<PieceType> {
+    match c {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
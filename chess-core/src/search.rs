@@ -0,0 +1,469 @@
+//! Iterative-deepening alpha-beta search with quiescence, a transposition
+//! table, MVV-LVA capture ordering and killer moves.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{Move, PieceType, Position, evaluate, piece_value};
+
+/// Score of delivering mate right now; mate in `n` plies scores
+/// `MATE_SCORE - n`.
+pub const MATE_SCORE: i32 = 30_000;
+const INFINITY: i32 = 32_000;
+const MAX_PLY: usize = 128;
+const MAX_DEPTH: u32 = 64;
+
+/// Nodes searched between checks of the clock and the stop flag.
+const CHECK_INTERVAL: u64 = 2048;
+
+/// When to stop searching. Unset limits do not apply; with none set the
+/// search runs until [`Searcher::stop_flag`] is raised.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub movetime: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> Self {
+        Self {
+            movetime: Some(movetime),
+            ..Self::default()
+        }
+    }
+}
+
+/// Outcome of one completed iteration, and of the search as a whole.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// Centipawns from the side to move's point of view, or a mate score.
+    pub score: i32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    /// Principal variation; its first move is the move to play.
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+
+    /// Full moves until mate: positive when the side to move mates,
+    /// negative when it gets mated, `None` for an ordinary score.
+    pub fn mate_in(&self) -> Option<i32> {
+        let plies = MATE_SCORE - self.score.abs();
+        if plies > MAX_PLY as i32 {
+            return None;
+        }
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    /// The score failed high; the true score is at least this.
+    Lower,
+    /// The score failed low; the true score is at most this.
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TtEntry {
+    key: u64,
+    best_move: Option<Move>,
+    score: i32,
+    depth: i32,
+    bound: Bound,
+}
+
+struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+}
+
+impl TranspositionTable {
+    fn new(megabytes: usize) -> Self {
+        let bytes = megabytes.max(1) * 1024 * 1024;
+        let count = (bytes / size_of::<Option<TtEntry>>() + 1).next_power_of_two() / 2;
+        Self {
+            entries: vec![None; count],
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    fn probe(&self, key: u64) -> Option<TtEntry> {
+        self.entries[self.slot(key)].filter(|entry| entry.key == key)
+    }
+
+    /// Keeps the deeper result when two positions share a slot.
+    fn store(&mut self, entry: TtEntry) {
+        let slot = self.slot(entry.key);
+        let replace = match self.entries[slot] {
+            Some(old) => old.key != entry.key || entry.depth >= old.depth,
+            None => true,
+        };
+        if replace {
+            self.entries[slot] = Some(entry);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+/// Search state that persists between moves: the transposition table and
+/// killer moves. Keep one per game so earlier searches speed up later ones.
+pub struct Searcher {
+    tt: TranspositionTable,
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    stop: Arc<AtomicBool>,
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Searcher {
+    /// A searcher with a 16 MB transposition table.
+    pub fn new() -> Self {
+        Self::with_hash_size(16)
+    }
+
+    /// A searcher whose transposition table takes about `megabytes` MB.
+    pub fn with_hash_size(megabytes: usize) -> Self {
+        Self {
+            tt: TranspositionTable::new(megabytes),
+            killers: [[None; 2]; MAX_PLY],
+            stop: Arc::new(AtomicBool::new(false)),
+            limits: SearchLimits::default(),
+            start: Instant::now(),
+            nodes: 0,
+            stopped: false,
+        }
+    }
+
+    /// Raising this flag from another thread ends the current search, which
+    /// then returns the result of the last completed iteration.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Forgets everything learned, e.g. before a new game.
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.killers = [[None; 2]; MAX_PLY];
+    }
+
+    /// Searches `position` within `limits` and returns the deepest completed
+    /// iteration. The principal variation is empty only when there is no
+    /// legal move.
+    pub fn search(&mut self, position: &Position, limits: SearchLimits) -> SearchInfo {
+        self.search_with(position, limits, |_| {})
+    }
+
+    /// Like [`Searcher::search`], calling `on_iteration` after each completed
+    /// depth.
+    pub fn search_with(
+        &mut self,
+        position: &Position,
+        limits: SearchLimits,
+        mut on_iteration: impl FnMut(&SearchInfo),
+    ) -> SearchInfo {
+        self.stop.store(false, Ordering::Relaxed);
+        self.limits = limits;
+        self.start = Instant::now();
+        self.nodes = 0;
+        self.stopped = false;
+        self.killers = [[None; 2]; MAX_PLY];
+
+        let mut position = position.clone();
+        let mut best = SearchInfo {
+            pv: position.legal_moves().into_iter().take(1).collect(),
+            ..SearchInfo::default()
+        };
+        if best.pv.is_empty() {
+            return best;
+        }
+
+        let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = self.negamax(&mut position, depth as i32, -INFINITY, INFINITY, 0, &mut pv);
+            if self.stopped || pv.is_empty() {
+                break;
+            }
+
+            best = SearchInfo {
+                depth,
+                score,
+                nodes: self.nodes,
+                elapsed: self.start.elapsed(),
+                pv,
+            };
+            on_iteration(&best);
+
+            // A forced mate will not improve, and the next iteration takes
+            // longer than all previous ones together.
+            let found_mate = best.mate_in().is_some();
+            let half_time_used = limits
+                .movetime
+                .is_some_and(|movetime| best.elapsed * 2 > movetime);
+            if found_mate || half_time_used {
+                break;
+            }
+        }
+        best.nodes = self.nodes;
+        best.elapsed = self.start.elapsed();
+        best
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self
+                    .limits
+                    .movetime
+                    .is_some_and(|movetime| self.start.elapsed() >= movetime)
+                || self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+        }
+        self.stopped
+    }
+
+    fn negamax(
+        &mut self,
+        position: &mut Position,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+        ply: usize,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply > 0
+            && (position.halfmove_clock() >= 100
+                || position.repetition_count() >= 2
+                || position.has_insufficient_material())
+        {
+            return 0;
+        }
+
+        let in_check = position.in_check();
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth <= 0 || ply >= MAX_PLY - 1 {
+            return self.quiesce(position, alpha, beta, ply);
+        }
+
+        let key = position.zobrist_key();
+        let entry = self.tt.probe(key);
+        if ply > 0
+            && let Some(entry) = entry
+            && entry.depth >= depth
+        {
+            let score = score_from_tt(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+        self.order_moves(position, &mut moves, entry.and_then(|e| e.best_move), ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        for mv in moves {
+            position.make_move(mv);
+            let score = -self.negamax(position, depth - 1, -beta, -alpha, ply + 1, &mut child_pv);
+            position.unmake_move();
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+            if alpha >= beta {
+                if !mv.is_capture() && mv.promotion.is_none() {
+                    self.store_killer(mv, ply);
+                }
+                break;
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(TtEntry {
+            key,
+            best_move,
+            score: score_to_tt(best_score, ply),
+            depth,
+            bound,
+        });
+        best_score
+    }
+
+    /// Resolves captures and queen promotions until the position is quiet,
+    /// so the static evaluation is never taken in the middle of an exchange.
+    /// In check, every evasion is searched instead.
+    fn quiesce(&mut self, position: &mut Position, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+        if ply >= MAX_PLY - 1 {
+            return evaluate(position);
+        }
+
+        let in_check = position.in_check();
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        let mut best_score = -INFINITY;
+        if !in_check {
+            best_score = evaluate(position);
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+            moves.retain(|mv| mv.is_capture() || mv.promotion == Some(PieceType::Queen));
+        }
+        self.order_moves(position, &mut moves, None, ply);
+
+        for mv in moves {
+            position.make_move(mv);
+            let score = -self.quiesce(position, -beta, -alpha, ply + 1);
+            position.unmake_move();
+            if self.stopped {
+                return 0;
+            }
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+
+    /// Hash move first, then captures by most valuable victim and least
+    /// valuable attacker, promotions, killer moves, and quiet moves last.
+    fn order_moves(
+        &self,
+        position: &Position,
+        moves: &mut [Move],
+        hash_move: Option<Move>,
+        ply: usize,
+    ) {
+        let killers = self.killers[ply];
+        moves.sort_by_cached_key(|&mv| {
+            let score = if Some(mv) == hash_move {
+                1_000_000
+            } else if mv.is_capture() {
+                let victim = mv
+                    .captured_square()
+                    .and_then(|sq| position.piece_at(sq))
+                    .map_or(0, |piece| piece_value(piece.kind));
+                let attacker = position
+                    .piece_at(mv.from)
+                    .map_or(0, |piece| piece_value(piece.kind));
+                100_000 + victim * 10 - attacker
+            } else if let Some(kind) = mv.promotion {
+                90_000 + piece_value(kind)
+            } else if killers[0] == Some(mv) {
+                80_000
+            } else if killers[1] == Some(mv) {
+                79_000
+            } else {
+                0
+            };
+            -score
+        });
+    }
+
+    fn store_killer(&mut self, mv: Move, ply: usize) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+}
+
+/// Mate scores are stored relative to the node rather than the root, so a
+/// table hit at another ply still reports the right distance to mate.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+
+/// A board square, indexed `rank * 8 + file` with a1 = 0 and h8 = 63.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    pub fn new(file: u8, rank: u8) -> Self {
+        debug_assert!(file < 8 && rank < 8, "square out of range");
+        Self(rank * 8 + file)
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        debug_assert!(index < 64, "square index out of range");
+        Self(index as u8)
+    }
+
+    /// Returns the square at signed coordinates, or `None` when off the board.
+    pub fn from_coords(file: i32, rank: i32) -> Option<Self> {
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Some(Self::new(file as u8, rank as u8))
+        } else {
+            None
+        }
+    }
+
+    /// Parses a square in algebraic notation such as `"e4"`.
+    pub fn from_algebraic(s: &str) -> Option<Self> {
+        let mut chars = s.chars();
+        let file = chars.next()?;
+        let rank = chars.next()?;
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+            return None;
+        }
+        Some(Self::new(file as u8 - b'a', rank as u8 - b'1'))
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    pub fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    pub fn offset(self, dx: i32, dy: i32) -> Option<Self> {
+        Self::from_coords(self.file() as i32 + dx, self.rank() as i32 + dy)
+    }
+
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = (b'a' + self.file()) as char;
+        let rank = self.rank() + 1;
+        write!(f, "{file}{rank}")
+    }
+}
@@ -0,0 +1,354 @@
+//! The Universal Chess Interface. The client side builds the commands sent
+//! to an engine and parses its replies; the engine side parses what a GUI
+//! sends to `src/bin/uci.rs`.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::{FenError, Move, PieceColor, Position, STARTING_FEN, SearchInfo, SearchLimits};
+
+/// A line sent by the engine. Moves stay in UCI notation until matched
+/// against a position with [`Position::parse_uci`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EngineMessage {
+    IdName(String),
+    IdAuthor(String),
+    UciOk,
+    ReadyOk,
+    /// The search result; `mv` is `None` when the engine has no move
+    /// (`bestmove (none)`), as in a mated or stalemated position.
+    BestMove {
+        mv: Option<String>,
+        ponder: Option<String>,
+    },
+    Info(UciInfo),
+    /// Anything else: `option` declarations, copyright banners and the like.
+    Other(String),
+}
+
+impl EngineMessage {
+    pub fn parse(line: &str) -> Self {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("id") => {
+                let field = tokens.next();
+                let value = tokens.collect::<Vec<_>>().join(" ");
+                match field {
+                    Some("name") => EngineMessage::IdName(value),
+                    Some("author") => EngineMessage::IdAuthor(value),
+                    _ => EngineMessage::Other(line.to_string()),
+                }
+            }
+            Some("uciok") => EngineMessage::UciOk,
+            Some("readyok") => EngineMessage::ReadyOk,
+            Some("bestmove") => {
+                let mv = tokens.next().filter(|&mv| mv != "(none)" && mv != "0000");
+                let ponder = match tokens.next() {
+                    Some("ponder") => tokens.next(),
+                    _ => None,
+                };
+                EngineMessage::BestMove {
+                    mv: mv.map(str::to_string),
+                    ponder: ponder.map(str::to_string),
+                }
+            }
+            Some("info") => EngineMessage::Info(UciInfo::parse(tokens)),
+            _ => EngineMessage::Other(line.to_string()),
+        }
+    }
+}
+
+/// The fields of an `info` line that the engine filled in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UciInfo {
+    pub depth: Option<u32>,
+    pub score: Option<UciScore>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    pub pv: Vec<String>,
+    /// Free text from `info string`.
+    pub string: Option<String>,
+}
+
+/// An engine's evaluation, from the side to move's point of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UciScore {
+    Centipawns(i32),
+    /// Moves (not plies) until mate; negative when the engine is getting mated.
+    Mate(i32),
+}
+
+impl fmt::Display for UciScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            UciScore::Centipawns(cp) => write!(f, "{:+.2}", cp as f64 / 100.0),
+            UciScore::Mate(moves) if moves < 0 => write!(f, "-M{}", -moves),
+            UciScore::Mate(moves) => write!(f, "M{moves}"),
+        }
+    }
+}
+
+impl UciInfo {
+    fn parse<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Self {
+        let mut info = UciInfo::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = tokens.next().and_then(|n| n.parse().ok()),
+                "nodes" => info.nodes = tokens.next().and_then(|n| n.parse().ok()),
+                "time" => {
+                    info.time = tokens
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .map(Duration::from_millis);
+                }
+                "score" => {
+                    let kind = tokens.next();
+                    let value = tokens.next().and_then(|n| n.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(cp)) => Some(UciScore::Centipawns(cp)),
+                        (Some("mate"), Some(moves)) => Some(UciScore::Mate(moves)),
+                        _ => None,
+                    };
+                }
+                // The principal variation and the string run to the end of the line.
+                "pv" => info.pv = tokens.by_ref().map(str::to_string).collect(),
+                "string" => info.string = Some(tokens.by_ref().collect::<Vec<_>>().join(" ")),
+                _ => {}
+            }
+        }
+        info
+    }
+}
+
+impl Position {
+    /// Finds the legal move written in UCI long algebraic notation, such as
+    /// `e2e4`, `e1g1` or `e7e8q`.
+    pub fn parse_uci(&self, uci: &str) -> Option<Move> {
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+    }
+}
+
+/// The `position` command for the game that started at `initial` and has
+/// seen `moves` since.
+pub fn position_command(initial: &Position, moves: &[Move]) -> String {
+    let fen = initial.to_fen();
+    let mut command = if fen == STARTING_FEN {
+        "position startpos".to_string()
+    } else {
+        format!("position fen {fen}")
+    };
+    if !moves.is_empty() {
+        command.push_str(" moves");
+        for mv in moves {
+            command.push_str(&format!(" {mv}"));
+        }
+    }
+    command
+}
+
+/// The `go` command for `limits`; with no limit set the engine searches
+/// until told to stop.
+pub fn go_command(limits: SearchLimits) -> String {
+    let mut command = "go".to_string();
+    if let Some(depth) = limits.depth {
+        command.push_str(&format!(" depth {depth}"));
+    }
+    if let Some(movetime) = limits.movetime {
+        command.push_str(&format!(" movetime {}", movetime.as_millis()));
+    }
+    if let Some(nodes) = limits.nodes {
+        command.push_str(&format!(" nodes {nodes}"));
+    }
+    if command == "go" {
+        command.push_str(" infinite");
+    }
+    command
+}
+
+/// A command sent by the GUI to an engine; the engine side of the protocol.
+#[derive(Clone, Debug)]
+pub enum GuiCommand {
+    Uci,
+    IsReady,
+    UciNewGame,
+    /// `position` with its moves already played on the position.
+    Position(Box<Position>),
+    Go(GoParams),
+    Stop,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    Quit,
+}
+
+/// Why a GUI command was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GuiCommandError {
+    Unknown(String),
+    InvalidFen(FenError),
+    IllegalMove(String),
+    Malformed(String),
+}
+
+impl fmt::Display for GuiCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuiCommandError::Unknown(line) => write!(f, "unknown command '{line}'"),
+            GuiCommandError::InvalidFen(err) => write!(f, "invalid FEN: {err}"),
+            GuiCommandError::IllegalMove(mv) => write!(f, "illegal move '{mv}'"),
+            GuiCommandError::Malformed(line) => write!(f, "malformed command '{line}'"),
+        }
+    }
+}
+
+impl std::error::Error for GuiCommandError {}
+
+impl GuiCommand {
+    pub fn parse(line: &str) -> Result<Self, GuiCommandError> {
+        let malformed = || GuiCommandError::Malformed(line.to_string());
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => Ok(GuiCommand::Uci),
+            Some("isready") => Ok(GuiCommand::IsReady),
+            Some("ucinewgame") => Ok(GuiCommand::UciNewGame),
+            Some("stop") => Ok(GuiCommand::Stop),
+            Some("quit") => Ok(GuiCommand::Quit),
+            Some("go") => GoParams::parse(tokens)
+                .map(GuiCommand::Go)
+                .ok_or_else(malformed),
+            Some("position") => {
+                let mut position = match tokens.next() {
+                    Some("startpos") => Position::new(),
+                    Some("fen") => {
+                        let fen: Vec<&str> =
+                            tokens.by_ref().take_while(|&t| t != "moves").collect();
+                        let position = Position::from_fen(&fen.join(" "))
+                            .map_err(GuiCommandError::InvalidFen)?;
+                        // `take_while` consumed the `moves` keyword.
+                        return play_moves(position, tokens)
+                            .map(|p| GuiCommand::Position(Box::new(p)));
+                    }
+                    _ => return Err(malformed()),
+                };
+                match tokens.next() {
+                    Some("moves") => position = play_moves(position, tokens)?,
+                    Some(_) => return Err(malformed()),
+                    None => {}
+                }
+                Ok(GuiCommand::Position(Box::new(position)))
+            }
+            Some("setoption") => {
+                // Option names and values may contain spaces.
+                let rest: Vec<&str> = tokens.collect();
+                if rest.first() != Some(&"name") {
+                    return Err(malformed());
+                }
+                let value_at = rest.iter().position(|&t| t == "value");
+                let name = rest[1..value_at.unwrap_or(rest.len())].join(" ");
+                let value = value_at.map(|i| rest[i + 1..].join(" "));
+                Ok(GuiCommand::SetOption { name, value })
+            }
+            _ => Err(GuiCommandError::Unknown(line.to_string())),
+        }
+    }
+}
+
+fn play_moves<'a>(
+    mut position: Position,
+    moves: impl Iterator<Item = &'a str>,
+) -> Result<Position, GuiCommandError> {
+    for uci in moves {
+        let mv = position
+            .parse_uci(uci)
+            .ok_or_else(|| GuiCommandError::IllegalMove(uci.to_string()))?;
+        position.make_move(mv);
+    }
+    Ok(position)
+}
+
+/// Reserved on every move for the GUI and the pipe, so that a search given
+/// the whole remaining time does not lose on time.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// Moves the remaining time is spread over when the GUI gives no `movestogo`.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// The parameters of a `go` command.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GoParams {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    fn parse<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let mut params = GoParams::default();
+        while let Some(token) = tokens.next() {
+            let mut millis = || tokens.next()?.parse().ok().map(Duration::from_millis);
+            match token {
+                "wtime" => params.wtime = Some(millis()?),
+                "btime" => params.btime = Some(millis()?),
+                "winc" => params.winc = Some(millis()?),
+                "binc" => params.binc = Some(millis()?),
+                "movetime" => params.movetime = Some(millis()?),
+                "depth" => params.depth = Some(tokens.next()?.parse().ok()?),
+                "nodes" => params.nodes = Some(tokens.next()?.parse().ok()?),
+                "movestogo" => params.movestogo = Some(tokens.next()?.parse().ok()?),
+                "infinite" => params.infinite = true,
+                // `ponder`, `searchmoves` and `mate` are not supported.
+                _ => {}
+            }
+        }
+        Some(params)
+    }
+
+    /// The limits for a search by `side`. A fixed `movetime` wins over the
+    /// clock; otherwise the remaining time is split evenly over the moves to
+    /// the next time control, plus most of the increment.
+    pub fn limits(&self, side: PieceColor) -> SearchLimits {
+        let (time, increment) = match side {
+            PieceColor::White => (self.wtime, self.winc),
+            PieceColor::Black => (self.btime, self.binc),
+        };
+        let movetime = self.movetime.or_else(|| {
+            let time = time?;
+            let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let budget = time / moves_to_go + increment.unwrap_or_default() * 3 / 4;
+            let safe = time.saturating_sub(MOVE_OVERHEAD).max(time / 2);
+            Some(budget.min(safe))
+        });
+        SearchLimits {
+            depth: self.depth,
+            movetime: if self.infinite { None } else { movetime },
+            nodes: self.nodes,
+        }
+    }
+}
+
+/// The `info` line reporting a completed search iteration.
+pub fn info_line(info: &SearchInfo) -> String {
+    let score = match info.mate_in() {
+        Some(moves) => format!("mate {moves}"),
+        None => format!("cp {}", info.score),
+    };
+    let millis = info.elapsed.as_millis() as u64;
+    let nps = info.nodes * 1000 / millis.max(1);
+    let mut line = format!(
+        "info depth {} score {score} nodes {} nps {nps} time {millis} pv",
+        info.depth, info.nodes
+    );
+    for mv in &info.pv {
+        line.push_str(&format!(" {mv}"));
+    }
+    line
+}
@@ -0,0 +1,63 @@
+//! Zobrist keys: one random number per (piece, square), side to move,
+//! castling-rights combination and en-passant file, XORed together into a
+//! position key that is updated incrementally as moves are made.
+
+use std::sync::OnceLock;
+
+use crate::attacks::XorShift;
+use crate::{CastlingRights, Piece, PieceColor, Square};
+
+struct Keys {
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    castling: [u64; 16],
+    en_passant: [u64; 8],
+}
+
+fn keys() -> &'static Keys {
+    static KEYS: OnceLock<Keys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        let mut keys = Keys {
+            pieces: [[0; 64]; 12],
+            black_to_move: rng.next(),
+            castling: [0; 16],
+            en_passant: [0; 8],
+        };
+        for key in keys.pieces.iter_mut().flatten() {
+            *key = rng.next();
+        }
+        // Individual rights get their own keys; combinations XOR them, so
+        // losing one right changes the key by the same amount in any state.
+        let rights: [u64; 4] = std::array::from_fn(|_| rng.next());
+        for (bits, key) in keys.castling.iter_mut().enumerate() {
+            *key = (0..4)
+                .filter(|i| bits & (1 << i) != 0)
+                .fold(0, |acc, i| acc ^ rights[i]);
+        }
+        for key in &mut keys.en_passant {
+            *key = rng.next();
+        }
+        keys
+    })
+}
+
+pub(crate) fn piece(piece: Piece, square: Square) -> u64 {
+    let index = piece.color as usize * 6 + piece.kind as usize;
+    keys().pieces[index][square.index()]
+}
+
+pub(crate) fn side(color: PieceColor) -> u64 {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => keys().black_to_move,
+    }
+}
+
+pub(crate) fn castling(rights: CastlingRights) -> u64 {
+    keys().castling[rights.bits() as usize]
+}
+
+pub(crate) fn en_passant(square: Square) -> u64 {
+    keys().en_passant[square.file() as usize]
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use chess_core::{ChessClock, PieceColor, Position, TimeBonus, TimeControl};
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn parses_and_displays_time_controls() {
+    for (text, control) in [
+        ("5", TimeControl::new(secs(300), TimeBonus::None)),
+        ("5+3", TimeControl::fischer(5, 3)),
+        (
+            "15d5",
+            TimeControl::new(secs(900), TimeBonus::Delay(secs(5))),
+        ),
+        (
+            "25b10",
+            TimeControl::new(secs(1500), TimeBonus::Bronstein(secs(10))),
+        ),
+        (
+            "90s+1",
+            TimeControl::new(secs(90), TimeBonus::Increment(secs(1))),
+        ),
+    ] {
+        assert_eq!(text.parse::<TimeControl>(), Ok(control));
+        assert_eq!(control.to_string(), text);
+    }
+
+    for bad in ["", "0", "5+", "five", "5x3", "5+3+2"] {
+        assert!(bad.parse::<TimeControl>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn fischer_increment_is_added_after_each_move() {
+    let mut clock = ChessClock::new(TimeControl::fischer(1, 2));
+    clock.start(PieceColor::White);
+    clock.tick(secs(5));
+    assert_eq!(clock.remaining(PieceColor::White), secs(55));
+
+    clock.press();
+    assert_eq!(clock.running(), Some(PieceColor::Black));
+    assert_eq!(clock.remaining(PieceColor::White), secs(57));
+
+    clock.tick(secs(1));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::Black), secs(61));
+}
+
+#[test]
+fn bronstein_delay_returns_time_used_up_to_the_delay() {
+    let control = TimeControl::new(secs(60), TimeBonus::Bronstein(secs(3)));
+    let mut clock = ChessClock::new(control);
+    clock.start(PieceColor::White);
+    clock.tick(secs(2));
+    assert_eq!(clock.remaining(PieceColor::White), secs(58));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::White), secs(60));
+
+    clock.tick(secs(10));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::Black), secs(53));
+}
+
+#[test]
+fn simple_delay_holds_the_clock_at_the_start_of_each_turn() {
+    let control = TimeControl::new(secs(60), TimeBonus::Delay(secs(5)));
+    let mut clock = ChessClock::new(control);
+    clock.start(PieceColor::White);
+    clock.tick(secs(4));
+    assert_eq!(clock.remaining(PieceColor::White), secs(60));
+    clock.tick(secs(3));
+    assert_eq!(clock.remaining(PieceColor::White), secs(58));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::White), secs(58));
+
+    clock.tick(secs(2));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::Black), secs(60));
+}
+
+#[test]
+fn flag_falls_at_zero_and_stops_the_clock() {
+    let mut clock = ChessClock::new("10s".parse().unwrap());
+    assert_eq!(clock.tick(secs(60)), None, "the clock has not started");
+
+    clock.start(PieceColor::Black);
+    assert_eq!(clock.tick(secs(9)), None);
+    assert_eq!(clock.tick(secs(2)), Some(PieceColor::Black));
+    assert_eq!(clock.remaining(PieceColor::Black), Duration::ZERO);
+    assert_eq!(clock.running(), None);
+
+    clock.start(PieceColor::White);
+    assert_eq!(clock.running(), None);
+}
+
+#[test]
+fn pause_and_restart_keep_the_time_used() {
+    let mut clock = ChessClock::new(TimeControl::fischer(1, 10));
+    clock.start(PieceColor::White);
+    clock.tick(secs(20));
+    clock.pause();
+    clock.tick(secs(20));
+    assert_eq!(clock.remaining(PieceColor::White), secs(40));
+
+    // A take-back switches sides without an increment.
+    clock.start(PieceColor::White);
+    clock.tick(secs(5));
+    clock.start(PieceColor::Black);
+    assert_eq!(clock.remaining(PieceColor::White), secs(35));
+}
+
+#[test]
+fn remaining_time_can_be_set() {
+    let mut clock = ChessClock::new(TimeControl::fischer(1, 0));
+    clock.start(PieceColor::White);
+    clock.tick(secs(20));
+    clock.set_remaining(PieceColor::White, secs(30));
+    clock.set_remaining(PieceColor::Black, secs(50));
+    assert_eq!(clock.remaining(PieceColor::White), secs(30));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(50));
+
+    // The running side counts down from the new time.
+    clock.tick(secs(5));
+    assert_eq!(clock.remaining(PieceColor::White), secs(25));
+}
+
+#[test]
+fn mating_material_decides_time_forfeits() {
+    let has = |fen: &str, color| Position::from_fen(fen).unwrap().has_mating_material(color);
+
+    // Lone king.
+    assert!(!has("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", PieceColor::Black));
+    // A single minor piece against a bare king.
+    assert!(!has("4k3/8/8/8/8/8/8/2N1K3 w - - 0 1", PieceColor::White));
+    assert!(!has("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", PieceColor::White));
+    // Bishops on one square color against a bare king; on both, they can.
+    assert!(!has("4k3/8/8/8/8/8/1B6/2B1K3 w - - 0 1", PieceColor::White));
+    assert!(has("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", PieceColor::White));
+    // With the defender's pieces to block, a knight can mate.
+    assert!(has("4k3/4p3/8/8/8/8/8/2N1K3 w - - 0 1", PieceColor::White));
+    assert!(has("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1", PieceColor::White));
+    assert!(has("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", PieceColor::White));
+    assert!(has("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", PieceColor::White));
+}
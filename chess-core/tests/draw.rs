@@ -0,0 +1,145 @@
+use chess_core::{DrawReason, Position};
+
+fn play(position: &mut Position, moves: &str) {
+    for uci in moves.split_whitespace() {
+        let mv = position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+            .unwrap_or_else(|| panic!("{uci} is not legal"));
+        position.make_move(mv);
+    }
+}
+
+#[test]
+fn incremental_keys_match_fresh_positions() {
+    let mut position =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let start_key = position.zobrist_key();
+
+    play(&mut position, "e1g1 h3g2 a2a4 b4a3 f3f6 e8c8 e5f7 g2f1q");
+    let fresh = Position::from_fen(&position.to_fen()).unwrap();
+    assert_eq!(position.zobrist_key(), fresh.zobrist_key());
+
+    while position.unmake_move().is_some() {}
+    assert_eq!(position.zobrist_key(), start_key);
+}
+
+#[test]
+fn transpositions_share_a_key() {
+    let mut a = Position::new();
+    let mut b = Position::new();
+    play(&mut a, "g1f3 g8f6 b1c3");
+    play(&mut b, "b1c3 g8f6 g1f3");
+    assert_eq!(a.zobrist_key(), b.zobrist_key());
+
+    let mut c = Position::new();
+    play(&mut c, "g1f3 g8f6");
+    assert_ne!(a.zobrist_key(), c.zobrist_key());
+}
+
+#[test]
+fn en_passant_counts_only_when_capturable() {
+    let mut position = Position::new();
+    play(&mut position, "e2e4");
+    let without_target =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(position.zobrist_key(), without_target.zobrist_key());
+
+    let capturable = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    let not_capturable = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+    assert_ne!(capturable.zobrist_key(), not_capturable.zobrist_key());
+}
+
+#[test]
+fn threefold_and_fivefold_repetition() {
+    let shuffle = "g1f3 g8f6 f3g1 f6g8";
+    let mut position = Position::new();
+    assert_eq!(position.repetition_count(), 1);
+
+    play(&mut position, shuffle);
+    assert_eq!(position.repetition_count(), 2);
+    assert_eq!(position.claimable_draw(), None);
+
+    play(&mut position, shuffle);
+    assert_eq!(position.repetition_count(), 3);
+    assert_eq!(
+        position.claimable_draw(),
+        Some(DrawReason::ThreefoldRepetition)
+    );
+    assert_eq!(position.automatic_draw(), None);
+
+    play(&mut position, shuffle);
+    play(&mut position, shuffle);
+    assert_eq!(position.repetition_count(), 5);
+    assert_eq!(
+        position.automatic_draw(),
+        Some(DrawReason::FivefoldRepetition)
+    );
+}
+
+#[test]
+fn lost_castling_rights_break_repetition() {
+    let mut position = Position::from_fen("r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1").unwrap();
+    play(&mut position, "e1f1 e8f8 f1e1 f8e8");
+    assert_eq!(position.repetition_count(), 1);
+
+    play(&mut position, "e1f1 e8f8 f1e1 f8e8");
+    assert_eq!(position.repetition_count(), 2);
+}
+
+#[test]
+fn fifty_and_seventy_five_move_rules() {
+    let mut position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+    assert_eq!(position.claimable_draw(), None);
+    play(&mut position, "a1a2");
+    assert_eq!(position.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+    assert_eq!(position.automatic_draw(), None);
+
+    let mut position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 80").unwrap();
+    play(&mut position, "a1a2");
+    assert_eq!(
+        position.automatic_draw(),
+        Some(DrawReason::SeventyFiveMoveRule)
+    );
+
+    let mut position = Position::from_fen("4k3/8/8/8/8/8/P7/R3K3 w - - 99 80").unwrap();
+    play(&mut position, "a2a3");
+    assert_eq!(position.claimable_draw(), None);
+}
+
+#[test]
+fn insufficient_material() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+        "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/4B3/8/2B1K3 w - - 0 1",
+        "2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+    ] {
+        let position = Position::from_fen(fen).unwrap();
+        assert!(position.has_insufficient_material(), "{fen}");
+        assert_eq!(
+            position.automatic_draw(),
+            Some(DrawReason::InsufficientMaterial)
+        );
+    }
+
+    for fen in [
+        "4k3/8/8/8/8/8/8/4KBB1 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+        "4kn2/8/8/8/8/8/8/4KB2 w - - 0 1",
+        "4k3/8/8/8/8/8/P7/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K2R w - - 0 1",
+    ] {
+        let position = Position::from_fen(fen).unwrap();
+        assert!(!position.has_insufficient_material(), "{fen}");
+    }
+}
+
+#[test]
+fn stalemate_is_an_automatic_draw() {
+    let position = Position::from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(position.automatic_draw(), Some(DrawReason::Stalemate));
+}
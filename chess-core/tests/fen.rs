@@ -0,0 +1,185 @@
+use chess_core::{
+    CastlingRights, FenError, Move, MoveKind, Piece, PieceColor, PieceType, Position, STARTING_FEN,
+    Square,
+};
+
+fn sq(s: &str) -> Square {
+    Square::from_algebraic(s).unwrap()
+}
+
+fn play(position: &mut Position, uci: &str) {
+    let mv: Move = position
+        .legal_moves()
+        .into_iter()
+        .find(|mv| mv.to_string() == uci)
+        .unwrap_or_else(|| panic!("{uci} is not legal"));
+    position.make_move(mv);
+}
+
+#[test]
+fn starting_position_round_trips() {
+    let position = Position::from_fen(STARTING_FEN).unwrap();
+    assert_eq!(position.to_fen(), STARTING_FEN);
+    assert_eq!(Position::new().to_fen(), STARTING_FEN);
+}
+
+#[test]
+fn round_trips_all_fields() {
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 17 42",
+        "r3k3/8/8/8/8/8/8/4K2R b Kq - 3 20",
+    ] {
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn parses_fields() {
+    let position =
+        Position::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 4 12").unwrap();
+
+    assert_eq!(position.side_to_move(), PieceColor::White);
+    assert_eq!(
+        position.piece_at(sq("e5")),
+        Some(Piece::new(PieceColor::White, PieceType::Pawn))
+    );
+    assert!(
+        position
+            .castling_rights()
+            .contains(CastlingRights::WHITE_KINGSIDE)
+    );
+    assert!(
+        !position
+            .castling_rights()
+            .contains(CastlingRights::WHITE_QUEENSIDE)
+    );
+    assert!(
+        position
+            .castling_rights()
+            .contains(CastlingRights::BLACK_QUEENSIDE)
+    );
+    assert_eq!(position.en_passant(), Some(sq("f6")));
+    assert_eq!(position.halfmove_clock(), 4);
+    assert_eq!(position.fullmove_number(), 12);
+    assert!(
+        position
+            .legal_moves()
+            .contains(&Move::new(sq("e5"), sq("f6"), MoveKind::EnPassant))
+    );
+}
+
+#[test]
+fn counters_default_when_omitted() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn export_tracks_moves() {
+    let mut position = Position::new();
+    play(&mut position, "e2e4");
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+    play(&mut position, "g8f6");
+    play(&mut position, "e1e2");
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 2 2"
+    );
+}
+
+#[test]
+fn rejects_malformed_fields() {
+    let cases = [
+        ("8/8/8/8/8/8/8/8 w", FenError::FieldCount(2)),
+        ("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::RankCount(7)),
+        (
+            "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+            FenError::RankLength {
+                rank: 1,
+                squares: 9,
+            },
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+            FenError::RankLength {
+                rank: 1,
+                squares: 7,
+            },
+        ),
+        ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", FenError::InvalidPiece('X')),
+        (
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            FenError::InvalidSideToMove("x".into()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w KX - 0 1",
+            FenError::InvalidCastling("KX".into()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+            FenError::InconsistentCastling('K'),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - e4 0 1",
+            FenError::InvalidEnPassant("e4".into()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            FenError::InvalidHalfmoveClock("x".into()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+            FenError::InvalidFullmoveNumber("0".into()),
+        ),
+        (
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::KingCount {
+                color: PieceColor::Black,
+                count: 0,
+            },
+        ),
+        (
+            "4k3/8/8/8/8/8/8/P3K3 w - - 0 1",
+            FenError::PawnOnBackRank(sq("a1")),
+        ),
+        ("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1", FenError::OpponentInCheck),
+    ];
+
+    for (fen, expected) in cases {
+        assert_eq!(Position::from_fen(fen).unwrap_err(), expected, "{fen}");
+    }
+}
+
+#[test]
+fn en_passant_needs_a_pawn_that_just_advanced_two() {
+    for fen in [
+        // A knight on the target square, and no pawn in front of it.
+        "4k3/8/3n4/4P3/8/8/8/4K3 w - d6 0 1",
+        // The square the pawn came from is occupied.
+        "4k3/3n4/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        // The pawn in front is White's own.
+        "4k3/8/8/3PP3/8/8/8/4K3 w - d6 0 1",
+        "4k3/8/8/8/3pp3/8/8/4K3 b - d3 0 1",
+    ] {
+        let square = fen.split_whitespace().nth(3).unwrap();
+        assert_eq!(
+            Position::from_fen(fen).unwrap_err(),
+            FenError::InvalidEnPassant(square.into()),
+            "{fen}"
+        );
+    }
+
+    let position = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    assert_eq!(position.en_passant(), Some(sq("d6")));
+}
+
+#[test]
+fn errors_describe_the_problem() {
+    let err = Position::from_fen("4k3/8/8/8/8/8/8/4K4 w - - 0 1").unwrap_err();
+    assert_eq!(err.to_string(), "rank 1 describes 9 squares instead of 8");
+}
@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+use chess_core::{
+    NetError, PROTOCOL_VERSION, PeerConnection, PeerListener, PeerMessage, PieceColor, Position,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn round_trip(line: &str) -> String {
+    PeerMessage::parse(line).unwrap().to_string()
+}
+
+/// A host and a guest connected over localhost.
+fn connected_pair() -> (PeerConnection, PeerConnection) {
+    let listener = PeerListener::bind("127.0.0.1:0").unwrap();
+    let guest = PeerConnection::connect(listener.local_addr().unwrap()).unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        if let Some(host) = listener.accept().unwrap() {
+            return (host, guest);
+        }
+        assert!(Instant::now() < deadline, "the guest was never accepted");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn messages_round_trip() {
+    for line in [
+        "hello 1",
+        "color white",
+        "color black",
+        "move e2e4",
+        "move e7e8q",
+        "resign",
+        "abort",
+        "draw offer",
+        "draw accept",
+        "draw decline",
+        "draw claim",
+        "resync",
+        "error a game is already in progress",
+    ] {
+        assert_eq!(round_trip(line), line);
+    }
+}
+
+#[test]
+fn sync_carries_the_game() {
+    let line = "sync rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves e2e4 e7e5";
+    let PeerMessage::Sync { initial, moves } = PeerMessage::parse(line).unwrap() else {
+        panic!("not a sync");
+    };
+    assert_eq!(initial.to_fen(), Position::new().to_fen());
+    let moves: Vec<String> = moves.iter().map(ToString::to_string).collect();
+    assert_eq!(moves, ["e2e4", "e7e5"]);
+    assert_eq!(round_trip(line), line);
+
+    let empty = "sync 4k3/8/8/8/8/8/8/4K3 b - - 0 1 moves";
+    assert_eq!(round_trip(empty), empty);
+}
+
+#[test]
+fn rejects_bad_lines() {
+    for line in [
+        "",
+        "hi",
+        "hello",
+        "hello one",
+        "color red",
+        "move",
+        "move e2e4 e7e5",
+        "draw",
+        "draw maybe",
+        "resign now",
+    ] {
+        assert!(
+            matches!(PeerMessage::parse(line), Err(NetError::Malformed(_))),
+            "{line:?} was accepted"
+        );
+    }
+    assert!(matches!(
+        PeerMessage::parse("sync not a fen moves"),
+        Err(NetError::InvalidFen(_))
+    ));
+    assert!(matches!(
+        PeerMessage::parse(
+            "sync rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves e2e5"
+        ),
+        Err(NetError::IllegalMove(mv)) if mv == "e2e5"
+    ));
+}
+
+#[test]
+fn talks_over_localhost() {
+    let (mut host, mut guest) = connected_pair();
+    guest
+        .send(&PeerMessage::Hello {
+            version: PROTOCOL_VERSION,
+        })
+        .unwrap();
+    assert!(matches!(
+        host.recv_timeout(TIMEOUT).unwrap(),
+        Some(PeerMessage::Hello { version }) if version == PROTOCOL_VERSION
+    ));
+
+    host.send(&PeerMessage::Color(PieceColor::Black)).unwrap();
+    host.send(&PeerMessage::Move("e2e4".into())).unwrap();
+    assert!(matches!(
+        guest.recv_timeout(TIMEOUT).unwrap(),
+        Some(PeerMessage::Color(PieceColor::Black))
+    ));
+    assert!(matches!(
+        guest.recv_timeout(TIMEOUT).unwrap(),
+        Some(PeerMessage::Move(mv)) if mv == "e2e4"
+    ));
+    assert!(guest.try_recv().unwrap().is_none());
+}
+
+#[test]
+fn notices_a_disconnect() {
+    let (mut host, guest) = connected_pair();
+    drop(guest);
+    assert!(matches!(
+        host.recv_timeout(TIMEOUT),
+        Err(NetError::Disconnected)
+    ));
+}
+
+#[test]
+fn connecting_to_a_closed_port_fails() {
+    let listener = PeerListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    assert!(matches!(
+        PeerConnection::connect(address),
+        Err(NetError::Io(_))
+    ));
+}
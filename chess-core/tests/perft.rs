@@ -0,0 +1,256 @@
+//! Perft regression suite. Node counts are the published figures for the
+//! standard test positions; the divide tables were recorded from a generator
+//! matching all of them, so a failure names the first root move whose subtree
+//! diverges.
+
+use chess_core::Position;
+
+fn setup(fen: &str) -> Position {
+    Position::from_fen(fen).unwrap_or_else(|err| panic!("{fen}: {err}"))
+}
+
+/// Formats a divide like `go perft` does, for diffing against another engine.
+fn format_divide(position: &mut Position, depth: u32) -> String {
+    let mut lines: Vec<String> = position
+        .divide(depth)
+        .into_iter()
+        .map(|(mv, nodes)| format!("{mv}: {nodes}"))
+        .collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+/// Checks the counts for depths `1..=expected.len()`, then compares the
+/// deepest level move by move against `divide`.
+fn assert_perft(fen: &str, expected: &[u64], divide: &str) {
+    let mut position = setup(fen);
+    let depth = expected.len() as u32;
+
+    let mut recorded: Vec<(&str, u64)> = divide
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .map(|pair| (pair[0], pair[1].parse().unwrap()))
+        .collect();
+    recorded.sort();
+
+    let mut actual: Vec<(String, u64)> = position
+        .divide(depth)
+        .into_iter()
+        .map(|(mv, nodes)| (mv.to_string(), nodes))
+        .collect();
+    actual.sort();
+
+    for (mv, nodes) in &actual {
+        match recorded.iter().find(|(m, _)| m == mv) {
+            None => panic!("{fen}: generated illegal root move {mv}"),
+            Some((_, want)) => assert_eq!(
+                nodes, want,
+                "{fen}: perft({depth}) diverges after root move {mv}"
+            ),
+        }
+    }
+    for (mv, _) in &recorded {
+        assert!(
+            actual.iter().any(|(m, _)| m == mv),
+            "{fen}: missing root move {mv}"
+        );
+    }
+
+    for (d, &want) in (1..depth).zip(expected) {
+        assert_eq!(position.perft(d), want, "{fen}: perft({d})");
+    }
+    let total: u64 = actual.iter().map(|(_, nodes)| nodes).sum();
+    assert_eq!(total, expected[expected.len() - 1], "{fen}: perft({depth})");
+}
+
+/// Checks a single published count, printing the divide on mismatch.
+fn assert_perft_total(fen: &str, depth: u32, expected: u64) {
+    let mut position = setup(fen);
+    let nodes = position.perft(depth);
+    if nodes != expected {
+        panic!(
+            "{fen}: perft({depth}) = {nodes}, expected {expected}\n{}",
+            format_divide(&mut position, depth)
+        );
+    }
+}
+
+#[test]
+fn initial_position() {
+    assert_perft(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+        &[20, 400, 8902, 197281],
+        "
+        a2a3 8457  a2a4 9329  b1a3 8885  b1c3 9755
+        b2b3 9345  b2b4 9332  c2c3 9272  c2c4 9744
+        d2d3 11959  d2d4 12435  e2e3 13134  e2e4 13160
+        f2f3 8457  f2f4 8929  g1f3 9748  g1h3 8881
+        g2g3 9345  g2g4 9328  h2h3 8457  h2h4 9329
+        ",
+    );
+}
+
+#[test]
+fn kiwipete() {
+    assert_perft(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -",
+        &[48, 2039, 97862, 4085603],
+        "
+        a1b1 83348  a1c1 83263  a1d1 79695  a2a3 94405
+        a2a4 90978  b2b3 81066  c3a4 91447  c3b1 84773
+        c3b5 81498  c3d1 84782  d2c1 83037  d2e3 90274
+        d2f4 84869  d2g5 87951  d2h6 82323  d5d6 79551
+        d5e6 97464  e1c1 79803  e1d1 79989  e1f1 77887
+        e1g1 86975  e2a6 69334  e2b5 79739  e2c4 84835
+        e2d1 74963  e2d3 85119  e2f1 88728  e5c4 77752
+        e5c6 83885  e5d3 77431  e5d7 93913  e5f7 88799
+        e5g4 79912  e5g6 83866  f3d3 83727  f3e3 92505
+        f3f4 90488  f3f5 104992  f3f6 77838  f3g3 94461
+        f3g4 92037  f3h3 98524  f3h5 95034  g2g3 77468
+        g2g4 75677  g2h3 82759  h1f1 81563  h1g1 84876
+        ",
+    );
+}
+
+#[test]
+fn position_3() {
+    assert_perft(
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - -",
+        &[14, 191, 2812, 43238],
+        "
+        a5a4 3394  a5a6 3653  b4a4 3019  b4b1 4199
+        b4b2 3328  b4b3 3658  b4c4 3797  b4d4 3622
+        b4e4 3391  b4f4 606  e2e3 3107  e2e4 2748
+        g2g3 1014  g2g4 3702
+        ",
+    );
+}
+
+#[test]
+fn position_4() {
+    assert_perft(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq -",
+        &[6, 264, 9467, 422333],
+        "
+        b4c5 58167  c4c5 60769  d2d4 72051  f1f2 73972
+        f3d4 75736  g1h1 81638
+        ",
+    );
+}
+
+#[test]
+fn position_5() {
+    assert_perft(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ -",
+        &[44, 1486, 62379, 2103487],
+        "
+        a2a3 46833  a2a4 48882  b1a3 44378  b1c3 50303
+        b1d2 40560  b2b3 46497  b2b4 46696  c1d2 46881
+        c1e3 53637  c1f4 52350  c1g5 45601  c1h6 40913
+        c2c3 49406  c4a6 41884  c4b3 43453  c4b5 45559
+        c4d3 43565  c4d5 48002  c4e6 49872  c4f7 43289
+        d1d2 48843  d1d3 57153  d1d4 57744  d1d5 56899
+        d1d6 43766  d7c8b 65053  d7c8n 62009  d7c8q 44226
+        d7c8r 38077  e1d2 33423  e1f1 49775  e1f2 36783
+        e1g1 47054  e2c3 54792  e2d4 52109  e2f4 51127
+        e2g1 48844  e2g3 51892  g2g3 44509  g2g4 45506
+        h1f1 46101  h1g1 44668  h2h3 46762  h2h4 47811
+        ",
+    );
+}
+
+#[test]
+fn position_6() {
+    assert_perft(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - -",
+        &[46, 2079, 89890, 3894594],
+        "
+        a1a2 80000  a1b1 83917  a1c1 81943  a1d1 79961
+        a1e1 76017  a3a4 89719  b2b3 83959  b2b4 85907
+        c3a2 81990  c3a4 83785  c3b1 74196  c3b5 86102
+        c3d1 72167  c3d5 84310  c4a2 83990  c4a6 87691
+        c4b3 84078  c4b5 84686  c4d5 82111  c4e6 88890
+        c4f7 8203  d3d4 93464  e2d1 82176  e2d2 89961
+        e2e1 84117  e2e3 90128  f1b1 83966  f1c1 85946
+        f1d1 87918  f1e1 87928  f3d2 87558  f3d4 98744
+        f3e1 77246  f3e5 111761  f3h4 89355  g1h1 95870
+        g2g3 89798  g5c1 81175  g5d2 89433  g5e3 91743
+        g5f4 99547  g5f6 79097  g5h4 83607  g5h6 89238
+        h2h3 93404  h2h4 87792
+        ",
+    );
+}
+
+// Edge cases for en passant, castling and promotion from Martin Sedlak's
+// collection.
+
+#[test]
+fn illegal_en_passant_exposes_rank() {
+    assert_perft_total("3k4/3p4/8/K1P4r/8/8/8/8 b - -", 6, 1134888);
+}
+
+#[test]
+fn illegal_en_passant_exposes_diagonal() {
+    assert_perft_total("8/8/4k3/8/2p5/8/B2P2K1/8 w - -", 6, 1015133);
+}
+
+#[test]
+fn en_passant_capture_gives_check() {
+    assert_perft_total("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3", 6, 1440467);
+}
+
+#[test]
+fn short_castling_gives_check() {
+    assert_perft_total("5k2/8/8/8/8/8/8/4K2R w K -", 6, 661072);
+}
+
+#[test]
+fn long_castling_gives_check() {
+    assert_perft_total("3k4/8/8/8/8/8/8/R3K3 w Q -", 6, 803711);
+}
+
+#[test]
+fn castling_rights_lost_on_rook_capture() {
+    assert_perft_total("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq -", 4, 1274206);
+}
+
+#[test]
+fn castling_prevented_by_attacks() {
+    assert_perft_total("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq -", 4, 1720476);
+}
+
+#[test]
+fn promote_out_of_check() {
+    assert_perft_total("2K2r2/4P3/8/8/8/8/8/3k4 w - -", 6, 3821001);
+}
+
+#[test]
+fn discovered_check() {
+    assert_perft_total("8/8/1P2K3/8/2n5/1q6/8/5k2 b - -", 5, 1004658);
+}
+
+#[test]
+fn promote_to_give_check() {
+    assert_perft_total("4k3/1P6/8/8/8/8/K7/8 w - -", 6, 217342);
+}
+
+#[test]
+fn underpromote_to_give_check() {
+    assert_perft_total("8/P1k5/K7/8/8/8/8/8 w - -", 6, 92683);
+}
+
+#[test]
+fn self_stalemate() {
+    assert_perft_total("K1k5/8/P7/8/8/8/8/8 w - -", 6, 2217);
+}
+
+#[test]
+fn stalemate_and_checkmate_with_pawn() {
+    assert_perft_total("8/k1P5/8/1K6/8/8/8/8 w - -", 7, 567584);
+}
+
+#[test]
+fn stalemate_and_checkmate_with_queen() {
+    assert_perft_total("8/8/2k5/5q2/5n2/8/5K2/8 b - -", 4, 23527);
+}
@@ -0,0 +1,207 @@
+use chess_core::{PgnError, PgnErrorKind, PgnGame, Position, SanError};
+
+fn play(game: &mut PgnGame, san: &str) {
+    let mv = game.position().parse_san(san).unwrap();
+    game.push(mv);
+}
+
+#[test]
+fn exports_seven_tag_roster_and_movetext() {
+    let mut game = PgnGame::new(Position::new());
+    game.set_tag("White", "Morphy, Paul");
+    game.set_tag("Black", "Duke \"Karl\"");
+    game.set_tag("Annotator", "us");
+    for san in ["e4", "e5", "Nf3", "d6"] {
+        play(&mut game, san);
+    }
+    game.moves_mut()[1].comment = Some("solid".into());
+    game.moves_mut()[2].nags.push(1);
+
+    assert_eq!(
+        game.to_pgn(),
+        "[Event \"?\"]\n\
+         [Site \"?\"]\n\
+         [Date \"????.??.??\"]\n\
+         [Round \"?\"]\n\
+         [White \"Morphy, Paul\"]\n\
+         [Black \"Duke \\\"Karl\\\"\"]\n\
+         [Result \"*\"]\n\
+         [Annotator \"us\"]\n\
+         \n\
+         1. e4 e5 {solid} 2. Nf3 $1 d6 *\n"
+    );
+}
+
+#[test]
+fn export_records_setup_position_and_result() {
+    let position = Position::from_fen("k7/4P3/1K6/8/8/8/8/8 w - - 0 60").unwrap();
+    let mut game = PgnGame::new(position);
+    play(&mut game, "e8=Q#");
+    game.set_result("1-0");
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Result \"1-0\"]\n"));
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"k7/4P3/1K6/8/8/8/8/8 w - - 0 60\"]\n"));
+    assert!(pgn.ends_with("\n60. e8=Q# 1-0\n"));
+}
+
+#[test]
+fn export_wraps_long_movetext() {
+    let mut game = PgnGame::new(Position::new());
+    for _ in 0..20 {
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+            play(&mut game, san);
+        }
+    }
+    let pgn = game.to_pgn();
+    let movetext = pgn.split("\n\n").nth(1).unwrap();
+    assert!(movetext.lines().count() > 1);
+    assert!(movetext.lines().all(|line| line.len() < 80));
+}
+
+#[test]
+fn parses_annotated_game_with_variations() {
+    let pgn = r#"
+[Event "Casual"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3!? (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 $14
+; rest of line comment
+3. Bb5 a6?! {The Morphy defence} 1-0
+"#;
+    let game = PgnGame::from_pgn(pgn).unwrap();
+
+    assert_eq!(game.tag("Event"), Some("Casual"));
+    assert_eq!(game.tag("White"), Some("A"));
+    assert_eq!(game.result(), "1-0");
+
+    let sans: Vec<&str> = game.moves().iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(sans, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+
+    let moves = game.moves();
+    assert_eq!(moves[0].comment_before.as_deref(), Some("Opening comment"));
+    assert_eq!(moves[2].nags, [5]);
+    assert_eq!(moves[3].nags, [14]);
+    assert_eq!(moves[3].comment.as_deref(), Some("rest of line comment"));
+    assert_eq!(moves[5].nags, [6]);
+    assert_eq!(moves[5].comment.as_deref(), Some("The Morphy defence"));
+
+    let variation = &moves[2].variations[0];
+    let sans: Vec<&str> = variation.iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(sans, ["f4", "exf4", "Nf3"]);
+    assert_eq!(variation[1].variations[0][0].san, "d5");
+
+    assert_eq!(
+        game.position().to_fen(),
+        "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4"
+    );
+}
+
+#[test]
+fn export_round_trips() {
+    let pgn = "[Event \"Casual\"]\n[Site \"?\"]\n[Date \"2024.05.01\"]\n[Round \"1\"]\n\
+               [White \"A\"]\n[Black \"B\"]\n[Result \"1/2-1/2\"]\n\n\
+               {Start} 1. d4 {QP} 1... d5 (1... Nf6 2. c4 (2. Nf3) 2... e6) \
+               2. c4 $2 1/2-1/2\n";
+    let game = PgnGame::from_pgn(pgn).unwrap();
+    assert_eq!(game.to_pgn(), pgn);
+}
+
+#[test]
+fn parses_multi_game_files() {
+    let pgn = "[Event \"One\"]\n\n1. e4 e5 1-0\n\n\
+               [Event \"Two\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n\n\
+               1... Kd7 2. e4 *\n\n\
+               1. d4 0-1\n";
+    let games = PgnGame::parse_all(pgn).unwrap();
+
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].tag("Event"), Some("One"));
+    assert_eq!(games[0].result(), "1-0");
+    assert_eq!(games[1].tag("Event"), Some("Two"));
+    assert_eq!(games[1].moves().len(), 2);
+    assert_eq!(
+        games[1].position().to_fen(),
+        "8/3k4/8/8/4P3/8/8/4K3 b - e3 0 2"
+    );
+    assert_eq!(games[1].result(), "*");
+    assert_eq!(games[2].moves()[0].san, "d4");
+    assert_eq!(games[2].result(), "0-1");
+}
+
+#[test]
+fn game_without_result_token_takes_it_from_the_tag() {
+    let game = PgnGame::from_pgn("[Result \"0-1\"]\n1. f3 e5 2. g4 Qh4#").unwrap();
+    assert_eq!(game.result(), "0-1");
+    assert!(game.position().is_checkmate());
+}
+
+#[test]
+fn reports_error_positions() {
+    let error = |pgn: &str| PgnGame::parse_all(pgn).unwrap_err();
+
+    assert_eq!(
+        error("[Event \"x\"]\n\n1. e4 e5\n2. Ke3 *"),
+        PgnError {
+            line: 4,
+            column: 4,
+            kind: PgnErrorKind::IllegalMove(SanError::Illegal("Ke3".into())),
+        }
+    );
+    assert_eq!(
+        error("1. e4 {never closed\n*").kind,
+        PgnErrorKind::UnterminatedComment
+    );
+    assert_eq!(
+        error("1. e4 (1. d4 *"),
+        PgnError {
+            line: 1,
+            column: 7,
+            kind: PgnErrorKind::UnclosedVariation,
+        }
+    );
+    assert_eq!(
+        error("1. e4 ) *"),
+        PgnError {
+            line: 1,
+            column: 7,
+            kind: PgnErrorKind::UnmatchedParen,
+        }
+    );
+    assert_eq!(
+        error("[Event x]"),
+        PgnError {
+            line: 1,
+            column: 8,
+            kind: PgnErrorKind::MalformedTag,
+        }
+    );
+    assert_eq!(
+        error("[Event \"x\"]\n1. e4 & *"),
+        PgnError {
+            line: 2,
+            column: 7,
+            kind: PgnErrorKind::UnexpectedChar('&'),
+        }
+    );
+    assert_eq!(error("(1. e4) *").kind, PgnErrorKind::NothingToAnnotate);
+    assert!(matches!(
+        error("[FEN \"8/8 w - -\"]\n*").kind,
+        PgnErrorKind::InvalidFen(_)
+    ));
+    assert_eq!(
+        PgnGame::from_pgn("  \n").unwrap_err().kind,
+        PgnErrorKind::NoGame
+    );
+}
+
+#[test]
+fn errors_describe_the_location() {
+    let err = PgnGame::from_pgn("1. e4 e4 *").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 1, column 7: 'e4' is not a legal move"
+    );
+}
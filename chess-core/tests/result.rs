@@ -0,0 +1,34 @@
+use chess_core::{DrawReason, GameResult, PieceColor, Termination};
+
+#[test]
+fn draw_reasons_map_to_terminations() {
+    for (reason, termination) in [
+        (DrawReason::Stalemate, Termination::Stalemate),
+        (
+            DrawReason::TimeoutVsInsufficientMaterial,
+            Termination::Timeout,
+        ),
+        (DrawReason::FivefoldRepetition, Termination::Repetition),
+        (DrawReason::SeventyFiveMoveRule, Termination::FiftyMoveRule),
+        (DrawReason::Agreement, Termination::Agreement),
+    ] {
+        assert_eq!(Termination::from(reason), termination);
+    }
+}
+
+#[test]
+fn results_describe_the_outcome() {
+    let mate = GameResult::win(PieceColor::Black, Termination::Checkmate);
+    assert_eq!(mate, GameResult::BlackWins(Termination::Checkmate));
+    assert_eq!(mate.winner(), Some(PieceColor::Black));
+    assert_eq!(mate.pgn_result(), "0-1");
+    assert_eq!(mate.to_string(), "Black wins by checkmate");
+
+    let draw = GameResult::Draw(Termination::Repetition);
+    assert_eq!(draw.winner(), None);
+    assert_eq!(draw.pgn_result(), "1/2-1/2");
+    assert_eq!(draw.to_string(), "Draw by repetition");
+
+    assert_eq!(Termination::Timeout.pgn_tag(), "time forfeit");
+    assert_eq!(Termination::Resignation.pgn_tag(), "normal");
+}
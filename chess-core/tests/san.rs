@@ -0,0 +1,93 @@
+use chess_core::{Position, SanError};
+
+fn san_of(fen: &str, uci: &str) -> String {
+    let position = Position::from_fen(fen).unwrap();
+    let mv = position
+        .legal_moves()
+        .into_iter()
+        .find(|mv| mv.to_string() == uci)
+        .unwrap_or_else(|| panic!("{uci} is not legal in {fen}"));
+    position.san(mv)
+}
+
+#[test]
+fn pawn_moves_and_captures() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san_of(start, "e2e4"), "e4");
+    assert_eq!(san_of(start, "g1f3"), "Nf3");
+    assert_eq!(san_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+}
+
+#[test]
+fn checks_and_mates() {
+    assert_eq!(san_of("7k/5p2/8/6N1/8/8/8/4K3 w - - 0 1", "g5f7"), "Nxf7+");
+    assert_eq!(san_of("k7/4P3/1K6/8/8/8/8/8 w - - 0 1", "e7e8q"), "e8=Q#");
+    assert_eq!(san_of("k7/4P3/1K6/8/8/8/8/8 w - - 0 1", "e7e8n"), "e8=N");
+}
+
+#[test]
+fn castling() {
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    assert_eq!(san_of(fen, "e1g1"), "O-O");
+    assert_eq!(san_of(fen, "e1c1"), "O-O-O");
+    assert_eq!(san_of("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", "e1c1"), "O-O-O+");
+}
+
+#[test]
+fn disambiguation() {
+    let knights = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+    assert_eq!(san_of(knights, "b1d2"), "Nbd2");
+    assert_eq!(san_of(knights, "f1d2"), "Nfd2");
+    assert_eq!(san_of(knights, "b1c3"), "Nc3");
+
+    let rooks = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+    assert_eq!(san_of(rooks, "a1a3"), "R1a3");
+    assert_eq!(san_of(rooks, "a5a3"), "R5a3");
+
+    let queens = "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1";
+    assert_eq!(san_of(queens, "a1b2"), "Qa1b2");
+    assert_eq!(san_of(queens, "a3b2"), "Q3b2");
+    assert_eq!(san_of(queens, "c1b2"), "Qcb2");
+}
+
+#[test]
+fn parse_round_trips_every_legal_move() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1",
+    ] {
+        let position = Position::from_fen(fen).unwrap();
+        for mv in position.legal_moves() {
+            let san = position.san(mv);
+            assert_eq!(position.parse_san(&san), Ok(mv), "{fen}: {san}");
+        }
+    }
+}
+
+#[test]
+fn parse_is_lenient_about_suffixes() {
+    let position = Position::from_fen("k7/4P3/1K6/8/8/8/8/8 w - - 0 1").unwrap();
+    let mv = position.parse_san("e8=Q#").unwrap();
+    assert_eq!(position.parse_san("e8Q"), Ok(mv));
+    assert_eq!(position.parse_san("e8=Q!?"), Ok(mv));
+
+    let start = Position::new();
+    assert_eq!(start.parse_san("0-0"), Err(SanError::Illegal("0-0".into())));
+}
+
+#[test]
+fn parse_errors() {
+    let start = Position::new();
+    assert_eq!(start.parse_san("e5"), Err(SanError::Illegal("e5".into())));
+    assert_eq!(start.parse_san("Zz9"), Err(SanError::Syntax("Zz9".into())));
+    assert_eq!(start.parse_san(""), Err(SanError::Syntax("".into())));
+
+    let knights = Position::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+    assert_eq!(
+        knights.parse_san("Nd2"),
+        Err(SanError::Ambiguous("Nd2".into()))
+    );
+}
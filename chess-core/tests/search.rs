@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use chess_core::{Position, SearchLimits, Searcher, evaluate};
+
+fn best_move(fen: &str, depth: u32) -> (String, Option<i32>) {
+    let position = Position::from_fen(fen).unwrap();
+    let info = Searcher::new().search(&position, SearchLimits::depth(depth));
+    (info.best_move().unwrap().to_string(), info.mate_in())
+}
+
+#[test]
+fn evaluation_is_symmetric() {
+    assert_eq!(evaluate(&Position::new()), 0);
+
+    let white = Position::from_fen("4k3/8/8/8/3P4/2N5/8/4K3 w - - 0 1").unwrap();
+    let black = Position::from_fen("4k3/8/2n5/3p4/8/8/8/4K3 b - - 0 1").unwrap();
+    assert!(evaluate(&white) > 300);
+    assert_eq!(evaluate(&white), evaluate(&black));
+}
+
+#[test]
+fn finds_mate_in_one() {
+    assert_eq!(
+        best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3),
+        ("a1a8".to_string(), Some(1))
+    );
+}
+
+#[test]
+fn finds_mate_in_two() {
+    assert_eq!(
+        best_move("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", 4),
+        ("a1a6".to_string(), Some(2))
+    );
+}
+
+#[test]
+fn sees_being_mated() {
+    let position = Position::from_fen("6k1/8/8/8/8/1r6/r7/6K1 w - - 0 1").unwrap();
+    let info = Searcher::new().search(&position, SearchLimits::depth(3));
+    assert_eq!(info.mate_in(), Some(-1));
+}
+
+#[test]
+fn wins_material() {
+    assert_eq!(best_move("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 3).0, "d2d5");
+}
+
+#[test]
+fn quiescence_avoids_defended_pawns() {
+    // Qxd5 loses the queen to exd5; every other move keeps the material.
+    let (mv, _) = best_move("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", 2);
+    assert_ne!(mv, "d1d5");
+}
+
+#[test]
+fn reports_no_move_without_legal_moves() {
+    let position = Position::from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+    let info = Searcher::new().search(&position, SearchLimits::depth(3));
+    assert_eq!(info.best_move(), None);
+}
+
+#[test]
+fn respects_movetime() {
+    let position = Position::new();
+    let start = Instant::now();
+    let info = Searcher::new().search(
+        &position,
+        SearchLimits::movetime(Duration::from_millis(100)),
+    );
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(info.best_move().is_some());
+    assert!(info.depth >= 1);
+}
+
+#[test]
+fn reports_each_iteration() {
+    let position = Position::new();
+    let mut depths = Vec::new();
+    Searcher::new().search_with(&position, SearchLimits::depth(3), |info| {
+        depths.push(info.depth);
+        assert!(!info.pv.is_empty());
+    });
+    assert_eq!(depths, [1, 2, 3]);
+}
@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use chess_core::{
+    EngineMessage, GoParams, GuiCommand, GuiCommandError, PieceColor, Position, SearchInfo,
+    SearchLimits, UciInfo, UciScore, go_command, info_line, position_command,
+};
+
+#[test]
+fn parses_engine_output() {
+    assert_eq!(
+        EngineMessage::parse("id name Stockfish 17"),
+        EngineMessage::IdName("Stockfish 17".into())
+    );
+    assert_eq!(EngineMessage::parse("uciok"), EngineMessage::UciOk);
+    assert_eq!(EngineMessage::parse("readyok"), EngineMessage::ReadyOk);
+    assert_eq!(
+        EngineMessage::parse("bestmove e2e4 ponder e7e5"),
+        EngineMessage::BestMove {
+            mv: Some("e2e4".into()),
+            ponder: Some("e7e5".into()),
+        }
+    );
+    assert_eq!(
+        EngineMessage::parse("bestmove (none)"),
+        EngineMessage::BestMove {
+            mv: None,
+            ponder: None,
+        }
+    );
+    assert_eq!(
+        EngineMessage::parse(
+            "info depth 12 seldepth 18 multipv 1 score cp -35 nodes 48213 nps 900000 \
+             time 53 pv d7d5 c2c4"
+        ),
+        EngineMessage::Info(UciInfo {
+            depth: Some(12),
+            score: Some(UciScore::Centipawns(-35)),
+            nodes: Some(48213),
+            time: Some(Duration::from_millis(53)),
+            pv: vec!["d7d5".into(), "c2c4".into()],
+            string: None,
+        })
+    );
+    let EngineMessage::Info(info) = EngineMessage::parse("info depth 5 score mate -3 pv a1a2")
+    else {
+        panic!("not an info line");
+    };
+    assert_eq!(info.score, Some(UciScore::Mate(-3)));
+    assert_eq!(info.score.unwrap().to_string(), "-M3");
+    assert_eq!(UciScore::Centipawns(35).to_string(), "+0.35");
+    assert!(matches!(
+        EngineMessage::parse("option name Hash type spin"),
+        EngineMessage::Other(_)
+    ));
+}
+
+#[test]
+fn parses_uci_moves() {
+    let position = Position::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    assert_eq!(position.parse_uci("e1g1").unwrap().to_string(), "e1g1");
+    assert_eq!(
+        position.parse_uci("b7a8n").unwrap().promotion,
+        Some(chess_core::PieceType::Knight)
+    );
+    assert_eq!(position.parse_uci("b7b8"), None);
+    assert_eq!(position.parse_uci("e1e3"), None);
+}
+
+#[test]
+fn builds_commands() {
+    let start = Position::new();
+    let e4 = start.parse_uci("e2e4").unwrap();
+    assert_eq!(position_command(&start, &[]), "position startpos");
+    assert_eq!(
+        position_command(&start, &[e4]),
+        "position startpos moves e2e4"
+    );
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    assert_eq!(
+        position_command(&Position::from_fen(fen).unwrap(), &[]),
+        format!("position fen {fen}")
+    );
+
+    assert_eq!(go_command(SearchLimits::depth(6)), "go depth 6");
+    assert_eq!(
+        go_command(SearchLimits::movetime(Duration::from_millis(1500))),
+        "go movetime 1500"
+    );
+    assert_eq!(go_command(SearchLimits::default()), "go infinite");
+}
+
+#[test]
+fn parses_gui_commands() {
+    let parse = |line: &str| GuiCommand::parse(line).unwrap();
+
+    assert!(matches!(parse("uci"), GuiCommand::Uci));
+    assert!(matches!(parse("isready"), GuiCommand::IsReady));
+    assert!(matches!(parse("stop"), GuiCommand::Stop));
+
+    let GuiCommand::Position(position) = parse("position startpos moves e2e4 e7e5 g1f3") else {
+        panic!("not a position command");
+    };
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+    let GuiCommand::Position(position) =
+        parse("position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 moves e2e4 e8d7")
+    else {
+        panic!("not a position command");
+    };
+    assert_eq!(position.to_fen(), "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2");
+
+    let GuiCommand::SetOption { name, value } = parse("setoption name Clear Hash") else {
+        panic!("not a setoption command");
+    };
+    assert_eq!((name.as_str(), value), ("Clear Hash", None));
+    let GuiCommand::SetOption { name, value } = parse("setoption name Hash value 64") else {
+        panic!("not a setoption command");
+    };
+    assert_eq!((name.as_str(), value.as_deref()), ("Hash", Some("64")));
+
+    let GuiCommand::Go(params) = parse("go wtime 60000 btime 50000 winc 1000 movestogo 20") else {
+        panic!("not a go command");
+    };
+    assert_eq!(
+        params,
+        GoParams {
+            wtime: Some(Duration::from_secs(60)),
+            btime: Some(Duration::from_secs(50)),
+            winc: Some(Duration::from_secs(1)),
+            movestogo: Some(20),
+            ..GoParams::default()
+        }
+    );
+
+    assert!(matches!(
+        GuiCommand::parse("position startpos moves e2e5"),
+        Err(GuiCommandError::IllegalMove(mv)) if mv == "e2e5"
+    ));
+    assert!(matches!(
+        GuiCommand::parse("position fen 8/8 w"),
+        Err(GuiCommandError::InvalidFen(_))
+    ));
+    assert!(matches!(
+        GuiCommand::parse("go depth deep"),
+        Err(GuiCommandError::Malformed(_))
+    ));
+    assert!(matches!(
+        GuiCommand::parse("xyzzy"),
+        Err(GuiCommandError::Unknown(_))
+    ));
+}
+
+#[test]
+fn go_params_budget_the_clock() {
+    let params = GoParams {
+        wtime: Some(Duration::from_secs(60)),
+        btime: Some(Duration::from_secs(30)),
+        winc: Some(Duration::from_secs(2)),
+        ..GoParams::default()
+    };
+    assert_eq!(
+        params.limits(PieceColor::White).movetime,
+        Some(Duration::from_millis(2000 + 1500))
+    );
+    assert_eq!(
+        params.limits(PieceColor::Black).movetime,
+        Some(Duration::from_millis(1000))
+    );
+
+    // Never the whole clock, even on the last move before the time control.
+    let last_move = GoParams {
+        wtime: Some(Duration::from_millis(400)),
+        movestogo: Some(1),
+        ..GoParams::default()
+    };
+    assert_eq!(
+        last_move.limits(PieceColor::White).movetime,
+        Some(Duration::from_millis(350))
+    );
+
+    let fixed = GoParams {
+        movetime: Some(Duration::from_millis(250)),
+        wtime: Some(Duration::from_secs(60)),
+        depth: Some(9),
+        ..GoParams::default()
+    };
+    assert_eq!(
+        fixed.limits(PieceColor::White),
+        SearchLimits {
+            depth: Some(9),
+            movetime: Some(Duration::from_millis(250)),
+            nodes: None,
+        }
+    );
+    let infinite = GoParams {
+        infinite: true,
+        ..GoParams::default()
+    };
+    assert_eq!(infinite.limits(PieceColor::White), SearchLimits::default());
+}
+
+#[test]
+fn formats_search_info() {
+    let position = Position::new();
+    let info = SearchInfo {
+        depth: 4,
+        score: 25,
+        nodes: 5000,
+        elapsed: Duration::from_millis(10),
+        pv: vec![position.parse_uci("e2e4").unwrap()],
+    };
+    assert_eq!(
+        info_line(&info),
+        "info depth 4 score cp 25 nodes 5000 nps 500000 time 10 pv e2e4"
+    );
+    let mate = SearchInfo {
+        score: chess_core::MATE_SCORE - 3,
+        ..info
+    };
+    assert!(info_line(&mate).contains(" score mate 2 "));
+}
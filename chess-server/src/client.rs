@@ -0,0 +1,130 @@
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::WebSocket;
+
+use crate::protocol::{ClientMessage, ServerError, ServerMessage};
+use crate::socket::{POLL, handshake_error, read_text, send};
+
+/// How long connecting to the server may take before giving up.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A client's WebSocket connection to the server. A background thread owns
+/// the socket, so messages can be sent and polled for without blocking.
+/// Dropping it closes the connection.
+pub struct ServerConnection {
+    outgoing: Sender<ClientMessage>,
+    incoming: Receiver<Result<String, ServerError>>,
+}
+
+impl ServerConnection {
+    /// Connects to the server at `address`, such as `chess.example.com:7879`.
+    pub fn connect(address: &str) -> Result<Self, ServerError> {
+        let mut last_error = None;
+        let mut stream = None;
+        for socket_address in address.to_socket_addrs().map_err(ServerError::Io)? {
+            match TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT) {
+                Ok(connected) => {
+                    stream = Some(connected);
+                    break;
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+        let stream = stream.ok_or_else(|| {
+            ServerError::Io(last_error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "no address to connect to")
+            }))
+        })?;
+        stream.set_nodelay(true).map_err(ServerError::Io)?;
+        stream
+            .set_read_timeout(Some(CONNECT_TIMEOUT))
+            .map_err(ServerError::Io)?;
+        let (websocket, _) =
+            tungstenite::client(format!("ws://{address}/"), stream).map_err(handshake_error)?;
+        websocket
+            .get_ref()
+            .set_read_timeout(Some(POLL))
+            .map_err(ServerError::Io)?;
+
+        let (outgoing, to_send) = mpsc::channel();
+        let (received, incoming) = mpsc::channel();
+        thread::spawn(move || run(websocket, &to_send, &received));
+        Ok(Self { outgoing, incoming })
+    }
+
+    /// Queues `message` to be sent. Fails once the connection has closed.
+    pub fn send(&mut self, message: ClientMessage) -> Result<(), ServerError> {
+        self.outgoing
+            .send(message)
+            .map_err(|_| ServerError::Disconnected)
+    }
+
+    /// The next message if one has arrived, without waiting. A message that
+    /// does not parse is returned as an error; the connection stays usable.
+    pub fn try_recv(&mut self) -> Result<Option<ServerMessage>, ServerError> {
+        match self.incoming.try_recv() {
+            Ok(text) => ServerMessage::parse(&text?).map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(ServerError::Disconnected),
+        }
+    }
+
+    /// The next message, waiting at most `timeout` for it; `None` if none
+    /// arrived in time.
+    pub fn recv_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<ServerMessage>, ServerError> {
+        match self.incoming.recv_timeout(timeout) {
+            Ok(text) => ServerMessage::parse(&text?).map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(ServerError::Disconnected),
+        }
+    }
+}
+
+/// Owns the socket until either side goes away. The error that ended the
+/// connection is passed on last.
+fn run(
+    mut websocket: WebSocket<TcpStream>,
+    to_send: &Receiver<ClientMessage>,
+    received: &Sender<Result<String, ServerError>>,
+) {
+    match pump(&mut websocket, to_send, received) {
+        Ok(()) => {
+            let _ = websocket.close(None);
+            let _ = websocket.flush();
+        }
+        Err(ServerError::Disconnected) => {}
+        Err(err) => {
+            let _ = received.send(Err(err));
+        }
+    }
+}
+
+/// Shuttles messages between the socket and the channels. Returns `Ok` when
+/// the `ServerConnection` was dropped.
+fn pump(
+    websocket: &mut WebSocket<TcpStream>,
+    to_send: &Receiver<ClientMessage>,
+    received: &Sender<Result<String, ServerError>>,
+) -> Result<(), ServerError> {
+    loop {
+        if let Some(text) = read_text(websocket)?
+            && received.send(Ok(text)).is_err()
+        {
+            return Ok(());
+        }
+        loop {
+            match to_send.try_recv() {
+                Ok(message) => send(websocket, &message)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! A game server for many concurrent games over WebSocket. A client creates
+//! a room and gets a code to share; whoever joins with the code takes the
+//! other seat, and anyone after that watches. The server keeps the game: it
+//! checks every move against the rules, runs the clocks and passes each
+//! move on to everyone else in the room.
+//!
+//! Each WebSocket text frame carries one [`ClientMessage`] or
+//! [`ServerMessage`] in the same line format the LAN protocol uses.
+
+mod client;
+mod lobby;
+mod protocol;
+mod server;
+mod socket;
+
+pub use client::ServerConnection;
+pub use lobby::{ClientId, Lobby, ROOM_EXPIRY};
+pub use protocol::{ClientMessage, DEFAULT_PORT, Seat, ServerError, ServerMessage};
+pub use server::{ConnectionError, Server};
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use chess_core::{ChessClock, Move, PieceColor, Position, TimeControl};
+
+use crate::protocol::{ClientMessage, Seat, ServerMessage};
+
+/// How long a room stays open once everyone has left it.
+pub const ROOM_EXPIRY: Duration = Duration::from_secs(10 * 60);
+
+/// Room codes use letters and digits that cannot be mistaken for each other.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 6;
+
+/// Identifies a connection for as long as it is open.
+pub type ClientId = u64;
+
+/// Every room and every connected client. The server owns one behind a
+/// mutex; connections hand it what they receive and it answers through each
+/// client's outbox.
+#[derive(Default)]
+pub struct Lobby {
+    rooms: HashMap<String, Room>,
+    clients: HashMap<ClientId, Client>,
+    next_client: ClientId,
+    codes: RandomState,
+}
+
+struct Client {
+    outbox: Sender<ServerMessage>,
+    room: Option<String>,
+}
+
+/// One game and the clients following it.
+struct Room {
+    initial: Position,
+    position: Position,
+    moves: Vec<Move>,
+    clock: Option<ChessClock>,
+    /// The client playing each side, White first, while connected.
+    players: [Option<ClientId>; 2],
+    spectators: Vec<ClientId>,
+    draw_offer: Option<PieceColor>,
+    /// The message that ended the game by resignation, abort, agreement, a
+    /// draw claim or a flag fall, repeated to anyone who joins later. Games decided on the board
+    /// need none.
+    ending: Option<ServerMessage>,
+    /// Time since the last client left, while nobody is in the room.
+    empty_for: Option<Duration>,
+}
+
+fn index(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    }
+}
+
+impl Room {
+    fn new(control: Option<TimeControl>) -> Self {
+        Self {
+            initial: Position::new(),
+            position: Position::new(),
+            moves: Vec::new(),
+            clock: control.map(ChessClock::new),
+            players: [None, None],
+            spectators: Vec::new(),
+            draw_offer: None,
+            ending: None,
+            empty_for: None,
+        }
+    }
+
+    /// Threefold repetition and the fifty-move rule only end the game once
+    /// a player claims the draw.
+    fn is_over(&self) -> bool {
+        self.ending.is_some()
+            || self.position.is_checkmate()
+            || self.position.automatic_draw().is_some()
+    }
+
+    fn seat_of(&self, client: ClientId) -> Option<Seat> {
+        if let Some(i) = self.players.iter().position(|&p| p == Some(client)) {
+            Some(Seat::Player(if i == 0 {
+                PieceColor::White
+            } else {
+                PieceColor::Black
+            }))
+        } else if self.spectators.contains(&client) {
+            Some(Seat::Spectator)
+        } else {
+            None
+        }
+    }
+
+    fn members(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.players
+            .iter()
+            .flatten()
+            .chain(&self.spectators)
+            .copied()
+    }
+
+    fn game_message(&self) -> ServerMessage {
+        ServerMessage::Game {
+            initial: Box::new(self.initial.clone()),
+            moves: self.moves.clone(),
+        }
+    }
+
+    fn clock_message(&self) -> Option<ServerMessage> {
+        self.clock.as_ref().map(|clock| ServerMessage::Clock {
+            white: clock.remaining(PieceColor::White),
+            black: clock.remaining(PieceColor::Black),
+        })
+    }
+
+    /// Plays `mv`, which must be legal, and runs the clock the way the
+    /// client does: it starts after White's first move and switches on
+    /// every move after that.
+    fn play(&mut self, mv: Move) {
+        let mover = self.position.side_to_move();
+        self.position.make_move(mv);
+        self.moves.push(mv);
+        if self.draw_offer == Some(mover.opposite()) {
+            self.draw_offer = None;
+        }
+        let over = self.is_over();
+        if let Some(clock) = &mut self.clock {
+            clock.press();
+            if over {
+                clock.pause();
+            } else if clock.running().is_none() {
+                clock.start(self.position.side_to_move());
+            }
+        }
+    }
+
+    fn end(&mut self, ending: ServerMessage) {
+        self.ending = Some(ending);
+        self.draw_offer = None;
+        if let Some(clock) = &mut self.clock {
+            clock.pause();
+        }
+    }
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new connection; everything the server has to say to it
+    /// goes to `outbox`.
+    pub fn connect(&mut self, outbox: Sender<ServerMessage>) -> ClientId {
+        let id = self.next_client;
+        self.next_client += 1;
+        self.clients.insert(id, Client { outbox, room: None });
+        id
+    }
+
+    /// Forgets a closed connection and frees its seat.
+    pub fn disconnect(&mut self, client: ClientId) {
+        self.leave(client);
+        self.clients.remove(&client);
+    }
+
+    /// The number of open rooms.
+    pub fn room_count(&self) -> usize {
+        self.rooms.len()
+    }
+
+    pub fn handle(&mut self, client: ClientId, message: ClientMessage) {
+        match message {
+            ClientMessage::Create { color, control } => {
+                self.leave(client);
+                let code = self.new_code();
+                let mut room = Room::new(control);
+                room.players[index(color)] = Some(client);
+                self.rooms.insert(code.clone(), room);
+                self.enter(client, code);
+            }
+            ClientMessage::Join(code) => self.join(client, &code, false),
+            ClientMessage::Watch(code) => self.join(client, &code, true),
+            ClientMessage::Move(uci) => self.play(client, &uci),
+            ClientMessage::RequestSync => {
+                let Some((_, room)) = self.room_of(client) else {
+                    return self.refuse(client, "not in a room");
+                };
+                let messages: Vec<_> = [Some(room.game_message()), room.clock_message()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                for message in messages {
+                    self.send(client, message);
+                }
+            }
+            ClientMessage::Resign
+            | ClientMessage::Abort
+            | ClientMessage::OfferDraw
+            | ClientMessage::AcceptDraw
+            | ClientMessage::DeclineDraw
+            | ClientMessage::ClaimDraw => self.act(client, message),
+        }
+    }
+
+    /// Runs the clocks of games in progress, announcing flag falls, and
+    /// closes rooms that have stood empty for [`ROOM_EXPIRY`].
+    pub fn tick(&mut self, delta: Duration) {
+        let mut flags = Vec::new();
+        self.rooms.retain(|code, room| {
+            if room.members().next().is_none() {
+                let empty_for = room.empty_for.get_or_insert(Duration::ZERO);
+                *empty_for += delta;
+                return *empty_for < ROOM_EXPIRY;
+            }
+            room.empty_for = None;
+            if room.ending.is_none()
+                && let Some(color) = room.clock.as_mut().and_then(|clock| clock.tick(delta))
+            {
+                let flag = ServerMessage::Flag(color);
+                room.end(flag.clone());
+                flags.push((code.clone(), flag));
+            }
+            true
+        });
+        for (code, flag) in flags {
+            self.broadcast(&code, None, flag);
+        }
+    }
+
+    /// A code no open room uses.
+    fn new_code(&mut self) -> String {
+        loop {
+            let mut hash = self
+                .codes
+                .hash_one(self.next_client ^ self.rooms.len() as u64);
+            let code: String = (0..CODE_LENGTH)
+                .map(|_| {
+                    let c = CODE_ALPHABET[(hash % CODE_ALPHABET.len() as u64) as usize];
+                    hash /= CODE_ALPHABET.len() as u64;
+                    c as char
+                })
+                .collect();
+            if !self.rooms.contains_key(&code) {
+                return code;
+            }
+            // Hash something else next time round.
+            self.codes = RandomState::new();
+        }
+    }
+
+    fn join(&mut self, client: ClientId, code: &str, watch: bool) {
+        let code = code.to_ascii_uppercase();
+        if !self.rooms.contains_key(&code) {
+            return self.refuse(client, &format!("no room {code}"));
+        }
+        self.leave(client);
+        let room = self.rooms.get_mut(&code).expect("checked above");
+        match room.players.iter().position(Option::is_none) {
+            Some(free) if !watch => room.players[free] = Some(client),
+            _ => room.spectators.push(client),
+        }
+        self.enter(client, code);
+    }
+
+    /// Tells `client` about the room it was just seated in, and the room
+    /// about it.
+    fn enter(&mut self, client: ClientId, code: String) {
+        let room = &self.rooms[&code];
+        let seat = room.seat_of(client).expect("the client was just seated");
+        let mut messages = vec![
+            ServerMessage::Joined {
+                room: code.clone(),
+                seat,
+                control: room.clock.as_ref().map(ChessClock::control),
+            },
+            room.game_message(),
+        ];
+        messages.extend(room.clock_message());
+        for color in [PieceColor::White, PieceColor::Black] {
+            if room.players[index(color)].is_some_and(|player| player != client) {
+                messages.push(ServerMessage::Presence {
+                    color,
+                    present: true,
+                });
+            }
+        }
+        messages.extend(room.draw_offer.map(ServerMessage::OfferDraw));
+        messages.extend(room.ending.clone());
+
+        for message in messages {
+            self.send(client, message);
+        }
+        if let Some(client) = self.clients.get_mut(&client) {
+            client.room = Some(code.clone());
+        }
+        if let Seat::Player(color) = seat {
+            let presence = ServerMessage::Presence {
+                color,
+                present: true,
+            };
+            self.broadcast(&code, Some(client), presence);
+        }
+    }
+
+    /// Takes `client` out of its room, if it is in one.
+    fn leave(&mut self, client: ClientId) {
+        let Some(code) = self
+            .clients
+            .get_mut(&client)
+            .and_then(|client| client.room.take())
+        else {
+            return;
+        };
+        let Some(room) = self.rooms.get_mut(&code) else {
+            return;
+        };
+        let seat = room.seat_of(client);
+        for player in &mut room.players {
+            if *player == Some(client) {
+                *player = None;
+            }
+        }
+        room.spectators.retain(|&s| s != client);
+        if let Some(Seat::Player(color)) = seat {
+            let presence = ServerMessage::Presence {
+                color,
+                present: false,
+            };
+            self.broadcast(&code, None, presence);
+        }
+    }
+
+    fn room_of(&mut self, client: ClientId) -> Option<(String, &mut Room)> {
+        let code = self.clients.get(&client)?.room.clone()?;
+        let room = self.rooms.get_mut(&code)?;
+        Some((code, room))
+    }
+
+    fn play(&mut self, client: ClientId, uci: &str) {
+        let Some((code, room)) = self.room_of(client) else {
+            return self.refuse(client, "not in a room");
+        };
+        let Some(Seat::Player(color)) = room.seat_of(client) else {
+            return self.refuse(client, "spectators cannot move");
+        };
+        let legal = (!room.is_over() && room.position.side_to_move() == color)
+            .then(|| room.position.parse_uci(uci))
+            .flatten();
+        let Some(mv) = legal else {
+            // Whatever the client thought the game was, this is it.
+            let game = room.game_message();
+            let clock = room.clock_message();
+            self.refuse(client, &format!("illegal move {uci}"));
+            self.send(client, game);
+            if let Some(clock) = clock {
+                self.send(client, clock);
+            }
+            return;
+        };
+
+        room.play(mv);
+        let clock = room.clock_message();
+        self.broadcast(&code, Some(client), ServerMessage::Move(mv.to_string()));
+        if let Some(clock) = clock {
+            self.broadcast(&code, None, clock);
+        }
+    }
+
+    /// Resignations, aborts, draw offers and draw claims.
+    fn act(&mut self, client: ClientId, action: ClientMessage) {
+        let Some((code, room)) = self.room_of(client) else {
+            return self.refuse(client, "not in a room");
+        };
+        let Some(Seat::Player(color)) = room.seat_of(client) else {
+            return self.refuse(client, "spectators cannot play");
+        };
+        if room.is_over() {
+            return self.refuse(client, "the game is over");
+        }
+        let message = match action {
+            ClientMessage::Resign => {
+                room.end(ServerMessage::Resign(color));
+                ServerMessage::Resign(color)
+            }
+            ClientMessage::Abort => {
+                room.end(ServerMessage::Abort(color));
+                ServerMessage::Abort(color)
+            }
+            ClientMessage::OfferDraw if room.draw_offer.is_none() => {
+                room.draw_offer = Some(color);
+                ServerMessage::OfferDraw(color)
+            }
+            ClientMessage::OfferDraw => {
+                return self.refuse(client, "a draw offer is already pending");
+            }
+            ClientMessage::ClaimDraw if room.position.claimable_draw().is_some() => {
+                room.end(ServerMessage::ClaimDraw(color));
+                ServerMessage::ClaimDraw(color)
+            }
+            ClientMessage::ClaimDraw => return self.refuse(client, "no draw to claim"),
+            ClientMessage::AcceptDraw if room.draw_offer == Some(color.opposite()) => {
+                room.end(ServerMessage::AcceptDraw(color));
+                ServerMessage::AcceptDraw(color)
+            }
+            ClientMessage::DeclineDraw if room.draw_offer == Some(color.opposite()) => {
+                room.draw_offer = None;
+                ServerMessage::DeclineDraw(color)
+            }
+            _ => return self.refuse(client, "no draw offer to answer"),
+        };
+        self.broadcast(&code, Some(client), message);
+    }
+
+    fn send(&self, client: ClientId, message: ServerMessage) {
+        if let Some(client) = self.clients.get(&client) {
+            // A closed outbox belongs to a connection that is going away.
+            let _ = client.outbox.send(message);
+        }
+    }
+
+    fn refuse(&self, client: ClientId, reason: &str) {
+        self.send(client, ServerMessage::Error(reason.to_string()));
+    }
+
+    /// Sends `message` to everyone in room `code` except `except`.
+    fn broadcast(&self, code: &str, except: Option<ClientId>, message: ServerMessage) {
+        let Some(room) = self.rooms.get(code) else {
+            return;
+        };
+        for member in room.members().filter(|&member| Some(member) != except) {
+            self.send(member, message.clone());
+        }
+    }
+}
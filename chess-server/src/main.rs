@@ -0,0 +1,39 @@
+//! The game server binary. Listens on every interface, at the port given as
+//! the only argument or the default one:
+//!
+//! ```text
+//! cargo run --release -p chess-server -- 7879
+//! ```
+
+use std::process::ExitCode;
+
+use chess_server::{DEFAULT_PORT, Server};
+
+fn main() -> ExitCode {
+    let port = match std::env::args().nth(1) {
+        None => DEFAULT_PORT,
+        Some(port) => match port.parse() {
+            Ok(port) => port,
+            Err(_) => {
+                eprintln!("chess-server: invalid port '{port}'");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let server = match Server::bind(("0.0.0.0", port)) {
+        Ok(server) => server.on_error(|err| eprintln!("chess-server: {err}")),
+        Err(err) => {
+            eprintln!("chess-server: cannot listen on port {port}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("chess-server: listening on port {port}");
+    match server.run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("chess-server: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use chess_core::{FenError, Move, PieceColor, Position, TimeControl};
+
+/// The port the server listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 7879;
+
+/// Why talking to the server, or to a client, failed.
+#[derive(Debug)]
+pub enum ServerError {
+    /// Listening, connecting or a socket operation failed.
+    Io(io::Error),
+    /// The WebSocket handshake or a frame failed.
+    WebSocket(tungstenite::Error),
+    /// The other side closed the connection.
+    Disconnected,
+    /// A message that is not part of the protocol.
+    Malformed(String),
+    /// A `game` whose starting position is not valid FEN.
+    InvalidFen(FenError),
+    /// A `game` listing a move that is not legal where it is played.
+    IllegalMove(String),
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::Io(err) => write!(f, "network error: {err}"),
+            ServerError::WebSocket(err) => write!(f, "WebSocket error: {err}"),
+            ServerError::Disconnected => write!(f, "the connection was closed"),
+            ServerError::Malformed(text) => write!(f, "malformed message '{text}'"),
+            ServerError::InvalidFen(err) => write!(f, "invalid FEN: {err}"),
+            ServerError::IllegalMove(mv) => write!(f, "illegal move '{mv}'"),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// Where a client sits in a room.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Seat {
+    Player(PieceColor),
+    Spectator,
+}
+
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Seat::Player(color) => f.write_str(color_name(*color)),
+            Seat::Spectator => f.write_str("spectator"),
+        }
+    }
+}
+
+/// A message from a client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// `create <white|black> [<time control>]`: opens a room with the sender
+    /// playing `color`.
+    Create {
+        color: PieceColor,
+        control: Option<TimeControl>,
+    },
+    /// `join <code>`: takes the free seat in a room, or watches the game if
+    /// both are taken.
+    Join(String),
+    /// `watch <code>`: follows a room's game without playing.
+    Watch(String),
+    /// `move <uci>`, in UCI long algebraic notation.
+    Move(String),
+    Resign,
+    /// `abort`: calls the game off, or abandons it once both sides have
+    /// moved.
+    Abort,
+    /// `draw offer`, `draw accept` and `draw decline`.
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    /// `draw claim`: ends the game by threefold repetition or the fifty-move
+    /// rule.
+    ClaimDraw,
+    /// `resync`: asks for the whole game again.
+    RequestSync,
+}
+
+impl ClientMessage {
+    pub fn parse(text: &str) -> Result<Self, ServerError> {
+        let malformed = || ServerError::Malformed(text.to_string());
+        let mut tokens = text.split_whitespace();
+        let message = match tokens.next() {
+            Some("create") => {
+                let color = tokens.next().and_then(parse_color).ok_or_else(malformed)?;
+                let control = match tokens.next() {
+                    Some(control) => Some(control.parse().map_err(|_| malformed())?),
+                    None => None,
+                };
+                ClientMessage::Create { color, control }
+            }
+            Some("join") => ClientMessage::Join(tokens.next().ok_or_else(malformed)?.to_string()),
+            Some("watch") => ClientMessage::Watch(tokens.next().ok_or_else(malformed)?.to_string()),
+            Some("move") => ClientMessage::Move(tokens.next().ok_or_else(malformed)?.to_string()),
+            Some("resign") => ClientMessage::Resign,
+            Some("abort") => ClientMessage::Abort,
+            Some("draw") => match tokens.next() {
+                Some("offer") => ClientMessage::OfferDraw,
+                Some("accept") => ClientMessage::AcceptDraw,
+                Some("decline") => ClientMessage::DeclineDraw,
+                Some("claim") => ClientMessage::ClaimDraw,
+                _ => return Err(malformed()),
+            },
+            Some("resync") => ClientMessage::RequestSync,
+            _ => return Err(malformed()),
+        };
+        match tokens.next() {
+            Some(_) => Err(malformed()),
+            None => Ok(message),
+        }
+    }
+}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientMessage::Create { color, control } => {
+                write!(f, "create {}", color_name(*color))?;
+                if let Some(control) = control {
+                    write!(f, " {control}")?;
+                }
+                Ok(())
+            }
+            ClientMessage::Join(room) => write!(f, "join {room}"),
+            ClientMessage::Watch(room) => write!(f, "watch {room}"),
+            ClientMessage::Move(mv) => write!(f, "move {mv}"),
+            ClientMessage::Resign => f.write_str("resign"),
+            ClientMessage::Abort => f.write_str("abort"),
+            ClientMessage::OfferDraw => f.write_str("draw offer"),
+            ClientMessage::AcceptDraw => f.write_str("draw accept"),
+            ClientMessage::DeclineDraw => f.write_str("draw decline"),
+            ClientMessage::ClaimDraw => f.write_str("draw claim"),
+            ClientMessage::RequestSync => f.write_str("resync"),
+        }
+    }
+}
+
+/// A message from the server. Everything that happens in a room is sent to
+/// everyone in it except the client that caused it.
+#[derive(Clone, Debug)]
+pub enum ServerMessage {
+    /// `joined <code> <white|black|spectator> [<time control>]`: the room
+    /// the client is in, its seat and the game's time control.
+    Joined {
+        room: String,
+        seat: Seat,
+        control: Option<TimeControl>,
+    },
+    /// `player <white|black> joined|left`.
+    Presence {
+        color: PieceColor,
+        present: bool,
+    },
+    /// `game <fen> moves <uci>...`: the whole game, sent on joining and in
+    /// answer to `resync` or a rejected move.
+    Game {
+        initial: Box<Position>,
+        moves: Vec<Move>,
+    },
+    /// `move <uci>`: a move played in the room.
+    Move(String),
+    /// `clock <white ms> <black ms>`: the official time left, sent after
+    /// every move.
+    Clock {
+        white: Duration,
+        black: Duration,
+    },
+    /// `resign <color>`.
+    Resign(PieceColor),
+    /// `abort <color>`.
+    Abort(PieceColor),
+    /// `draw offer|accept|decline|claim <color>`, naming the player who
+    /// acted.
+    OfferDraw(PieceColor),
+    AcceptDraw(PieceColor),
+    DeclineDraw(PieceColor),
+    ClaimDraw(PieceColor),
+    /// `flag <color>`: that side ran out of time.
+    Flag(PieceColor),
+    /// `error <reason>`: a request the server turned down.
+    Error(String),
+}
+
+impl ServerMessage {
+    pub fn parse(text: &str) -> Result<Self, ServerError> {
+        let malformed = || ServerError::Malformed(text.to_string());
+        let mut tokens = text.split_whitespace();
+        let color = |tokens: &mut std::str::SplitWhitespace| {
+            tokens.next().and_then(parse_color).ok_or_else(malformed)
+        };
+        let message = match tokens.next() {
+            Some("joined") => {
+                let room = tokens.next().ok_or_else(malformed)?.to_string();
+                let seat = match tokens.next() {
+                    Some("spectator") => Seat::Spectator,
+                    other => Seat::Player(other.and_then(parse_color).ok_or_else(malformed)?),
+                };
+                let control = match tokens.next() {
+                    Some(control) => Some(control.parse().map_err(|_| malformed())?),
+                    None => None,
+                };
+                ServerMessage::Joined {
+                    room,
+                    seat,
+                    control,
+                }
+            }
+            Some("player") => {
+                let color = color(&mut tokens)?;
+                let present = match tokens.next() {
+                    Some("joined") => true,
+                    Some("left") => false,
+                    _ => return Err(malformed()),
+                };
+                ServerMessage::Presence { color, present }
+            }
+            Some("game") => {
+                let (initial, moves) = parse_game(tokens)?;
+                return Ok(ServerMessage::Game {
+                    initial: Box::new(initial),
+                    moves,
+                });
+            }
+            Some("move") => ServerMessage::Move(tokens.next().ok_or_else(malformed)?.to_string()),
+            Some("clock") => {
+                let mut millis = || {
+                    tokens
+                        .next()
+                        .and_then(|ms| ms.parse().ok())
+                        .map(Duration::from_millis)
+                        .ok_or_else(malformed)
+                };
+                let white = millis()?;
+                let black = millis()?;
+                ServerMessage::Clock { white, black }
+            }
+            Some("resign") => ServerMessage::Resign(color(&mut tokens)?),
+            Some("abort") => ServerMessage::Abort(color(&mut tokens)?),
+            Some("draw") => match tokens.next() {
+                Some("offer") => ServerMessage::OfferDraw(color(&mut tokens)?),
+                Some("accept") => ServerMessage::AcceptDraw(color(&mut tokens)?),
+                Some("decline") => ServerMessage::DeclineDraw(color(&mut tokens)?),
+                Some("claim") => ServerMessage::ClaimDraw(color(&mut tokens)?),
+                _ => return Err(malformed()),
+            },
+            Some("flag") => ServerMessage::Flag(color(&mut tokens)?),
+            Some("error") => ServerMessage::Error(tokens.by_ref().collect::<Vec<_>>().join(" ")),
+            _ => return Err(malformed()),
+        };
+        match tokens.next() {
+            Some(_) => Err(malformed()),
+            None => Ok(message),
+        }
+    }
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerMessage::Joined {
+                room,
+                seat,
+                control,
+            } => {
+                write!(f, "joined {room} {seat}")?;
+                if let Some(control) = control {
+                    write!(f, " {control}")?;
+                }
+                Ok(())
+            }
+            ServerMessage::Presence { color, present } => {
+                let presence = if *present { "joined" } else { "left" };
+                write!(f, "player {} {presence}", color_name(*color))
+            }
+            ServerMessage::Game { initial, moves } => {
+                write!(f, "game {} moves", initial.to_fen())?;
+                for mv in moves {
+                    write!(f, " {mv}")?;
+                }
+                Ok(())
+            }
+            ServerMessage::Move(mv) => write!(f, "move {mv}"),
+            ServerMessage::Clock { white, black } => {
+                write!(f, "clock {} {}", white.as_millis(), black.as_millis())
+            }
+            ServerMessage::Resign(color) => write!(f, "resign {}", color_name(*color)),
+            ServerMessage::Abort(color) => write!(f, "abort {}", color_name(*color)),
+            ServerMessage::OfferDraw(color) => write!(f, "draw offer {}", color_name(*color)),
+            ServerMessage::AcceptDraw(color) => write!(f, "draw accept {}", color_name(*color)),
+            ServerMessage::DeclineDraw(color) => write!(f, "draw decline {}", color_name(*color)),
+            ServerMessage::ClaimDraw(color) => write!(f, "draw claim {}", color_name(*color)),
+            ServerMessage::Flag(color) => write!(f, "flag {}", color_name(*color)),
+            ServerMessage::Error(reason) => write!(f, "error {reason}"),
+        }
+    }
+}
+
+fn parse_color(token: &str) -> Option<PieceColor> {
+    match token {
+        "white" => Some(PieceColor::White),
+        "black" => Some(PieceColor::Black),
+        _ => None,
+    }
+}
+
+fn color_name(color: PieceColor) -> &'static str {
+    match color {
+        PieceColor::White => "white",
+        PieceColor::Black => "black",
+    }
+}
+
+/// A starting FEN, the `moves` keyword and the moves played from it.
+fn parse_game<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+) -> Result<(Position, Vec<Move>), ServerError> {
+    let fen: Vec<&str> = tokens.by_ref().take_while(|&t| t != "moves").collect();
+    let initial = Position::from_fen(&fen.join(" ")).map_err(ServerError::InvalidFen)?;
+    // `take_while` consumed the `moves` keyword.
+    let mut position = initial.clone();
+    let mut moves = Vec::new();
+    for uci in tokens {
+        let mv = position
+            .parse_uci(uci)
+            .ok_or_else(|| ServerError::IllegalMove(uci.to_string()))?;
+        position.make_move(mv);
+        moves.push(mv);
+    }
+    Ok((initial, moves))
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tungstenite::WebSocket;
+
+use crate::lobby::{ClientId, Lobby};
+use crate::protocol::{ClientMessage, ServerError, ServerMessage};
+use crate::socket::{POLL, handshake_error, read_text, send};
+
+/// How often the server looks for new connections and runs the clocks.
+const TICK: Duration = Duration::from_millis(10);
+
+/// A connection that ended on an error. The server carries on without it;
+/// see [`Server::on_error`].
+#[derive(Debug)]
+pub enum ConnectionError {
+    /// The WebSocket handshake failed, before the client got an id.
+    Handshake(ServerError),
+    /// A client's connection failed.
+    Dropped(ClientId, ServerError),
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::Handshake(err) => write!(f, "handshake failed: {err}"),
+            ConnectionError::Dropped(client, err) => write!(f, "client {client} dropped: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+type ErrorReport = Arc<dyn Fn(ConnectionError) + Send + Sync>;
+
+/// The game server: a listening socket and the lobby every connection
+/// shares. Each connection gets a thread of its own.
+pub struct Server {
+    listener: TcpListener,
+    lobby: Arc<Mutex<Lobby>>,
+    report: ErrorReport,
+}
+
+impl Server {
+    /// Listens at `address`, such as `0.0.0.0:7879`; port 0 picks a free one.
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self, ServerError> {
+        let listener = TcpListener::bind(address).map_err(ServerError::Io)?;
+        listener.set_nonblocking(true).map_err(ServerError::Io)?;
+        Ok(Self {
+            listener,
+            lobby: Arc::new(Mutex::new(Lobby::new())),
+            report: Arc::new(|_| {}),
+        })
+    }
+
+    /// Hands failed connections to `report`, from the thread that served
+    /// them. They are ignored otherwise.
+    pub fn on_error(mut self, report: impl Fn(ConnectionError) + Send + Sync + 'static) -> Self {
+        self.report = Arc::new(report);
+        self
+    }
+
+    /// The address actually listened at.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+
+    /// Serves clients until the process ends.
+    pub fn run(self) -> Result<(), ServerError> {
+        let mut last_tick = Instant::now();
+        loop {
+            loop {
+                match self.listener.accept() {
+                    Ok((stream, _)) => {
+                        let lobby = Arc::clone(&self.lobby);
+                        let report = Arc::clone(&self.report);
+                        thread::spawn(move || serve(stream, &lobby, &*report));
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(err) => return Err(ServerError::Io(err)),
+                }
+            }
+
+            let now = Instant::now();
+            lock(&self.lobby).tick(now - last_tick);
+            last_tick = now;
+            thread::sleep(TICK);
+        }
+    }
+}
+
+fn lock(lobby: &Mutex<Lobby>) -> MutexGuard<'_, Lobby> {
+    // A connection thread that panicked leaves the lobby as it was between
+    // two messages, which is still consistent.
+    lobby.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs one client's connection until it closes.
+fn serve(stream: TcpStream, lobby: &Mutex<Lobby>, report: &dyn Fn(ConnectionError)) {
+    let mut websocket = match accept(stream) {
+        Ok(websocket) => websocket,
+        Err(err) => return report(ConnectionError::Handshake(err)),
+    };
+    let (outbox, messages) = mpsc::channel();
+    let client = lock(lobby).connect(outbox);
+
+    let result = talk(&mut websocket, lobby, client, &messages);
+    lock(lobby).disconnect(client);
+    if let Err(err) = result
+        && !matches!(err, ServerError::Disconnected)
+    {
+        report(ConnectionError::Dropped(client, err));
+    }
+    let _ = websocket.close(None);
+    let _ = websocket.flush();
+}
+
+/// Hands the client's messages to the lobby and sends it what the lobby has
+/// to say, until the connection fails.
+fn talk(
+    websocket: &mut WebSocket<TcpStream>,
+    lobby: &Mutex<Lobby>,
+    client: ClientId,
+    messages: &Receiver<ServerMessage>,
+) -> Result<(), ServerError> {
+    loop {
+        if let Some(text) = read_text(websocket)? {
+            match ClientMessage::parse(&text) {
+                Ok(message) => lock(lobby).handle(client, message),
+                Err(err) => send(websocket, &ServerMessage::Error(err.to_string()))?,
+            }
+        }
+        loop {
+            match messages.try_recv() {
+                Ok(message) => send(websocket, &message)?,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Err(ServerError::Disconnected),
+            }
+        }
+    }
+}
+
+fn accept(stream: TcpStream) -> Result<WebSocket<TcpStream>, ServerError> {
+    stream.set_nonblocking(false).map_err(ServerError::Io)?;
+    stream.set_nodelay(true).map_err(ServerError::Io)?;
+    let websocket = tungstenite::accept(stream).map_err(handshake_error)?;
+    websocket
+        .get_ref()
+        .set_read_timeout(Some(POLL))
+        .map_err(ServerError::Io)?;
+    Ok(websocket)
+}
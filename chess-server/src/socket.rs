@@ -0,0 +1,54 @@
+//! What the server and its clients share: reading and writing protocol lines
+//! as WebSocket text frames over a stream with a short read timeout.
+
+use std::io;
+use std::net::TcpStream;
+use std::time::Duration;
+
+use tungstenite::handshake::{HandshakeError, HandshakeRole};
+use tungstenite::{Message, WebSocket};
+
+use crate::protocol::ServerError;
+
+/// How long a connection waits for a message before checking its outbox.
+pub(crate) const POLL: Duration = Duration::from_millis(10);
+
+pub(crate) fn handshake_error<R: HandshakeRole>(err: HandshakeError<R>) -> ServerError {
+    match err {
+        HandshakeError::Failure(err) => ServerError::WebSocket(err),
+        // Only non-blocking streams are interrupted.
+        HandshakeError::Interrupted(_) => ServerError::Io(io::ErrorKind::WouldBlock.into()),
+    }
+}
+
+/// The next text frame, or `None` if nothing arrived within the read
+/// timeout. Other frames are answered by tungstenite itself.
+pub(crate) fn read_text(
+    websocket: &mut WebSocket<TcpStream>,
+) -> Result<Option<String>, ServerError> {
+    match websocket.read() {
+        Ok(Message::Text(text)) => Ok(Some(text.to_string())),
+        Ok(_) => Ok(None),
+        Err(tungstenite::Error::Io(err))
+            if matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+            Err(ServerError::Disconnected)
+        }
+        Err(err) => Err(ServerError::WebSocket(err)),
+    }
+}
+
+pub(crate) fn send(
+    websocket: &mut WebSocket<TcpStream>,
+    message: &impl ToString,
+) -> Result<(), ServerError> {
+    websocket
+        .send(Message::text(message.to_string()))
+        .map_err(ServerError::WebSocket)
+}
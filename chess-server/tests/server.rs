@@ -0,0 +1,444 @@
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess_core::PieceColor;
+use chess_server::{
+    ClientMessage, ConnectionError, Lobby, ROOM_EXPIRY, Seat, Server, ServerConnection,
+    ServerError, ServerMessage,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A server on a free localhost port, running until the test ends.
+fn start_server() -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    address
+}
+
+fn connect(address: SocketAddr) -> ServerConnection {
+    ServerConnection::connect(&address.to_string()).unwrap()
+}
+
+/// The next message from the server.
+fn next(connection: &mut ServerConnection) -> ServerMessage {
+    connection
+        .recv_timeout(TIMEOUT)
+        .unwrap()
+        .expect("the server did not answer")
+}
+
+/// Reads messages until one matches, returning it.
+fn expect(
+    connection: &mut ServerConnection,
+    matches: impl Fn(&ServerMessage) -> bool,
+) -> ServerMessage {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match connection.recv_timeout(remaining).unwrap() {
+            Some(message) if matches(&message) => return message,
+            Some(_) => {}
+            None => panic!("timed out"),
+        }
+    }
+}
+
+/// Creates a room as `color` and returns its code.
+fn create(connection: &mut ServerConnection, color: PieceColor, control: Option<&str>) -> String {
+    let control = control.map(|control| control.parse().unwrap());
+    connection
+        .send(ClientMessage::Create { color, control })
+        .unwrap();
+    let ServerMessage::Joined { room, seat, .. } = next(connection) else {
+        panic!("not joined");
+    };
+    assert_eq!(seat, Seat::Player(color));
+    room
+}
+
+fn join(connection: &mut ServerConnection, message: ClientMessage) -> Seat {
+    connection.send(message).unwrap();
+    let ServerMessage::Joined { seat, .. } = next(connection) else {
+        panic!("not joined");
+    };
+    seat
+}
+
+fn play(connection: &mut ServerConnection, uci: &str) {
+    connection.send(ClientMessage::Move(uci.into())).unwrap();
+}
+
+fn is_move(uci: &str) -> impl Fn(&ServerMessage) -> bool + '_ {
+    move |message| matches!(message, ServerMessage::Move(mv) if mv == uci)
+}
+
+/// A server with a room, a player on each side and a spectator.
+fn full_room() -> (ServerConnection, ServerConnection, ServerConnection) {
+    let address = start_server();
+    let mut white = connect(address);
+    let code = create(&mut white, PieceColor::White, None);
+    let mut black = connect(address);
+    assert_eq!(
+        join(&mut black, ClientMessage::Join(code.clone())),
+        Seat::Player(PieceColor::Black)
+    );
+    let mut spectator = connect(address);
+    assert_eq!(
+        join(&mut spectator, ClientMessage::Join(code)),
+        Seat::Spectator
+    );
+    expect(&mut white, |message| {
+        matches!(message, ServerMessage::Presence { present: true, .. })
+    });
+    (white, black, spectator)
+}
+
+#[test]
+fn messages_round_trip() {
+    for line in [
+        "create white",
+        "create black 5+3",
+        "join ABC234",
+        "watch ABC234",
+        "move e7e8q",
+        "resign",
+        "abort",
+        "draw offer",
+        "draw accept",
+        "draw decline",
+        "draw claim",
+        "resync",
+    ] {
+        assert_eq!(ClientMessage::parse(line).unwrap().to_string(), line);
+    }
+    for line in [
+        "joined ABC234 white",
+        "joined ABC234 spectator 15d5",
+        "player black joined",
+        "player white left",
+        "game rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves e2e4",
+        "move e2e4",
+        "clock 300000 297500",
+        "resign black",
+        "abort white",
+        "draw offer white",
+        "draw accept black",
+        "draw decline black",
+        "draw claim white",
+        "flag white",
+        "error no room ABC234",
+    ] {
+        assert_eq!(ServerMessage::parse(line).unwrap().to_string(), line);
+    }
+}
+
+#[test]
+fn rejects_bad_lines() {
+    for line in [
+        "",
+        "create",
+        "create red",
+        "create white forever",
+        "join",
+        "move",
+        "draw",
+        "resign now",
+    ] {
+        assert!(
+            matches!(ClientMessage::parse(line), Err(ServerError::Malformed(_))),
+            "{line:?} was accepted"
+        );
+    }
+    for line in ["joined", "joined ABC234 referee", "player white", "clock 1"] {
+        assert!(
+            matches!(ServerMessage::parse(line), Err(ServerError::Malformed(_))),
+            "{line:?} was accepted"
+        );
+    }
+}
+
+#[test]
+fn joining_sends_the_game_and_announces_the_player() {
+    let address = start_server();
+    let mut white = connect(address);
+    let code = create(&mut white, PieceColor::White, Some("5+3"));
+    assert!(matches!(next(&mut white), ServerMessage::Game { moves, .. } if moves.is_empty()));
+
+    let mut black = connect(address);
+    black
+        .send(ClientMessage::Join(code.to_lowercase()))
+        .unwrap();
+    let ServerMessage::Joined {
+        room,
+        seat,
+        control,
+    } = next(&mut black)
+    else {
+        panic!("not joined");
+    };
+    assert_eq!(room, code);
+    assert_eq!(seat, Seat::Player(PieceColor::Black));
+    assert_eq!(control, Some("5+3".parse().unwrap()));
+    assert!(matches!(next(&mut black), ServerMessage::Game { .. }));
+    assert!(matches!(
+        next(&mut black),
+        ServerMessage::Clock { white, black } if white == black
+    ));
+    assert!(matches!(
+        next(&mut black),
+        ServerMessage::Presence {
+            color: PieceColor::White,
+            present: true
+        }
+    ));
+    expect(&mut white, |message| {
+        matches!(
+            message,
+            ServerMessage::Presence {
+                color: PieceColor::Black,
+                present: true
+            }
+        )
+    });
+}
+
+#[test]
+fn unknown_rooms_are_refused() {
+    let address = start_server();
+    let mut client = connect(address);
+    client.send(ClientMessage::Join("ZZZZZZ".into())).unwrap();
+    assert!(
+        matches!(next(&mut client), ServerMessage::Error(reason) if reason == "no room ZZZZZZ")
+    );
+}
+
+#[test]
+fn moves_reach_the_opponent_and_spectators() {
+    let (mut white, mut black, mut spectator) = full_room();
+    play(&mut white, "e2e4");
+    expect(&mut black, is_move("e2e4"));
+    expect(&mut spectator, is_move("e2e4"));
+    play(&mut black, "c7c5");
+    expect(&mut white, is_move("c7c5"));
+    expect(&mut spectator, is_move("c7c5"));
+
+    // Asking again gives the whole game so far.
+    spectator.send(ClientMessage::RequestSync).unwrap();
+    let ServerMessage::Game { moves, .. } = expect(&mut spectator, |message| {
+        matches!(message, ServerMessage::Game { .. })
+    }) else {
+        unreachable!()
+    };
+    let moves: Vec<String> = moves.iter().map(ToString::to_string).collect();
+    assert_eq!(moves, ["e2e4", "c7c5"]);
+}
+
+#[test]
+fn illegal_moves_are_answered_with_the_game() {
+    let (mut white, mut black, mut spectator) = full_room();
+    // Out of turn, then not a legal move.
+    play(&mut black, "e7e5");
+    assert!(matches!(
+        expect(&mut black, |message| matches!(message, ServerMessage::Error(_))),
+        ServerMessage::Error(reason) if reason == "illegal move e7e5"
+    ));
+    assert!(matches!(next(&mut black), ServerMessage::Game { moves, .. } if moves.is_empty()));
+    play(&mut white, "e2e5");
+    expect(&mut white, |message| {
+        matches!(message, ServerMessage::Error(_))
+    });
+
+    // Nor can spectators move.
+    play(&mut spectator, "e2e4");
+    assert!(matches!(
+        expect(&mut spectator, |message| matches!(message, ServerMessage::Error(_))),
+        ServerMessage::Error(reason) if reason == "spectators cannot move"
+    ));
+
+    play(&mut white, "d2d4");
+    expect(&mut black, is_move("d2d4"));
+}
+
+#[test]
+fn resignation_and_draws_are_relayed() {
+    let (mut white, mut black, mut spectator) = full_room();
+    white.send(ClientMessage::OfferDraw).unwrap();
+    expect(&mut black, |message| {
+        matches!(message, ServerMessage::OfferDraw(PieceColor::White))
+    });
+    // One offer at a time, from either side.
+    black.send(ClientMessage::OfferDraw).unwrap();
+    assert!(matches!(
+        expect(&mut black, |message| matches!(message, ServerMessage::Error(_))),
+        ServerMessage::Error(reason) if reason == "a draw offer is already pending"
+    ));
+    black.send(ClientMessage::DeclineDraw).unwrap();
+    expect(&mut white, |message| {
+        matches!(message, ServerMessage::DeclineDraw(PieceColor::Black))
+    });
+
+    black.send(ClientMessage::Resign).unwrap();
+    expect(&mut white, |message| {
+        matches!(message, ServerMessage::Resign(PieceColor::Black))
+    });
+    expect(&mut spectator, |message| {
+        matches!(message, ServerMessage::Resign(PieceColor::Black))
+    });
+
+    // The game is over.
+    play(&mut white, "e2e4");
+    expect(&mut white, |message| {
+        matches!(message, ServerMessage::Error(_))
+    });
+}
+
+/// A move from each side, each waiting until the other has seen it.
+fn exchange(white: &mut ServerConnection, black: &mut ServerConnection, ours: &str, theirs: &str) {
+    play(white, ours);
+    expect(black, is_move(ours));
+    play(black, theirs);
+    expect(white, is_move(theirs));
+}
+
+#[test]
+fn repetition_is_a_draw_once_claimed() {
+    let (mut white, mut black, mut spectator) = full_room();
+    exchange(&mut white, &mut black, "g1f3", "g8f6");
+    exchange(&mut white, &mut black, "f3g1", "f6g8");
+    black.send(ClientMessage::ClaimDraw).unwrap();
+    assert!(matches!(
+        expect(&mut black, |message| matches!(message, ServerMessage::Error(_))),
+        ServerMessage::Error(reason) if reason == "no draw to claim"
+    ));
+
+    // Threefold repetition leaves the game going until someone claims it.
+    exchange(&mut white, &mut black, "g1f3", "g8f6");
+    exchange(&mut white, &mut black, "f3g1", "f6g8");
+    black.send(ClientMessage::ClaimDraw).unwrap();
+    expect(&mut white, |message| {
+        matches!(message, ServerMessage::ClaimDraw(PieceColor::Black))
+    });
+    expect(&mut spectator, |message| {
+        matches!(message, ServerMessage::ClaimDraw(PieceColor::Black))
+    });
+    play(&mut white, "g1f3");
+    assert!(matches!(
+        expect(&mut white, |message| matches!(message, ServerMessage::Error(_))),
+        ServerMessage::Error(reason) if reason == "illegal move g1f3"
+    ));
+}
+
+#[test]
+fn the_server_runs_the_clock() {
+    let address = start_server();
+    let mut white = connect(address);
+    let code = create(&mut white, PieceColor::White, Some("1s"));
+    let mut black = connect(address);
+    join(&mut black, ClientMessage::Join(code));
+
+    // Black's clock starts after White's first move, and runs out.
+    play(&mut white, "e2e4");
+    expect(&mut black, is_move("e2e4"));
+    expect(
+        &mut black,
+        |message| matches!(message, ServerMessage::Clock { white, black } if white == black),
+    );
+    expect(&mut white, |message| {
+        matches!(message, ServerMessage::Flag(PieceColor::Black))
+    });
+    expect(&mut black, |message| {
+        matches!(message, ServerMessage::Flag(PieceColor::Black))
+    });
+}
+
+#[test]
+fn a_player_who_leaves_can_take_the_seat_back() {
+    let address = start_server();
+    let mut white = connect(address);
+    let code = create(&mut white, PieceColor::White, None);
+    let mut black = connect(address);
+    join(&mut black, ClientMessage::Join(code.clone()));
+    play(&mut white, "e2e4");
+    expect(&mut black, is_move("e2e4"));
+    drop(black);
+    expect(&mut white, |message| {
+        matches!(
+            message,
+            ServerMessage::Presence {
+                color: PieceColor::Black,
+                present: false
+            }
+        )
+    });
+
+    let mut black = connect(address);
+    assert_eq!(
+        join(&mut black, ClientMessage::Join(code)),
+        Seat::Player(PieceColor::Black)
+    );
+    assert!(matches!(next(&mut black), ServerMessage::Game { moves, .. } if moves.len() == 1));
+    play(&mut black, "e7e5");
+    expect(&mut white, is_move("e7e5"));
+}
+
+#[test]
+fn empty_rooms_expire() {
+    let mut lobby = Lobby::new();
+    let (outbox, messages) = mpsc::channel();
+    let client = lobby.connect(outbox);
+    lobby.handle(
+        client,
+        ClientMessage::Create {
+            color: PieceColor::Black,
+            control: None,
+        },
+    );
+    let ServerMessage::Joined { room, .. } = messages.recv().unwrap() else {
+        panic!("not joined");
+    };
+    lobby.disconnect(client);
+
+    lobby.tick(ROOM_EXPIRY / 2);
+    assert_eq!(lobby.room_count(), 1);
+    // The room can still be rejoined until it expires.
+    let (outbox, messages) = mpsc::channel();
+    let client = lobby.connect(outbox);
+    lobby.handle(client, ClientMessage::Join(room));
+    assert!(matches!(
+        messages.recv().unwrap(),
+        ServerMessage::Joined {
+            seat: Seat::Player(_),
+            ..
+        }
+    ));
+    lobby.disconnect(client);
+
+    lobby.tick(ROOM_EXPIRY);
+    assert_eq!(lobby.room_count(), 0);
+}
+
+#[test]
+fn failed_connections_are_reported() {
+    let (errors, reported) = mpsc::channel();
+    let server = Server::bind("127.0.0.1:0")
+        .unwrap()
+        .on_error(move |err| errors.send(err).unwrap());
+    let address = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(b"not a websocket\r\n\r\n").unwrap();
+    assert!(matches!(
+        reported.recv_timeout(TIMEOUT).unwrap(),
+        ConnectionError::Handshake(_)
+    ));
+
+    // The server still takes clients.
+    let mut white = connect(address);
+    create(&mut white, PieceColor::White, None);
+}
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::chess_piece_plugin::PIECE_Z;
+
+/// Depth of a moving piece, so it passes over the pieces it crosses.
+const MOVING_Z: f32 = PIECE_Z + 1.0;
+
+/// How long moves take on screen and how they ease in and out. A zero
+/// duration makes moves instant.
+#[derive(Resource, Clone, Copy)]
+pub struct AnimationSettings {
+    pub duration: Duration,
+    pub easing: EaseFunction,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_millis(200),
+            easing: EaseFunction::CubicInOut,
+        }
+    }
+}
+
+impl AnimationSettings {
+    /// How far along an animation that has run for `elapsed` is, from 0 to 1.
+    fn progress(&self, elapsed: Duration) -> f32 {
+        if elapsed >= self.duration {
+            1.0
+        } else {
+            elapsed.as_secs_f32() / self.duration.as_secs_f32()
+        }
+    }
+}
+
+/// Carries a piece from where it is drawn to its new square. A promoted
+/// pawn keeps its pawn sprite until it arrives.
+#[derive(Component)]
+pub struct MoveTween {
+    from: Vec3,
+    to: Vec3,
+    elapsed: Duration,
+    promotion: Option<Sprite>,
+}
+
+impl MoveTween {
+    /// A tween from `from`, where the piece is currently drawn, to the centre
+    /// of its square at `to`. `promotion` is the sprite to show on arrival.
+    pub fn new(from: Vec3, to: Vec2, promotion: Option<Sprite>) -> Self {
+        Self {
+            from: from.truncate().extend(MOVING_Z),
+            to: to.extend(PIECE_Z),
+            elapsed: Duration::ZERO,
+            promotion,
+        }
+    }
+}
+
+/// Fades a captured piece out, then despawns it. The entity has already
+/// lost its `Piece` and `GridPosition`, so the game no longer sees it.
+#[derive(Component, Default)]
+pub struct FadeOut {
+    elapsed: Duration,
+}
+
+/// Run condition for systems that read input or make moves: they wait until
+/// the board has caught up with the last move.
+pub fn animations_finished(animating: Query<(), Or<(With<MoveTween>, With<FadeOut>)>>) -> bool {
+    animating.is_empty()
+}
+
+pub fn move_tween_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<AnimationSettings>,
+    mut tweens: Query<(Entity, &mut MoveTween, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut tween, mut transform, mut sprite) in tweens.iter_mut() {
+        tween.elapsed += time.delta();
+        let t = settings.progress(tween.elapsed);
+        if t < 1.0 {
+            let eased = settings.easing.sample_clamped(t);
+            transform.translation = tween.from.lerp(tween.to, eased);
+            continue;
+        }
+
+        transform.translation = tween.to;
+        if let Some(promoted) = tween.promotion.take() {
+            *sprite = promoted;
+        }
+        commands.entity(entity).remove::<MoveTween>();
+    }
+}
+
+pub fn fade_out_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<AnimationSettings>,
+    mut fading: Query<(Entity, &mut FadeOut, &mut Sprite)>,
+) {
+    for (entity, mut fade, mut sprite) in fading.iter_mut() {
+        fade.elapsed += time.delta();
+        let t = settings.progress(fade.elapsed);
+        if t < 1.0 {
+            sprite.color = sprite.color.with_alpha(1.0 - t);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! The built-in engine speaking UCI over stdin and stdout, for chess GUIs,
+//! tournament managers and headless benchmarks:
+//!
+//! ```text
+//! cargo run --release --bin uci
+//! ```
+
+use std::io::{self, BufRead};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chess_core::{GoParams, GuiCommand, Position, Searcher, info_line};
+
+const NAME: &str = concat!("Bevy Chess ", env!("CARGO_PKG_VERSION"));
+const DEFAULT_HASH_MB: usize = 16;
+const MAX_HASH_MB: usize = 1024;
+
+struct Engine {
+    position: Position,
+    hash_mb: usize,
+    /// The idle searcher; it moves into the search thread while a search
+    /// runs and comes back when the thread is joined.
+    searcher: Option<Searcher>,
+    search: Option<RunningSearch>,
+}
+
+struct RunningSearch {
+    thread: JoinHandle<Searcher>,
+    stop: Arc<AtomicBool>,
+}
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match GuiCommand::parse(&line) {
+            Ok(GuiCommand::Quit) => break,
+            Ok(command) => engine.handle(command),
+            Err(err) => println!("info string {err}"),
+        }
+    }
+    engine.stop();
+}
+
+impl Engine {
+    fn new() -> Self {
+        Self {
+            position: Position::new(),
+            hash_mb: DEFAULT_HASH_MB,
+            searcher: None,
+            search: None,
+        }
+    }
+
+    fn handle(&mut self, command: GuiCommand) {
+        match command {
+            GuiCommand::Uci => {
+                println!("id name {NAME}");
+                println!("id author the rust-bevy contributors");
+                println!(
+                    "option name Hash type spin default {DEFAULT_HASH_MB} min 1 max {MAX_HASH_MB}"
+                );
+                println!("option name Clear Hash type button");
+                println!("uciok");
+            }
+            GuiCommand::IsReady => println!("readyok"),
+            GuiCommand::UciNewGame => {
+                self.stop();
+                self.searcher().clear();
+                self.position = Position::new();
+            }
+            GuiCommand::Position(position) => {
+                self.stop();
+                self.position = *position;
+            }
+            GuiCommand::Go(params) => {
+                self.stop();
+                self.go(params);
+            }
+            GuiCommand::Stop => self.stop(),
+            GuiCommand::SetOption { name, value } => {
+                self.stop();
+                self.set_option(&name, value.as_deref());
+            }
+            GuiCommand::Quit => {}
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) {
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => match value.and_then(|v| v.parse::<usize>().ok()) {
+                Some(mb) => {
+                    self.hash_mb = mb.clamp(1, MAX_HASH_MB);
+                    self.searcher = Some(Searcher::with_hash_size(self.hash_mb));
+                }
+                None => println!("info string Hash needs a size in MB"),
+            },
+            "clear hash" => self.searcher().clear(),
+            _ => println!("info string unknown option '{name}'"),
+        }
+    }
+
+    /// The idle searcher, created on first use so that a `Hash` option sent
+    /// before the first search does not allocate the table twice.
+    fn searcher(&mut self) -> &mut Searcher {
+        let hash_mb = self.hash_mb;
+        self.searcher
+            .get_or_insert_with(|| Searcher::with_hash_size(hash_mb))
+    }
+
+    /// Searches the current position on a separate thread, so that `stop`
+    /// and `isready` are answered while it runs.
+    fn go(&mut self, params: GoParams) {
+        let hash_mb = self.hash_mb;
+        let mut searcher = self
+            .searcher
+            .take()
+            .unwrap_or_else(|| Searcher::with_hash_size(hash_mb));
+        let stop = searcher.stop_flag();
+        let position = self.position.clone();
+        let limits = params.limits(position.side_to_move());
+
+        let stopped = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            let info = searcher.search_with(&position, limits, |info| {
+                println!("{}", info_line(info));
+            });
+            // In infinite mode the GUI expects `bestmove` only after `stop`.
+            while params.infinite && !stopped.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            match info.best_move() {
+                Some(mv) => println!("bestmove {mv}"),
+                None => println!("bestmove (none)"),
+            }
+            searcher
+        });
+        self.search = Some(RunningSearch { thread, stop });
+    }
+
+    /// Ends the running search, if any, and waits for its `bestmove`.
+    fn stop(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        // The search lowers the flag when it starts, so keep raising it
+        // until the thread has finished.
+        while !search.thread.is_finished() {
+            search.stop.store(true, Ordering::Relaxed);
+            thread::sleep(Duration::from_millis(1));
+        }
+        self.searcher = Some(search.thread.join().expect("search thread panicked"));
+    }
+}
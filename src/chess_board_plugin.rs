@@ -1,20 +1,49 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, sprite::Anchor, window::PrimaryWindow};
 
-pub const SCREEN_WIDTH: f32 = 1200.0;
-pub const SCREEN_HEIGHT: f32 = 800.0;
+use crate::{
+    components::{GridPosition, Piece, PieceColor},
+    computer_player::ComputerPlayer,
+    promotion_picker::PromotionPicker,
+    resources::GameState,
+    theme::ActiveTheme,
+};
+
+/// Size of a tile in world units. The board is centred on the world origin
+/// and the camera zooms so that it fits the window.
 pub const TILE_SIZE: f32 = 100.0;
+const BOARD_SIZE: f32 = 8.0 * TILE_SIZE;
+
+/// Room the side panel keeps beside a landscape board, or below a portrait
+/// one, in logical pixels.
+const PANEL_MIN_WIDTH: f32 = 300.0;
+const PANEL_MIN_HEIGHT: f32 = 250.0;
+/// The board never shrinks below this, even if the panel has to overlap it.
+const MIN_BOARD_PIXELS: f32 = 200.0;
 
 pub struct ChessBoardPlugin;
 
-const DARK_TILE: Color = Color::srgb_u8(32, 32, 35);
-const LIGHT_TILE: Color = Color::srgb_u8(235, 235, 235);
+/// Gap between a coordinate label and the corner of its tile.
+const LABEL_PADDING: f32 = 4.0;
+const LABEL_FONT_SIZE: f32 = 16.0;
 
+/// A board tile and the square it stands for.
 #[derive(Component)]
-pub struct BoardCell;
+pub struct BoardCell(pub GridPosition);
 
 impl Plugin for ChessBoardPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (setup_camera, setup_chessboard));
+        app.init_resource::<BoardOrientation>()
+            .init_resource::<CoordinateLabels>()
+            .init_resource::<BoardLayout>()
+            .add_systems(Startup, (setup_camera, setup_chessboard))
+            .add_systems(
+                Update,
+                (
+                    board_layout_system,
+                    board_theme_system,
+                    coordinate_label_system,
+                ),
+            );
     }
 }
 
@@ -22,26 +51,304 @@ pub fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2d);
 }
 
-pub fn setup_chessboard(mut commands: Commands) {
+/// Where the board sits in the window, in logical pixels: a square in the
+/// top left corner as large as the window's shorter side allows, with the
+/// side panel to its right in a landscape window or below it in a portrait
+/// one.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoardLayout {
+    pub window: Vec2,
+    pub board: f32,
+}
+
+impl BoardLayout {
+    pub fn new(window: Vec2) -> Self {
+        let board = if window.x >= window.y {
+            window.y.min(window.x - PANEL_MIN_WIDTH)
+        } else {
+            window.x.min(window.y - PANEL_MIN_HEIGHT)
+        };
+        Self {
+            window,
+            board: board.max(MIN_BOARD_PIXELS),
+        }
+    }
+
+    /// The space left for the side panel.
+    pub fn panel(&self) -> Rect {
+        if self.window.x >= self.window.y {
+            Rect::new(self.board, 0.0, self.window.x, self.window.y)
+        } else {
+            Rect::new(0.0, self.board, self.window.x, self.window.y)
+        }
+    }
+}
+
+/// Follows the window size: recomputes the [`BoardLayout`] and zooms and
+/// pans the camera so the board fills its square.
+pub fn board_layout_system(
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut layout: ResMut<BoardLayout>,
+    camera: Single<(&mut Transform, &mut Projection), With<Camera2d>>,
+) {
+    let new_layout = BoardLayout::new(window.size());
+    if *layout == new_layout {
+        return;
+    }
+    *layout = new_layout;
+
+    let (mut transform, mut projection) = camera.into_inner();
+    let scale = BOARD_SIZE / layout.board;
+    if let Projection::Orthographic(ortho) = &mut *projection {
+        ortho.scale = scale;
+    }
+    // Shift the view so the board's centre, the world origin, lands in the
+    // middle of its square rather than of the window.
+    let offset = (layout.window - Vec2::splat(layout.board)) / 2.0 * scale;
+    transform.translation.x = offset.x;
+    transform.translation.y = -offset.y;
+}
+
+pub fn setup_chessboard(
+    mut commands: Commands,
+    orientation: Res<BoardOrientation>,
+    theme: Res<ActiveTheme>,
+) {
+    let colors = &theme.0.board;
     for i in 0..8 {
         for j in 0..8 {
+            let pos = GridPosition::new(i, j);
             commands.spawn((
                 Sprite {
-                    color: if (i + j) % 2 == 0 {
-                        DARK_TILE
-                    } else {
-                        LIGHT_TILE
-                    },
+                    color: colors.tile(pos),
                     custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
                     ..default()
                 },
-                Transform::from_xyz(
-                    i as f32 * TILE_SIZE - SCREEN_WIDTH / 2.0 + TILE_SIZE / 2.0,
-                    j as f32 * TILE_SIZE - SCREEN_HEIGHT / 2.0 + TILE_SIZE / 2.0,
-                    0.0,
-                ),
-                BoardCell,
+                Transform::from_translation(orientation.grid_to_world(pos).extend(0.0)),
+                BoardCell(pos),
+            ));
+        }
+    }
+
+    for i in 0..8 {
+        for label in [CoordinateLabel::File(i), CoordinateLabel::Rank(i)] {
+            commands.spawn((
+                Text2d::new(label.text()),
+                TextFont {
+                    font_size: LABEL_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(colors.contrast(label.square(&orientation))),
+                label.anchor(),
+                Transform::from_translation(label.translation(&orientation)),
+                label,
             ));
         }
     }
 }
+
+/// Whether the file letters and rank numbers are shown; L toggles them.
+#[derive(Resource, Clone, Copy)]
+pub struct CoordinateLabels {
+    pub visible: bool,
+}
+
+impl Default for CoordinateLabels {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+/// A file letter or rank number, drawn inside a corner of the tile at the
+/// board's edge: files along the bottom row, ranks up the left column.
+#[derive(Component, Clone, Copy)]
+pub enum CoordinateLabel {
+    File(u32),
+    Rank(u32),
+}
+
+impl CoordinateLabel {
+    fn text(self) -> String {
+        match self {
+            Self::File(file) => char::from(b'a' + file as u8).to_string(),
+            Self::Rank(rank) => (rank + 1).to_string(),
+        }
+    }
+
+    /// The tile the label is drawn on, which depends on which side is at
+    /// the bottom.
+    fn square(self, orientation: &BoardOrientation) -> GridPosition {
+        let near = match orientation.bottom {
+            PieceColor::White => 0,
+            PieceColor::Black => 7,
+        };
+        match self {
+            Self::File(file) => GridPosition::new(file, near),
+            Self::Rank(rank) => GridPosition::new(near, rank),
+        }
+    }
+
+    /// File letters sit in the bottom right corner, rank numbers in the top
+    /// left.
+    fn anchor(self) -> Anchor {
+        match self {
+            Self::File(_) => Anchor::BOTTOM_RIGHT,
+            Self::Rank(_) => Anchor::TOP_LEFT,
+        }
+    }
+
+    fn translation(self, orientation: &BoardOrientation) -> Vec3 {
+        let corner = Vec2::splat(TILE_SIZE / 2.0 - LABEL_PADDING);
+        let offset = match self {
+            Self::File(_) => Vec2::new(corner.x, -corner.y),
+            Self::Rank(_) => Vec2::new(-corner.x, corner.y),
+        };
+        (orientation.grid_to_world(self.square(orientation)) + offset).extend(1.0)
+    }
+}
+
+/// Repaints the tiles when the theme changes.
+pub fn board_theme_system(theme: Res<ActiveTheme>, mut cells: Query<(&BoardCell, &mut Sprite)>) {
+    if !theme.is_changed() {
+        return;
+    }
+    for (BoardCell(pos), mut sprite) in cells.iter_mut() {
+        sprite.color = theme.0.board.tile(*pos);
+    }
+}
+
+/// L shows or hides the coordinate labels. When the board turns around they
+/// move to the new bottom row and left column, and they are drawn in the
+/// color of the other tiles so they stand out from their own.
+pub fn coordinate_label_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<CoordinateLabels>,
+    orientation: Res<BoardOrientation>,
+    theme: Res<ActiveTheme>,
+    mut labels: Query<(
+        &CoordinateLabel,
+        &mut Transform,
+        &mut TextColor,
+        &mut Visibility,
+    )>,
+) {
+    if keys.just_pressed(KeyCode::KeyL) {
+        settings.visible = !settings.visible;
+    }
+    if !settings.is_changed() && !orientation.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let colors = &theme.0.board;
+
+    for (label, mut transform, mut color, mut visibility) in labels.iter_mut() {
+        transform.translation = label.translation(&orientation);
+        color.0 = colors.contrast(label.square(&orientation));
+        *visibility = if settings.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Which side is drawn at the bottom of the board. Every conversion between
+/// squares and screen positions goes through it, so flipping it and moving
+/// the sprites to match turns the whole board around.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct BoardOrientation {
+    pub bottom: PieceColor,
+    /// For hot-seat play: after each move, turn the board to the side to
+    /// move.
+    pub auto_flip: bool,
+}
+
+impl Default for BoardOrientation {
+    fn default() -> Self {
+        Self::facing(PieceColor::White)
+    }
+}
+
+impl BoardOrientation {
+    /// The board seen from `bottom`'s side.
+    pub fn facing(bottom: PieceColor) -> Self {
+        Self {
+            bottom,
+            auto_flip: false,
+        }
+    }
+
+    /// The tile drawn where `pos` would be with White at the bottom. Turning
+    /// the board half a turn mirrors both axes, so this is its own inverse.
+    fn view(&self, pos: GridPosition) -> GridPosition {
+        match self.bottom {
+            PieceColor::White => pos,
+            PieceColor::Black => GridPosition::new(7 - pos.x, 7 - pos.y),
+        }
+    }
+
+    /// Centre of the tile at `pos`, in world coordinates.
+    pub fn grid_to_world(&self, pos: GridPosition) -> Vec2 {
+        let view = self.view(pos);
+        Vec2::new(view.x as f32 - 3.5, view.y as f32 - 3.5) * TILE_SIZE
+    }
+
+    /// The tile at a point in world coordinates, if it is on the board.
+    pub fn world_to_grid(&self, world: Vec2) -> Option<GridPosition> {
+        let x = (world.x / TILE_SIZE + 4.0).floor() as i32;
+        let y = (world.y / TILE_SIZE + 4.0).floor() as i32;
+        ((0..8).contains(&x) && (0..8).contains(&y))
+            .then(|| self.view(GridPosition::new(x as u32, y as u32)))
+    }
+}
+
+/// World coordinates of the cursor, if it is over the window.
+pub fn cursor_to_world(window: &Window, camera: (&Camera, &GlobalTransform)) -> Option<Vec2> {
+    let (camera, camera_transform) = camera;
+    let cursor = window.cursor_position()?;
+    camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+/// F turns the board around, which also ends auto-flip. Otherwise the board
+/// turns to the player's side when the computer switches sides, or, with
+/// auto-flip in a two-player game, the side to move. Board tiles and pieces
+/// are then moved to their new places.
+pub fn flip_board_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut orientation: ResMut<BoardOrientation>,
+    game_state: Res<GameState>,
+    computer: Res<ComputerPlayer>,
+    picker: Res<PromotionPicker>,
+    mut cells: Query<(&BoardCell, &mut Transform), Without<Piece>>,
+    mut pieces: Query<(&GridPosition, &mut Transform), With<Piece>>,
+) {
+    if picker.is_open() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::KeyF) {
+        orientation.auto_flip = false;
+        orientation.bottom = orientation.bottom.opposite();
+    } else {
+        let bottom = match computer.color {
+            Some(color) if computer.is_changed() && !computer.is_added() => Some(color.opposite()),
+            None if orientation.auto_flip => Some(game_state.position.side_to_move()),
+            _ => None,
+        };
+        if let Some(bottom) = bottom
+            && bottom != orientation.bottom
+        {
+            orientation.bottom = bottom;
+        }
+    }
+
+    if !orientation.is_changed() {
+        return;
+    }
+    for (BoardCell(pos), mut transform) in cells.iter_mut() {
+        transform.translation = orientation.grid_to_world(*pos).extend(0.0);
+    }
+    for (pos, mut transform) in pieces.iter_mut() {
+        let z = transform.translation.z;
+        transform.translation = orientation.grid_to_world(*pos).extend(z);
+    }
+}
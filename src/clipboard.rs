@@ -0,0 +1,9 @@
+//! Thin wrapper over the system clipboard.
+
+pub fn get_text() -> Result<String, arboard::Error> {
+    arboard::Clipboard::new()?.get_text()
+}
+
+pub fn set_text(text: &str) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new()?.set_text(text)
+}
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
-use std::ops::Add;
+use chess_core::Square;
+
+pub use chess_core::{PieceColor, PieceType};
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GridPosition {
@@ -13,29 +15,15 @@ impl GridPosition {
     }
 }
 
-#[repr(usize)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum PieceType {
-    Pawn = 0,
-    Knight = 1,
-    Bishop = 2,
-    Rook = 3,
-    Queen = 4,
-    King = 5,
-}
-
-#[repr(usize)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum PieceColor {
-    White = 7,
-    Black = 14,
+impl From<Square> for GridPosition {
+    fn from(square: Square) -> Self {
+        Self::new(square.file() as u32, square.rank() as u32)
+    }
 }
 
-impl Add<PieceColor> for PieceType {
-    type Output = usize;
-
-    fn add(self, rhs: PieceColor) -> Self::Output {
-        self as usize + rhs as usize
+impl From<GridPosition> for Square {
+    fn from(pos: GridPosition) -> Self {
+        Square::new(pos.x as u8, pos.y as u8)
     }
 }
 
@@ -43,17 +31,16 @@ impl Add<PieceColor> for PieceType {
 pub struct Piece {
     pub color: PieceColor,
     pub kind: PieceType,
-    pub is_first_move: bool,
-    pub just_double_jumped: bool,
 }
 
 impl Piece {
     pub fn new(color: PieceColor, kind: PieceType) -> Self {
-        Self {
-            color,
-            kind,
-            is_first_move: true,
-            just_double_jumped: false,
-        }
+        Self { color, kind }
+    }
+}
+
+impl From<chess_core::Piece> for Piece {
+    fn from(piece: chess_core::Piece) -> Self {
+        Self::new(piece.color, piece.kind)
     }
 }
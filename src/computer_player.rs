@@ -0,0 +1,156 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use chess_core::{SearchInfo, SearchLimits, Searcher};
+
+use crate::{
+    components::{GridPosition, Piece, PieceColor},
+    events::MoveMade,
+    promotion_picker::PromotionPicker,
+    resources::GameState,
+};
+
+/// Which side, if any, the built-in engine plays, and how long it may think
+/// per move.
+#[derive(Resource, Clone, Copy)]
+pub struct ComputerPlayer {
+    pub color: Option<PieceColor>,
+    pub limits: SearchLimits,
+}
+
+impl Default for ComputerPlayer {
+    fn default() -> Self {
+        Self {
+            color: None,
+            limits: SearchLimits {
+                depth: Some(8),
+                movetime: Some(Duration::from_secs(1)),
+                nodes: None,
+            },
+        }
+    }
+}
+
+impl ComputerPlayer {
+    pub fn plays(&self, color: PieceColor) -> bool {
+        self.color == Some(color)
+    }
+
+    /// Off, then Black, then White, then off again.
+    fn cycle(&mut self) {
+        self.color = match self.color {
+            None => Some(PieceColor::Black),
+            Some(PieceColor::Black) => Some(PieceColor::White),
+            Some(PieceColor::White) => None,
+        };
+    }
+
+    pub fn label(&self) -> String {
+        match self.color {
+            Some(color) => format!("Computer: {color:?}"),
+            None => "Computer: Off".to_string(),
+        }
+    }
+}
+
+/// The search running on the compute pool. The searcher travels with the
+/// task and comes back with the result, so its transposition table carries
+/// over from move to move.
+#[derive(Resource, Default)]
+pub struct ComputerSearch {
+    searcher: Option<Searcher>,
+    running: Option<RunningSearch>,
+}
+
+struct RunningSearch {
+    task: Task<(Searcher, SearchInfo)>,
+    stop: Arc<AtomicBool>,
+    /// Number of moves played and position key when the search started,
+    /// to recognise results that no longer fit the game.
+    ply: usize,
+    key: u64,
+}
+
+/// The button that switches the computer between sides.
+#[derive(Component)]
+pub struct ComputerButton;
+
+#[derive(Component)]
+pub struct ComputerButtonText;
+
+/// Collects a finished search and emits its move, and starts a new search
+/// when it is the computer's turn. A search whose game has moved on (undo,
+/// a loaded game, a mode switch) is stopped and discarded.
+pub fn computer_move_system(
+    computer: Res<ComputerPlayer>,
+    mut search: ResMut<ComputerSearch>,
+    mut game_state: ResMut<GameState>,
+    picker: Res<PromotionPicker>,
+    pieces: Query<(Entity, &GridPosition), With<Piece>>,
+    mut move_made_events: MessageWriter<MoveMade>,
+) {
+    let ply = game_state.moves.len();
+    let key = game_state.position.zobrist_key();
+    let our_turn = computer.plays(game_state.position.side_to_move()) && !game_state.is_over();
+
+    if let Some(running) = &mut search.running {
+        if running.ply != ply || running.key != key || !our_turn {
+            running.stop.store(true, Ordering::Relaxed);
+            search.running = None;
+            game_state.update_status();
+        } else if let Some((searcher, info)) = block_on(poll_once(&mut running.task)) {
+            search.running = None;
+            search.searcher = Some(searcher);
+
+            if let Some(mv) = info.best_move() {
+                let from = GridPosition::from(mv.from);
+                if let Some((entity, _)) = pieces.iter().find(|(_, pos)| **pos == from) {
+                    move_made_events.write(MoveMade {
+                        entity,
+                        from,
+                        to: GridPosition::from(mv.to),
+                        promotion: mv.promotion,
+                    });
+                }
+            }
+            return;
+        }
+    }
+
+    if search.running.is_none() && our_turn && !picker.is_open() {
+        let mut searcher = search.searcher.take().unwrap_or_default();
+        let stop = searcher.stop_flag();
+        let position = game_state.position.clone();
+        let limits = computer.limits;
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let info = searcher.search(&position, limits);
+            (searcher, info)
+        });
+
+        search.running = Some(RunningSearch {
+            task,
+            stop,
+            ply,
+            key,
+        });
+        game_state.status = format!("{:?} is thinking...", game_state.position.side_to_move());
+    }
+}
+
+pub fn computer_button_system(
+    mut computer: ResMut<ComputerPlayer>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ComputerButton>)>,
+    mut labels: Query<&mut Text, With<ComputerButtonText>>,
+) {
+    for interaction in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            computer.cycle();
+            for mut text in labels.iter_mut() {
+                text.0 = computer.label();
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    chess_board_plugin::{BoardOrientation, cursor_to_world},
+    chess_piece_plugin::PIECE_Z,
+    components::{GridPosition, Piece},
+    computer_player::ComputerPlayer,
+    events::TileClicked,
+    network::NetworkGame,
+    promotion_picker::PromotionPicker,
+    resources::GameState,
+    server_game::ServerGame,
+};
+
+/// Depth of a piece being dragged, so it passes over every other piece.
+const DRAG_Z: f32 = 10.0;
+
+/// How fast a dropped piece glides onto its square, in pixels per second.
+const SNAP_SPEED: f32 = 3000.0;
+
+/// The piece under the held mouse button, if any.
+#[derive(Resource, Default)]
+pub struct DragState {
+    dragging: Option<Dragging>,
+}
+
+#[derive(Clone, Copy)]
+struct Dragging {
+    entity: Entity,
+    from: GridPosition,
+}
+
+/// Marks a dropped piece on its way to the centre of its square.
+#[derive(Component)]
+pub struct SnapBack;
+
+/// Picks up a piece of the side to move when the mouse is pressed on it,
+/// keeps it under the cursor while the button is held, and drops it on
+/// release. A drop on another square is sent as a click on that square, so
+/// `selection_logic_system` decides whether it is a move; a plain click
+/// leaves the piece selected for click-to-move.
+pub fn drag_system(
+    mut commands: Commands,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    game_state: Res<GameState>,
+    picker: Res<PromotionPicker>,
+    computer: Res<ComputerPlayer>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    orientation: Res<BoardOrientation>,
+    mut drag: ResMut<DragState>,
+    mut pieces: Query<(Entity, &GridPosition, &Piece, &mut Transform)>,
+    mut tile_clicked_events: MessageWriter<TileClicked>,
+) {
+    let cursor = cursor_to_world(&window, *camera);
+    let side_to_move = game_state.position.side_to_move();
+
+    if buttons.just_pressed(MouseButton::Left)
+        && !picker.is_open()
+        && !game_state.is_over()
+        && !computer.plays(side_to_move)
+        && !network
+            .as_ref()
+            .is_some_and(|network| network.waits_for(side_to_move))
+        && !server
+            .as_ref()
+            .is_some_and(|server| server.waits_for(side_to_move))
+        && let Some(tile) = cursor.and_then(|cursor| orientation.world_to_grid(cursor))
+        && let Some((entity, ..)) = pieces
+            .iter()
+            .find(|(_, pos, piece, _)| **pos == tile && piece.color == side_to_move)
+    {
+        drag.dragging = Some(Dragging { entity, from: tile });
+        commands.entity(entity).remove::<SnapBack>();
+    }
+
+    let Some(dragging) = drag.dragging else {
+        return;
+    };
+    let Ok((.., mut transform)) = pieces.get_mut(dragging.entity) else {
+        // The piece was respawned, e.g. by an undo.
+        drag.dragging = None;
+        return;
+    };
+
+    if buttons.pressed(MouseButton::Left) {
+        if let Some(cursor) = cursor {
+            transform.translation = cursor.extend(DRAG_Z);
+        }
+        return;
+    }
+
+    drag.dragging = None;
+    commands.entity(dragging.entity).insert(SnapBack);
+    if let Some(tile) = cursor.and_then(|cursor| orientation.world_to_grid(cursor))
+        && tile != dragging.from
+    {
+        // Pressing the already selected piece deselected it; select it
+        // again so that the drop completes the move.
+        if game_state.selected_position != Some(dragging.from) {
+            tile_clicked_events.write(TileClicked {
+                position: dragging.from,
+            });
+        }
+        tile_clicked_events.write(TileClicked { position: tile });
+    }
+}
+
+/// Glides dropped pieces to the centre of their square: the target square
+/// after a legal move, or back where they came from otherwise.
+pub fn snap_back_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    orientation: Res<BoardOrientation>,
+    mut pieces: Query<(Entity, &GridPosition, &mut Transform), With<SnapBack>>,
+) {
+    let step = SNAP_SPEED * time.delta_secs();
+    for (entity, pos, mut transform) in pieces.iter_mut() {
+        let target = orientation.grid_to_world(*pos);
+        let offset = target - transform.translation.truncate();
+        if offset.length() <= step {
+            transform.translation = target.extend(PIECE_Z);
+            commands.entity(entity).remove::<SnapBack>();
+        } else {
+            transform.translation += (offset.normalize() * step).extend(0.0);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::components::GridPosition;
+use crate::components::{GridPosition, PieceColor, PieceType};
 use bevy::prelude::*;
 
 #[derive(Message)]
@@ -12,9 +12,42 @@ pub struct MoveMade {
     pub entity: Entity,
     pub from: GridPosition,
     pub to: GridPosition,
+    /// The piece a pawn promotes to; `None` for every other move.
+    pub promotion: Option<PieceType>,
+}
+
+/// A pawn move to the last rank that still needs a promotion piece.
+#[derive(Message)]
+pub struct PromotionRequested {
+    pub entity: Entity,
+    pub from: GridPosition,
+    pub to: GridPosition,
 }
 
 #[derive(Message)]
 pub struct TileClicked {
     pub position: GridPosition,
 }
+
+/// A player resigning, negotiating a draw or calling the game off. In a
+/// network game the local player's actions are also sent to the opponent.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameAction {
+    Resign(PieceColor),
+    OfferDraw(PieceColor),
+    AcceptDraw(PieceColor),
+    DeclineDraw(PieceColor),
+    /// See [`GameState::claim_draw`](crate::resources::GameState::claim_draw).
+    ClaimDraw(PieceColor),
+    /// See [`GameState::abort`](crate::resources::GameState::abort).
+    Abort(PieceColor),
+}
+
+/// Starts over once a game is finished.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewGame {
+    /// A fresh game from the standard position, with the same sides.
+    Fresh,
+    /// The same starting position again, with the players' colors swapped.
+    Rematch,
+}
@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::PieceColor, computer_player::ComputerPlayer, events::GameAction,
+    network::NetworkGame, resources::GameState, server_game::ServerGame,
+};
+
+/// The buttons for resigning, negotiating or claiming a draw and calling the
+/// game off.
+/// Only those that apply are shown, and none once the game is over.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionButton {
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    DeclineDraw,
+    ClaimDraw,
+    Abort,
+}
+
+impl ActionButton {
+    pub const ALL: [ActionButton; 6] = [
+        ActionButton::Resign,
+        ActionButton::OfferDraw,
+        ActionButton::AcceptDraw,
+        ActionButton::DeclineDraw,
+        ActionButton::ClaimDraw,
+        ActionButton::Abort,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ActionButton::Resign => "Resign",
+            ActionButton::OfferDraw => "Offer Draw",
+            ActionButton::AcceptDraw => "Accept Draw",
+            ActionButton::DeclineDraw => "Decline Draw",
+            ActionButton::ClaimDraw => "Claim Draw",
+            ActionButton::Abort => "Abort",
+        }
+    }
+
+    fn action(self, color: PieceColor) -> GameAction {
+        match self {
+            ActionButton::Resign => GameAction::Resign(color),
+            ActionButton::OfferDraw => GameAction::OfferDraw(color),
+            ActionButton::AcceptDraw => GameAction::AcceptDraw(color),
+            ActionButton::DeclineDraw => GameAction::DeclineDraw(color),
+            ActionButton::ClaimDraw => GameAction::ClaimDraw(color),
+            ActionButton::Abort => GameAction::Abort(color),
+        }
+    }
+}
+
+/// Who presses `button` on this screen: the local side of a network game,
+/// the human against the computer, or, with both players at one board, the
+/// side to move, or the side a draw is offered to when answering it. `None`
+/// while a network game is not under way, and for spectators.
+fn acting_color(
+    button: ActionButton,
+    game_state: &GameState,
+    computer: &ComputerPlayer,
+    network: Option<&NetworkGame>,
+    server: Option<&ServerGame>,
+) -> Option<PieceColor> {
+    if let Some(network) = network {
+        return network.local_color().filter(|_| network.is_connected());
+    }
+    if let Some(server) = server {
+        return server.local_color().filter(|_| server.is_connected());
+    }
+    if let Some(color) = computer.color {
+        return Some(color.opposite());
+    }
+    match button {
+        ActionButton::AcceptDraw | ActionButton::DeclineDraw => {
+            game_state.draw_offer.map(PieceColor::opposite)
+        }
+        _ => Some(game_state.position.side_to_move()),
+    }
+}
+
+/// Whether `button` is any use to `color` right now. The computer never
+/// takes a draw, so there is no offering it one; a claim needs threefold
+/// repetition or the fifty-move rule.
+fn applies(
+    button: ActionButton,
+    color: PieceColor,
+    game_state: &GameState,
+    computer: &ComputerPlayer,
+) -> bool {
+    match button {
+        ActionButton::Resign | ActionButton::Abort => true,
+        ActionButton::OfferDraw => game_state.draw_offer.is_none() && computer.color.is_none(),
+        ActionButton::AcceptDraw | ActionButton::DeclineDraw => {
+            game_state.draw_offer == Some(color.opposite())
+        }
+        ActionButton::ClaimDraw => game_state.position.claimable_draw().is_some(),
+    }
+}
+
+/// Turns presses of the action buttons into [`GameAction`]s.
+pub fn action_button_system(
+    game_state: Res<GameState>,
+    computer: Res<ComputerPlayer>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    mut actions: MessageWriter<GameAction>,
+) {
+    if game_state.is_over() {
+        return;
+    }
+    for (interaction, &button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(color) = acting_color(
+            button,
+            &game_state,
+            &computer,
+            network.as_deref(),
+            server.as_deref(),
+        ) && applies(button, color, &game_state, &computer)
+        {
+            actions.write(button.action(color));
+        }
+    }
+}
+
+/// Shows the action buttons that apply and hides the rest.
+pub fn action_button_visibility_system(
+    game_state: Res<GameState>,
+    computer: Res<ComputerPlayer>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    mut buttons: Query<(&ActionButton, &mut Node)>,
+) {
+    for (&button, mut node) in buttons.iter_mut() {
+        let shown = !game_state.is_over()
+            && acting_color(
+                button,
+                &game_state,
+                &computer,
+                network.as_deref(),
+                server.as_deref(),
+            )
+            .is_some_and(|color| applies(button, color, &game_state, &computer));
+        let display = if shown { Display::Flex } else { Display::None };
+        if node.display != display {
+            node.display = display;
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use chess_core::{ChessClock, TimeBonus, TimeControl};
+
+use crate::{components::PieceColor, resources::GameState, server_game::ServerGame};
+
+/// Time controls the clock button cycles through, after "off".
+const PRESETS: [(u64, TimeBonus); 6] = [
+    (1, TimeBonus::None),
+    (3, TimeBonus::Increment(Duration::from_secs(2))),
+    (5, TimeBonus::None),
+    (10, TimeBonus::Increment(Duration::from_secs(5))),
+    (15, TimeBonus::Delay(Duration::from_secs(10))),
+    (25, TimeBonus::Bronstein(Duration::from_secs(10))),
+];
+
+const RUNNING_COLOR: Color = Color::WHITE;
+const IDLE_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+const FLAGGED_COLOR: Color = Color::srgb(0.9, 0.3, 0.3);
+
+/// The game's clock, or `None` for an untimed game. It starts after White's
+/// first move, switches on every move played and stops when the game ends.
+/// Taking a move back puts both sides' time back to what it was before.
+#[derive(Resource, Default)]
+pub struct GameClock {
+    clock: Option<ChessClock>,
+    /// How many moves the game had when the clock last looked.
+    moves_seen: usize,
+    /// White's and Black's time left after each of those moves.
+    times: Vec<[Duration; 2]>,
+}
+
+impl GameClock {
+    pub fn new(control: Option<TimeControl>) -> Self {
+        Self {
+            clock: control.map(ChessClock::new),
+            moves_seen: 0,
+            times: Vec::new(),
+        }
+    }
+
+    /// Puts both sides back to the full base time, for a new game.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.control());
+    }
+
+    pub fn control(&self) -> Option<TimeControl> {
+        self.clock.as_ref().map(ChessClock::control)
+    }
+
+    /// Time left for `color`, in a timed game.
+    pub fn remaining(&self, color: PieceColor) -> Option<Duration> {
+        self.clock.as_ref().map(|clock| clock.remaining(color))
+    }
+
+    /// Sets both sides' time to what the game server says is left.
+    pub fn sync(&mut self, white: Duration, black: Duration) {
+        if let Some(clock) = &mut self.clock {
+            clock.set_remaining(PieceColor::White, white);
+            clock.set_remaining(PieceColor::Black, black);
+        }
+    }
+
+    /// Off, then each preset in turn, then off again.
+    fn cycle(&mut self) {
+        let presets = PRESETS
+            .map(|(minutes, bonus)| TimeControl::new(Duration::from_secs(minutes * 60), bonus));
+        let next = match self.control() {
+            None => Some(presets[0]),
+            Some(current) => presets
+                .iter()
+                .position(|&preset| preset == current)
+                .and_then(|i| presets.get(i + 1).copied()),
+        };
+        *self = Self::new(next);
+    }
+
+    pub fn label(&self) -> String {
+        match self.control() {
+            Some(control) => format!("Clock: {control}"),
+            None => "Clock: Off".to_string(),
+        }
+    }
+}
+
+/// The button that picks the time control before the first move.
+#[derive(Component)]
+pub struct ClockButton;
+
+#[derive(Component)]
+pub struct ClockButtonText;
+
+/// One side's time in the clock panel.
+#[derive(Component)]
+pub struct ClockText(pub PieceColor);
+
+/// The panel holding both [`ClockText`]s, hidden in untimed games.
+#[derive(Component)]
+pub struct ClockPanel;
+
+/// `m:ss`, `h:mm:ss` from an hour up, and tenths under ten seconds.
+fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else if secs >= 10 {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    } else {
+        format!("0:{:02}.{}", secs, time.subsec_millis() / 100)
+    }
+}
+
+/// Switches the clock when a move has been played, stops it at the end of
+/// the game and runs down the side to move. Only a single new move in the
+/// game counts as a press: a game that jumps ahead, such as a resynced
+/// network game, just has the clock follow the side to move, without a
+/// bonus. When moves are taken back, both sides get the time they had
+/// after the last move still standing, and a fallen flag is lifted. A flag
+/// fall ends the game, except on a game server, which announces flag falls
+/// itself.
+pub fn clock_system(
+    time: Res<Time>,
+    server: Option<Res<ServerGame>>,
+    mut game_clock: ResMut<GameClock>,
+    mut game_state: ResMut<GameState>,
+) {
+    let GameClock {
+        clock,
+        moves_seen,
+        times,
+    } = &mut *game_clock;
+    let moves = game_state.moves.len();
+    let moved = moves == *moves_seen + 1;
+    let taken_back = moves < *moves_seen;
+    *moves_seen = moves;
+    let Some(clock) = clock else {
+        return;
+    };
+
+    if moved {
+        clock.press();
+    }
+    if taken_back {
+        times.truncate(moves);
+        let control = clock.control();
+        let [white, black] = times.last().copied().unwrap_or([control.base; 2]);
+        *clock = ChessClock::new(control);
+        clock.set_remaining(PieceColor::White, white);
+        clock.set_remaining(PieceColor::Black, black);
+    }
+    while times.len() < moves {
+        times.push([PieceColor::White, PieceColor::Black].map(|color| clock.remaining(color)));
+    }
+
+    let side_to_move = game_state.position.side_to_move();
+    if game_state.is_over() {
+        clock.pause();
+    } else if !game_state.moves.is_empty() && clock.running() != Some(side_to_move) {
+        clock.start(side_to_move);
+    }
+
+    if let Some(color) = clock.tick(time.delta())
+        && game_state.flagged.is_none()
+        && server.is_none()
+    {
+        game_state.flag_fall(color);
+    }
+}
+
+/// Cycles the time control. Only before the first move, so that a game
+/// keeps the control it started with.
+pub fn clock_button_system(
+    mut game_clock: ResMut<GameClock>,
+    game_state: Res<GameState>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ClockButton>)>,
+    mut labels: Query<&mut Text, With<ClockButtonText>>,
+) {
+    for interaction in buttons.iter() {
+        if *interaction == Interaction::Pressed && game_state.moves.is_empty() {
+            game_clock.cycle();
+            for mut text in labels.iter_mut() {
+                text.0 = game_clock.label();
+            }
+        }
+    }
+}
+
+pub fn update_clock_ui_system(
+    game_clock: Res<GameClock>,
+    mut panels: Query<&mut Node, With<ClockPanel>>,
+    mut clock_texts: Query<(&ClockText, &mut Text, &mut TextColor)>,
+) {
+    for mut node in panels.iter_mut() {
+        let display = match game_clock.clock {
+            Some(_) => Display::Flex,
+            None => Display::None,
+        };
+        if node.display != display {
+            node.display = display;
+        }
+    }
+    let Some(clock) = &game_clock.clock else {
+        return;
+    };
+
+    for (ClockText(color), mut text, mut text_color) in clock_texts.iter_mut() {
+        let label = format!("{color:?} {}", format_time(clock.remaining(*color)));
+        if text.0 != label {
+            text.0 = label;
+        }
+        text_color.0 = if clock.flagged() == Some(*color) {
+            FLAGGED_COLOR
+        } else if clock.running() == Some(*color) {
+            RUNNING_COLOR
+        } else {
+            IDLE_COLOR
+        };
+    }
+}
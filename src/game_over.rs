@@ -0,0 +1,273 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, ui::FocusPolicy};
+use chess_core::Position;
+
+use crate::{
+    chess_board_plugin::BoardOrientation,
+    chess_piece_plugin::setup_chesspieces,
+    clipboard,
+    components::Piece,
+    computer_player::ComputerPlayer,
+    events::NewGame,
+    game_clock::GameClock,
+    game_plugin::{BUTTON_COLOR, today},
+    network::NetworkGame,
+    resources::GameState,
+    server_game::ServerGame,
+};
+
+/// Time spent playing, from the first move until the game ends.
+#[derive(Resource, Default)]
+pub struct GameDuration(pub Duration);
+
+/// Whether the game-over screen was put away to look through the finished
+/// game; it stays away until the next game.
+#[derive(Resource, Default)]
+pub struct GameOverScreen {
+    pub dismissed: bool,
+}
+
+/// The overlay summing up a finished game.
+#[derive(Component)]
+pub struct GameOverOverlay;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverButton {
+    NewGame,
+    Rematch,
+    CopyPgn,
+    Analyze,
+}
+
+/// `m:ss`, or `h:mm:ss` from an hour up.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+pub fn game_duration_system(
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    mut duration: ResMut<GameDuration>,
+) {
+    if !game_state.moves.is_empty() && !game_state.is_over() {
+        duration.0 += time.delta();
+    }
+}
+
+/// Starts the requested game: the pieces are despawned and set up again
+/// from the new position, and the clock starts over. A rematch swaps the
+/// computer's side and turns the board to the other player. Network games
+/// are left alone, as one side cannot restart them.
+pub fn new_game_system(
+    mut commands: Commands,
+    mut requests: MessageReader<NewGame>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    mut game_state: ResMut<GameState>,
+    mut game_clock: ResMut<GameClock>,
+    mut computer: ResMut<ComputerPlayer>,
+    mut orientation: ResMut<BoardOrientation>,
+    mut duration: ResMut<GameDuration>,
+    mut screen: ResMut<GameOverScreen>,
+    pieces: Query<Entity, With<Piece>>,
+) {
+    let Some(&request) = requests.read().last() else {
+        return;
+    };
+    if network.is_some() || server.is_some() {
+        return;
+    }
+
+    let initial = match request {
+        NewGame::Fresh => Position::new(),
+        NewGame::Rematch => {
+            if let Some(color) = &mut computer.color {
+                *color = color.opposite();
+            }
+            if !orientation.auto_flip {
+                orientation.bottom = orientation.bottom.opposite();
+            }
+            game_state.initial_position.clone()
+        }
+    };
+
+    for entity in pieces.iter() {
+        commands.entity(entity).despawn();
+    }
+    game_state.start_new_game(GameState::new(initial));
+    game_clock.reset();
+    *duration = GameDuration::default();
+    *screen = GameOverScreen::default();
+    commands.run_system_cached(setup_chesspieces);
+}
+
+/// Shows the game-over screen while the game is over, unless it was
+/// dismissed.
+pub fn game_over_screen_system(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    duration: Res<GameDuration>,
+    screen: Res<GameOverScreen>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    overlays: Query<Entity, With<GameOverOverlay>>,
+) {
+    let shown = game_state.is_over() && !screen.dismissed;
+    match overlays.single() {
+        Ok(overlay) if !shown => commands.entity(overlay).despawn(),
+        Err(_) if shown => {
+            let local = network.is_none() && server.is_none();
+            spawn_overlay(&mut commands, &game_state, duration.0, local);
+        }
+        _ => {}
+    }
+}
+
+/// The result, the number of moves and how long the game took, above the
+/// buttons. Only a local game can be restarted from here.
+fn spawn_overlay(commands: &mut Commands, game_state: &GameState, duration: Duration, local: bool) {
+    let title = match game_state.result {
+        Some(result) => result.to_string(),
+        None => "Game aborted".to_string(),
+    };
+    let moves = game_state.numbered_moves().len();
+    let plural = if moves == 1 { "" } else { "s" };
+    let summary = format!("{moves} move{plural} in {}", format_duration(duration));
+
+    let buttons = [
+        (GameOverButton::NewGame, "New Game"),
+        (GameOverButton::Rematch, "Rematch"),
+        (GameOverButton::CopyPgn, "Copy PGN"),
+        (GameOverButton::Analyze, "Analyze"),
+    ]
+    .into_iter()
+    .filter(|(button, _)| {
+        local || !matches!(button, GameOverButton::NewGame | GameOverButton::Rematch)
+    });
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            FocusPolicy::Block,
+            GlobalZIndex(10),
+            GameOverOverlay,
+        ))
+        .with_children(|overlay| {
+            overlay
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(10.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(title),
+                        TextFont {
+                            font_size: 28.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                    panel.spawn((
+                        Text::new(summary),
+                        TextFont {
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                    panel
+                        .spawn(Node {
+                            column_gap: Val::Px(10.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            for (button, label) in buttons {
+                                row.spawn((
+                                    Button,
+                                    Node {
+                                        padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(BUTTON_COLOR),
+                                    button,
+                                ))
+                                .with_child((
+                                    Text::new(label),
+                                    TextFont {
+                                        font_size: 15.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                ));
+                            }
+                        });
+                });
+        });
+}
+
+/// New Game and Rematch ask for a new game; Copy PGN puts the finished game
+/// on the clipboard; Analyze puts the screen away and reopens the final
+/// position for free play, with the computer, the clock and any network
+/// connection off.
+pub fn game_over_button_system(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &GameOverButton), Changed<Interaction>>,
+    mut game_state: ResMut<GameState>,
+    mut game_clock: ResMut<GameClock>,
+    mut computer: ResMut<ComputerPlayer>,
+    mut screen: ResMut<GameOverScreen>,
+    mut new_games: MessageWriter<NewGame>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            GameOverButton::NewGame => {
+                new_games.write(NewGame::Fresh);
+            }
+            GameOverButton::Rematch => {
+                new_games.write(NewGame::Rematch);
+            }
+            GameOverButton::CopyPgn => {
+                let mut game = game_state.to_pgn();
+                if game.tag("Date").is_none() {
+                    game.set_tag("Date", &today());
+                }
+                match clipboard::set_text(&game.to_pgn()) {
+                    Ok(()) => info!("Copied the game to the clipboard"),
+                    Err(err) => warn!("Could not copy PGN to clipboard: {err}"),
+                }
+            }
+            GameOverButton::Analyze => {
+                screen.dismissed = true;
+                *game_state = game_state.for_analysis();
+                *game_clock = GameClock::default();
+                computer.color = None;
+                commands.remove_resource::<NetworkGame>();
+                commands.remove_resource::<ServerGame>();
+            }
+        }
+    }
+}
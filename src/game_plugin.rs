@@ -1,11 +1,44 @@
+use std::time::Duration;
+
 use bevy::{prelude::*, window::PrimaryWindow};
 
+use chess_core::{Move, PgnGame, Position, Square};
+use chess_server::Seat;
+
 use crate::{
-    chess_board_plugin::{BoardCell, SCREEN_HEIGHT, SCREEN_WIDTH, TILE_SIZE},
+    animation::{
+        AnimationSettings, FadeOut, MoveTween, animations_finished, fade_out_system,
+        move_tween_system,
+    },
+    chess_board_plugin::{BoardLayout, BoardOrientation, cursor_to_world, flip_board_system},
+    chess_piece_plugin::{setup_chesspieces, spawn_pieces},
+    clipboard,
     components::{GridPosition, Piece, PieceColor, PieceType},
-    events::{MoveMade, PieceDeselected, PieceSelected, TileClicked},
-    resources::GameState,
-    rules::{get_valid_moves, is_checkmate, is_self_check, is_square_under_attack},
+    computer_player::{
+        ComputerButton, ComputerButtonText, ComputerPlayer, ComputerSearch, computer_button_system,
+        computer_move_system,
+    },
+    drag_and_drop::{DragState, SnapBack, drag_system, snap_back_system},
+    events::{
+        GameAction, MoveMade, NewGame, PieceDeselected, PieceSelected, PromotionRequested,
+        TileClicked,
+    },
+    game_actions::{ActionButton, action_button_system, action_button_visibility_system},
+    game_clock::{
+        ClockButton, ClockButtonText, ClockPanel, ClockText, GameClock, clock_button_system,
+        clock_system, update_clock_ui_system,
+    },
+    game_over::{
+        GameDuration, GameOverScreen, game_duration_system, game_over_button_system,
+        game_over_screen_system, new_game_system,
+    },
+    highlights::{highlight_system, highlight_theme_system, setup_highlights},
+    network::{NetworkGame, network_system},
+    promotion_picker::{PromotionPicker, promotion_picker_system},
+    resources::{GameState, Premove},
+    server_game::{ServerGame, server_system},
+    theme::{ActiveTheme, PieceSprites, Theme, ThemeButton, ThemeButtonText, theme_label},
+    uci_player::{UciPlayer, uci_move_system},
 };
 
 pub struct GamePlugin;
@@ -16,44 +49,195 @@ struct StatusText;
 #[derive(Component)]
 struct MoveHistoryText;
 
+/// The column beside or below the board with the clocks, the status, the
+/// move history and the buttons.
+#[derive(Component)]
+struct SidePanel;
+
+/// The undo and redo buttons below the move history.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum HistoryButton {
+    Undo,
+    Redo,
+}
+
+pub(crate) const BUTTON_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+const BUTTON_HOVER_COLOR: Color = Color::srgb(0.35, 0.35, 0.35);
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<GameState>()
-            .add_message::<TileClicked>()
-            .add_message::<PieceSelected>()
-            .add_message::<PieceDeselected>()
-            .add_message::<MoveMade>()
-            .add_systems(Startup, setup_ui)
+            .init_resource::<PromotionPicker>()
+            .init_resource::<ComputerPlayer>()
+            .init_resource::<ComputerSearch>()
+            .init_resource::<GameClock>()
+            .init_resource::<DragState>()
+            .init_resource::<AnimationSettings>()
+            .init_resource::<GameDuration>()
+            .init_resource::<GameOverScreen>()
+            .add_systems(Startup, (setup_ui, setup_highlights))
             .add_systems(
                 Update,
                 (
-                    input_system,
+                    fen_shortcut_system,
+                    pgn_shortcut_system,
+                    button_color_system,
+                    computer_button_system,
+                    clock_button_system,
+                    (action_button_system, game_over_button_system),
+                    (undo_redo_system, input_system, drag_system)
+                        .chain()
+                        .run_if(animations_finished),
+                    premove_system.run_if(animations_finished),
                     selection_logic_system,
+                    promotion_picker_system,
+                    (
+                        computer_move_system.run_if(not(resource_exists::<UciPlayer>)),
+                        uci_move_system.run_if(resource_exists::<UciPlayer>),
+                        network_system.run_if(resource_exists::<NetworkGame>),
+                        server_system.run_if(resource_exists::<ServerGame>),
+                    )
+                        .run_if(animations_finished),
                     move_execution_system,
-                    highlight_moves_system,
-                    update_ui_system,
-                    check_game_status_system,
+                    (snap_back_system, move_tween_system, fade_out_system),
+                    flip_board_system.run_if(animations_finished),
+                    (highlight_theme_system, highlight_system),
+                    (
+                        update_ui_system,
+                        side_panel_system,
+                        action_button_visibility_system,
+                        game_over_screen_system,
+                    ),
+                    (check_game_status_system, game_action_system),
+                    new_game_system,
+                    (clock_system, game_duration_system),
+                    update_clock_ui_system,
                 )
                     .chain(),
             );
+        add_game_messages(app);
     }
 }
 
-fn setup_ui(mut commands: Commands) {
+/// The game rules without a window, renderer or asset server, for running
+/// on `MinimalPlugins`. Clicks arrive as `TileClicked` messages, just like
+/// from the mouse, moves land instantly and a `GameClock` runs on `Time`.
+/// The undo and redo shortcuts are read from the `ButtonInput<KeyCode>`
+/// resource. Insert a `GameState` before adding the plugin to start from
+/// another position, a `UciPlayer` to have an engine play the computer's
+/// side, and a `NetworkGame` or a `ServerGame` to play over the network.
+pub struct HeadlessGamePlugin;
+
+impl Plugin for HeadlessGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameState>()
+            .init_resource::<PromotionPicker>()
+            .init_resource::<ComputerPlayer>()
+            .init_resource::<BoardOrientation>()
+            .init_resource::<GameClock>()
+            .init_resource::<GameDuration>()
+            .init_resource::<GameOverScreen>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(AnimationSettings {
+                duration: Duration::ZERO,
+                ..default()
+            })
+            .insert_resource(ActiveTheme(Theme::classic(
+                Handle::default(),
+                Handle::default(),
+            )))
+            .add_systems(Startup, setup_chesspieces)
+            .add_systems(
+                Update,
+                (
+                    undo_redo_system,
+                    selection_logic_system,
+                    promotion_picker_system,
+                    (
+                        uci_move_system.run_if(resource_exists::<UciPlayer>),
+                        network_system.run_if(resource_exists::<NetworkGame>),
+                        server_system.run_if(resource_exists::<ServerGame>),
+                    ),
+                    move_execution_system,
+                    (move_tween_system, fade_out_system),
+                    (check_game_status_system, game_action_system),
+                    new_game_system,
+                    clock_system,
+                )
+                    .chain(),
+            );
+        add_game_messages(app);
+    }
+}
+
+fn add_game_messages(app: &mut App) {
+    app.add_message::<TileClicked>()
+        .add_message::<PieceSelected>()
+        .add_message::<PieceDeselected>()
+        .add_message::<MoveMade>()
+        .add_message::<PromotionRequested>()
+        .add_message::<GameAction>()
+        .add_message::<NewGame>();
+}
+
+fn setup_ui(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    computer: Res<ComputerPlayer>,
+    game_clock: Res<GameClock>,
+    theme: Res<ActiveTheme>,
+) {
+    let boxed = || {
+        (
+            Node {
+                padding: UiRect::all(Val::Px(5.0)),
+                align_self: AlignSelf::FlexStart,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        )
+    };
+
     commands
         .spawn((
             Node {
                 position_type: PositionType::Absolute,
-                top: Val::Px(10.0),
-                left: Val::Px(820.0),
-                padding: UiRect::all(Val::Px(5.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                overflow: Overflow::clip(),
                 ..default()
             },
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            SidePanel,
         ))
-        .with_children(|parent| {
-            parent.spawn((
-                Text::new("Status: White's Turn"),
+        .with_children(|panel| {
+            panel
+                .spawn((
+                    Node {
+                        padding: UiRect::all(Val::Px(5.0)),
+                        align_self: AlignSelf::FlexStart,
+                        column_gap: Val::Px(15.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                    ClockPanel,
+                ))
+                .with_children(|parent| {
+                    for color in [PieceColor::White, PieceColor::Black] {
+                        parent.spawn((
+                            Text::new(""),
+                            TextFont {
+                                font_size: 20.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                            ClockText(color),
+                        ));
+                    }
+                });
+
+            panel.spawn(boxed()).with_child((
+                Text::new(format!("Status: {}", game_state.status)),
                 TextFont {
                     font_size: 20.0,
                     ..default()
@@ -61,21 +245,8 @@ fn setup_ui(mut commands: Commands) {
                 TextColor(Color::WHITE),
                 StatusText,
             ));
-        });
 
-    commands
-        .spawn((
-            Node {
-                position_type: PositionType::Absolute,
-                top: Val::Px(50.0),
-                left: Val::Px(820.0),
-                padding: UiRect::all(Val::Px(5.0)),
-                ..default()
-            },
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
-        ))
-        .with_children(|parent| {
-            parent.spawn((
+            panel.spawn(boxed()).with_child((
                 Text::new("Move History:\n"),
                 TextFont {
                     font_size: 15.0,
@@ -84,360 +255,674 @@ fn setup_ui(mut commands: Commands) {
                 TextColor(Color::WHITE),
                 MoveHistoryText,
             ));
+
+            // Pushes the buttons to the bottom of the panel.
+            panel.spawn(Node {
+                flex_grow: 1.0,
+                ..default()
+            });
+
+            panel
+                .spawn(Node {
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(10.0),
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for button in ActionButton::ALL {
+                        parent
+                            .spawn((
+                                Button,
+                                Node {
+                                    padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(BUTTON_COLOR),
+                                button,
+                            ))
+                            .with_child((
+                                Text::new(button.label()),
+                                TextFont {
+                                    font_size: 15.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                    }
+                });
+
+            panel
+                .spawn(Node {
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(10.0),
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in [
+                        (HistoryButton::Undo, "Undo (Ctrl+Z)"),
+                        (HistoryButton::Redo, "Redo (Ctrl+Y)"),
+                    ] {
+                        parent
+                            .spawn((
+                                Button,
+                                Node {
+                                    padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(BUTTON_COLOR),
+                                button,
+                            ))
+                            .with_child((
+                                Text::new(label),
+                                TextFont {
+                                    font_size: 15.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                    }
+
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                                ..default()
+                            },
+                            BackgroundColor(BUTTON_COLOR),
+                            ComputerButton,
+                        ))
+                        .with_child((
+                            Text::new(computer.label()),
+                            TextFont {
+                                font_size: 15.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                            ComputerButtonText,
+                        ));
+
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                                ..default()
+                            },
+                            BackgroundColor(BUTTON_COLOR),
+                            ClockButton,
+                        ))
+                        .with_child((
+                            Text::new(game_clock.label()),
+                            TextFont {
+                                font_size: 15.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                            ClockButtonText,
+                        ));
+
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                                ..default()
+                            },
+                            BackgroundColor(BUTTON_COLOR),
+                            ThemeButton,
+                        ))
+                        .with_child((
+                            Text::new(theme_label(&theme)),
+                            TextFont {
+                                font_size: 15.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                            ThemeButtonText,
+                        ));
+                });
         });
 }
 
-fn input_system(
-    window: Single<&Window, With<PrimaryWindow>>,
-    buttons: Res<ButtonInput<MouseButton>>,
-    mut tile_clicked_events: MessageWriter<TileClicked>,
+/// Fits the side panel into the part of the window the board leaves free.
+fn side_panel_system(layout: Res<BoardLayout>, mut panels: Query<&mut Node, With<SidePanel>>) {
+    if !layout.is_changed() {
+        return;
+    }
+    let area = layout.panel();
+    for mut node in panels.iter_mut() {
+        node.left = Val::Px(area.min.x);
+        node.top = Val::Px(area.min.y);
+        node.width = Val::Px(area.width());
+        node.height = Val::Px(area.height());
+    }
+}
+
+/// Lightens buttons under the cursor.
+fn button_color_system(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
 ) {
-    if buttons.just_pressed(MouseButton::Left) {
-        if let Some(position) = window.cursor_position() {
-            let x = (position.x / TILE_SIZE).floor() as i32;
-            let y = ((SCREEN_HEIGHT - position.y) / TILE_SIZE).floor() as i32;
-
-            if (0..8).contains(&x) && (0..8).contains(&y) {
-                tile_clicked_events.write(TileClicked {
-                    position: GridPosition::new(x as u32, y as u32),
-                });
-            }
-        }
+    for (interaction, mut color) in buttons.iter_mut() {
+        color.0 = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
     }
 }
 
-fn selection_logic_system(
-    mut tile_clicked_events: MessageReader<TileClicked>,
+/// Ctrl+Z / Ctrl+Y and the history buttons take moves back and replay them.
+/// The pieces are respawned from the resulting position, which brings back
+/// captured pieces and un-castles rooks. Against the computer, its replies
+/// are taken back and replayed along with the player's moves. In a timed
+/// game the clock goes back with the moves; see [`clock_system`]. Network
+/// and server games have no take-backs: the opponent would have no say in
+/// them.
+fn undo_redo_system(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Query<(&Interaction, &HistoryButton), Changed<Interaction>>,
     mut game_state: ResMut<GameState>,
-    pieces: Query<(Entity, &GridPosition, &Piece)>,
-    mut piece_selected_events: MessageWriter<PieceSelected>,
-    mut piece_deselected_events: MessageWriter<PieceDeselected>,
-    mut move_made_events: MessageWriter<MoveMade>,
+    picker: Res<PromotionPicker>,
+    computer: Res<ComputerPlayer>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    pieces: Query<Entity, With<Piece>>,
+    theme: Res<ActiveTheme>,
+    orientation: Res<BoardOrientation>,
 ) {
-    for event in tile_clicked_events.read() {
-        let clicked_pos = event.position;
-        let mut clicked_piece_entity = None;
+    let mut requested = None;
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            requested = Some(*button);
+        }
+    }
 
-        for (entity, pos, piece) in pieces.iter() {
-            if *pos == clicked_pos {
-                clicked_piece_entity = Some((entity, piece));
-                break;
-            }
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        if keys.just_pressed(KeyCode::KeyZ) {
+            requested = Some(HistoryButton::Undo);
+        } else if keys.just_pressed(KeyCode::KeyY) {
+            requested = Some(HistoryButton::Redo);
         }
+    }
 
-        if let Some((entity, piece)) = clicked_piece_entity {
-            if piece.color == game_state.current_turn {
-                // Clicked own piece
-                if game_state.selected_entity == Some(entity) {
-                    game_state.selected_entity = None;
-                    game_state.selected_position = None;
-                    piece_deselected_events.write(PieceDeselected);
-                } else {
-                    game_state.selected_entity = Some(entity);
-                    game_state.selected_position = Some(clicked_pos);
-                    piece_selected_events.write(PieceSelected);
-                }
-            } else {
-                // Clicked enemy piece (Potential Capture)
-                if let Some(selected_entity) = game_state.selected_entity {
-                    if let Ok((_, selected_pos, selected_piece)) = pieces.get(selected_entity) {
-                        let all_pieces: Vec<(GridPosition, Piece)> =
-                            pieces.iter().map(|(_, p, piece)| (*p, *piece)).collect();
-
-                        let valid_moves =
-                            get_valid_moves(*selected_pos, selected_piece, &all_pieces);
-
-                        if valid_moves.contains(&clicked_pos)
-                            && !is_self_check(
-                                *selected_pos,
-                                clicked_pos,
-                                selected_piece,
-                                &all_pieces,
-                                game_state.current_turn,
-                            )
-                        {
-                            move_made_events.write(MoveMade {
-                                entity: selected_entity,
-                                from: *selected_pos,
-                                to: clicked_pos,
-                            });
-                        }
-                    }
-                }
-            }
-        } else {
-            // Clicked empty tile
-            if let Some(selected_entity) = game_state.selected_entity {
-                if let Ok((_, selected_pos, selected_piece)) = pieces.get(selected_entity) {
-                    let all_pieces: Vec<(GridPosition, Piece)> =
-                        pieces.iter().map(|(_, p, piece)| (*p, *piece)).collect();
-
-                    let valid_moves = get_valid_moves(*selected_pos, selected_piece, &all_pieces);
-
-                    if valid_moves.contains(&clicked_pos)
-                        && !is_self_check(
-                            *selected_pos,
-                            clicked_pos,
-                            selected_piece,
-                            &all_pieces,
-                            game_state.current_turn,
-                        )
-                    {
-                        move_made_events.write(MoveMade {
-                            entity: selected_entity,
-                            from: *selected_pos,
-                            to: clicked_pos,
-                        });
-                    } else {
-                        game_state.selected_entity = None;
-                        game_state.selected_position = None;
-                        piece_deselected_events.write(PieceDeselected);
-                    }
-                }
-            }
+    let Some(button) = requested else {
+        return;
+    };
+    if picker.is_open() || network.is_some() || server.is_some() {
+        return;
+    }
+
+    let step = |game_state: &mut GameState| match button {
+        HistoryButton::Undo => game_state.undo(),
+        HistoryButton::Redo => game_state.redo(),
+    };
+    let changed = step(&mut game_state);
+    if changed.is_some() && computer.plays(game_state.position.side_to_move()) {
+        step(&mut game_state);
+    }
+    if changed.is_some() {
+        for entity in pieces.iter() {
+            commands.entity(entity).despawn();
         }
+        spawn_pieces(
+            &mut commands,
+            &theme.0.pieces,
+            &game_state.position,
+            &orientation,
+        );
     }
 }
 
-fn move_execution_system(
+/// Ctrl+C prints the current FEN and copies it to the clipboard; Ctrl+V
+/// replaces the game with the FEN on the clipboard.
+fn fen_shortcut_system(
     mut commands: Commands,
-    mut move_events: MessageReader<MoveMade>,
+    keys: Res<ButtonInput<KeyCode>>,
     mut game_state: ResMut<GameState>,
-    mut pieces: Query<(
-        Entity,
-        &mut GridPosition,
-        &mut Piece,
-        &mut Transform,
-        &mut Sprite,
-    )>,
+    mut game_clock: ResMut<GameClock>,
+    pieces: Query<Entity, With<Piece>>,
+    theme: Res<ActiveTheme>,
+    orientation: Res<BoardOrientation>,
 ) {
-    for event in move_events.read() {
-        let MoveMade { entity, from, to } = *event;
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
 
-        let Ok((_, _, p, _, _)) = pieces.get(entity) else {
-            continue;
-        };
-        let piece_kind = p.kind;
-        let piece_color = p.color;
-
-        let mut captured_entity = None;
-        for (e, pos, _, _, _) in pieces.iter() {
-            if *pos == to && e != entity {
-                captured_entity = Some(e);
-                break;
-            }
+    if keys.just_pressed(KeyCode::KeyC) {
+        let fen = game_state.position.to_fen();
+        println!("{fen}");
+        if let Err(err) = clipboard::set_text(&fen) {
+            warn!("Could not copy FEN to clipboard: {err}");
         }
+    }
 
-        let mut en_passant_victim = None;
-        if piece_kind == PieceType::Pawn && from.x != to.x && captured_entity.is_none() {
-            let victim_y = from.y;
-            let victim_x = to.x;
-            for (e, pos, _, _, _) in pieces.iter() {
-                if pos.x == victim_x && pos.y == victim_y && e != entity {
-                    en_passant_victim = Some(e);
-                    break;
-                }
+    if keys.just_pressed(KeyCode::KeyV) {
+        let text = match clipboard::get_text() {
+            Ok(text) => text,
+            Err(err) => {
+                warn!("Could not read clipboard: {err}");
+                return;
             }
-        }
+        };
 
-        let mut castling_rook_info = None;
-        if piece_kind == PieceType::King && (from.x as i32 - to.x as i32).abs() == 2 {
-            let rank = from.y;
-            let (rook_old_x, rook_new_x) = if to.x == 6 { (7, 5) } else { (0, 3) };
-            for (e, pos, _, _, _) in pieces.iter() {
-                if pos.x == rook_old_x && pos.y == rank {
-                    castling_rook_info = Some((e, *pos, GridPosition::new(rook_new_x, rank)));
-                    break;
-                }
+        match Position::from_fen(text.trim()) {
+            Ok(position) => {
+                replace_game(
+                    &mut commands,
+                    &pieces,
+                    &theme.0.pieces,
+                    &orientation,
+                    &mut game_state,
+                    &mut game_clock,
+                    GameState::new(position),
+                );
             }
+            Err(err) => game_state.status = format!("Invalid FEN: {err}"),
         }
+    }
+}
 
-        // Mutations
-        let move_str = record_move_string(piece_kind, piece_color, from, to);
-        game_state.move_history.push(move_str);
+/// File written by Ctrl+S and read by Ctrl+O, in the working directory.
+const PGN_FILE: &str = "game.pgn";
+
+/// Ctrl+S saves the game as PGN; Ctrl+O replaces it with the first game in
+/// the saved file.
+fn pgn_shortcut_system(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut game_state: ResMut<GameState>,
+    mut game_clock: ResMut<GameClock>,
+    pieces: Query<Entity, With<Piece>>,
+    theme: Res<ActiveTheme>,
+    orientation: Res<BoardOrientation>,
+) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
 
-        if let Some(e) = captured_entity {
-            commands.entity(e).despawn();
+    if keys.just_pressed(KeyCode::KeyS) {
+        let mut game = game_state.to_pgn();
+        if game.tag("Date").is_none() {
+            game.set_tag("Date", &today());
         }
-        if let Some(e) = en_passant_victim {
-            commands.entity(e).despawn();
+        match std::fs::write(PGN_FILE, game.to_pgn()) {
+            Ok(()) => info!("Saved game to {PGN_FILE}"),
+            Err(err) => game_state.status = format!("Could not save {PGN_FILE}: {err}"),
         }
+    }
 
-        if let Some((rook_e, _, rook_new_pos)) = castling_rook_info {
-            if let Ok((_, mut r_pos, _, mut r_transform, _)) = pieces.get_mut(rook_e) {
-                *r_pos = rook_new_pos;
-                update_transform(&mut r_transform, &rook_new_pos);
+    if keys.just_pressed(KeyCode::KeyO) {
+        let loaded = std::fs::read_to_string(PGN_FILE)
+            .map_err(|err| err.to_string())
+            .and_then(|text| PgnGame::from_pgn(&text).map_err(|err| err.to_string()));
+        match loaded {
+            Ok(game) => {
+                let loaded_state = GameState::from_pgn(&game);
+                replace_game(
+                    &mut commands,
+                    &pieces,
+                    &theme.0.pieces,
+                    &orientation,
+                    &mut game_state,
+                    &mut game_clock,
+                    loaded_state,
+                );
             }
+            Err(err) => game_state.status = format!("Could not load {PGN_FILE}: {err}"),
         }
+    }
+}
 
-        let is_double_jump =
-            piece_kind == PieceType::Pawn && (from.y as i32 - to.y as i32).abs() == 2;
-        let is_promotion = piece_kind == PieceType::Pawn
-            && ((piece_color == PieceColor::White && to.y == 7)
-                || (piece_color == PieceColor::Black && to.y == 0));
+/// Swaps in a new game, counted as the next one, respawns the piece sprites
+/// to match it and resets the clock.
+pub(crate) fn replace_game(
+    commands: &mut Commands,
+    pieces: &Query<Entity, With<Piece>>,
+    sprites: &PieceSprites,
+    orientation: &BoardOrientation,
+    game_state: &mut GameState,
+    game_clock: &mut GameClock,
+    new_state: GameState,
+) {
+    for entity in pieces.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_pieces(commands, sprites, &new_state.position, orientation);
+    game_state.start_new_game(new_state);
+    game_clock.reset();
+}
 
-        if let Ok((_, mut pos, mut piece, mut transform, mut sprite)) = pieces.get_mut(entity) {
-            *pos = to;
-            piece.is_first_move = false;
-            piece.just_double_jumped = is_double_jump;
+/// Today's date in the PGN `YYYY.MM.DD` format (UTC).
+pub(crate) fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    // Civil-from-days conversion for the proleptic Gregorian calendar.
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
 
-            if is_promotion {
-                piece.kind = PieceType::Queen;
-                if let Some(atlas) = &mut sprite.texture_atlas {
-                    atlas.index = PieceType::Queen + piece.color;
-                }
-            }
+fn input_system(
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    orientation: Res<BoardOrientation>,
+    mut tile_clicked_events: MessageWriter<TileClicked>,
+) {
+    if buttons.just_pressed(MouseButton::Left)
+        && let Some(position) =
+            cursor_to_world(&window, *camera).and_then(|cursor| orientation.world_to_grid(cursor))
+    {
+        tile_clicked_events.write(TileClicked { position });
+    }
+}
 
-            update_transform(&mut transform, &to);
+fn selection_logic_system(
+    mut tile_clicked_events: MessageReader<TileClicked>,
+    mut game_state: ResMut<GameState>,
+    picker: Res<PromotionPicker>,
+    computer: Res<ComputerPlayer>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    pieces: Query<(Entity, &GridPosition, &Piece)>,
+    mut piece_selected_events: MessageWriter<PieceSelected>,
+    mut piece_deselected_events: MessageWriter<PieceDeselected>,
+    mut move_made_events: MessageWriter<MoveMade>,
+    mut promotion_requests: MessageWriter<PromotionRequested>,
+) {
+    if picker.is_open() || game_state.is_over() {
+        tile_clicked_events.clear();
+        return;
+    }
+    let side_to_move = game_state.position.side_to_move();
+    if network
+        .as_ref()
+        .is_some_and(|network| !network.is_connected())
+        || server
+            .as_ref()
+            .is_some_and(|server| !server.is_connected() || server.seat() == Some(Seat::Spectator))
+    {
+        tile_clicked_events.clear();
+        return;
+    }
+    if computer.plays(side_to_move)
+        || network
+            .as_ref()
+            .is_some_and(|network| network.waits_for(side_to_move))
+        || server
+            .as_ref()
+            .is_some_and(|server| server.waits_for(side_to_move))
+    {
+        for event in tile_clicked_events.read() {
+            queue_premove(&mut game_state, &pieces, event.position);
         }
+        return;
+    }
 
-        for (e, _, mut p, _, _) in pieces.iter_mut() {
-            if e != entity {
-                p.just_double_jumped = false;
+    for event in tile_clicked_events.read() {
+        let clicked_pos = event.position;
+        let clicked_piece = pieces
+            .iter()
+            .find(|(_, pos, _)| **pos == clicked_pos)
+            .map(|(entity, _, piece)| (entity, *piece));
+
+        if let Some((entity, piece)) = clicked_piece
+            && piece.color == game_state.position.side_to_move()
+        {
+            // Clicked own piece
+            if game_state.selected_entity == Some(entity) {
+                game_state.selected_entity = None;
+                game_state.selected_position = None;
+                piece_deselected_events.write(PieceDeselected);
+            } else {
+                game_state.selected_entity = Some(entity);
+                game_state.selected_position = Some(clicked_pos);
+                piece_selected_events.write(PieceSelected);
+            }
+        } else if let Some(selected_entity) = game_state.selected_entity
+            && let Some(selected_pos) = game_state.selected_position
+        {
+            // Clicked an empty tile or an enemy piece (potential capture)
+            let (from, to) = (Square::from(selected_pos), Square::from(clicked_pos));
+            let target = game_state
+                .position
+                .legal_moves()
+                .into_iter()
+                .find(|mv| mv.from == from && mv.to == to);
+            if let Some(mv) = target {
+                if mv.promotion.is_some() {
+                    promotion_requests.write(PromotionRequested {
+                        entity: selected_entity,
+                        from: selected_pos,
+                        to: clicked_pos,
+                    });
+                } else {
+                    move_made_events.write(MoveMade {
+                        entity: selected_entity,
+                        from: selected_pos,
+                        to: clicked_pos,
+                        promotion: None,
+                    });
+                }
+            } else if clicked_piece.is_none() {
+                game_state.selected_entity = None;
+                game_state.selected_position = None;
+                piece_deselected_events.write(PieceDeselected);
             }
         }
-
-        game_state.current_turn = match game_state.current_turn {
-            PieceColor::White => PieceColor::Black,
-            PieceColor::Black => PieceColor::White,
-        };
-        game_state.selected_entity = None;
-        game_state.selected_position = None;
     }
 }
 
-fn update_transform(transform: &mut Transform, pos: &GridPosition) {
-    transform.translation.x = pos.x as f32 * TILE_SIZE - SCREEN_WIDTH / 2.0 + TILE_SIZE / 2.0;
-    transform.translation.y = pos.y as f32 * TILE_SIZE - SCREEN_HEIGHT / 2.0 + TILE_SIZE / 2.0;
+/// A click while the computer or the other player is to move: one of the
+/// player's pieces starts a premove, the next square completes it, and any
+/// other click cancels it.
+fn queue_premove(
+    game_state: &mut GameState,
+    pieces: &Query<(Entity, &GridPosition, &Piece)>,
+    clicked: GridPosition,
+) {
+    let player = game_state.position.side_to_move().opposite();
+    let own_piece = pieces
+        .iter()
+        .any(|(_, pos, piece)| *pos == clicked && piece.color == player);
+
+    game_state.premove = match game_state.premove {
+        Some(Premove { from, to: None }) if from == clicked => None,
+        _ if own_piece => Some(Premove {
+            from: clicked,
+            to: None,
+        }),
+        Some(Premove { from, to: None }) => Some(Premove {
+            from,
+            to: Some(clicked),
+        }),
+        _ => None,
+    };
 }
 
-fn record_move_string(
-    kind: PieceType,
-    color: PieceColor,
-    from: GridPosition,
-    to: GridPosition,
-) -> String {
-    let from_str = get_chess_notation(from);
-    let to_str = get_chess_notation(to);
-    let piece_str = match kind {
-        PieceType::Pawn => "P",
-        PieceType::Knight => "N",
-        PieceType::Bishop => "B",
-        PieceType::Rook => "R",
-        PieceType::Queen => "Q",
-        PieceType::King => "K",
+/// Plays the premove once it is the player's turn and the board has caught
+/// up, or drops it if it is not legal in the new position. A pawn premoved
+/// to the last rank becomes a queen.
+fn premove_system(
+    mut game_state: ResMut<GameState>,
+    computer: Res<ComputerPlayer>,
+    network: Option<Res<NetworkGame>>,
+    server: Option<Res<ServerGame>>,
+    pieces: Query<(Entity, &GridPosition), With<Piece>>,
+    mut move_made_events: MessageWriter<MoveMade>,
+) {
+    let Some(premove) = game_state.premove else {
+        return;
     };
+    let side_to_move = game_state.position.side_to_move();
+    let waiting = computer.plays(side_to_move)
+        || network
+            .as_ref()
+            .is_some_and(|network| network.waits_for(side_to_move))
+        || server
+            .as_ref()
+            .is_some_and(|server| server.waits_for(side_to_move));
+    if !game_state.is_over() && waiting {
+        return;
+    }
+    game_state.premove = None;
 
-    let color_prefix = match color {
-        PieceColor::White => "W",
-        PieceColor::Black => "B",
+    if game_state.is_over() {
+        return;
+    }
+    let Some(to) = premove.to else {
+        return;
     };
-
-    format!("{color_prefix}: {piece_str} {from_str} -> {to_str}")
+    let legal = find_legal_move(&game_state.position, premove.from, to, None).or_else(|| {
+        find_legal_move(
+            &game_state.position,
+            premove.from,
+            to,
+            Some(PieceType::Queen),
+        )
+    });
+    if let Some(mv) = legal
+        && let Some((entity, _)) = pieces.iter().find(|(_, pos)| **pos == premove.from)
+    {
+        move_made_events.write(MoveMade {
+            entity,
+            from: premove.from,
+            to,
+            promotion: mv.promotion,
+        });
+    }
 }
 
-fn get_chess_notation(pos: GridPosition) -> String {
-    let file = (b'a' + pos.x as u8) as char;
-    let rank = pos.y + 1;
-    format!("{file}{rank}")
+/// Finds the legal move between two squares with the given promotion piece.
+fn find_legal_move(
+    position: &Position,
+    from: GridPosition,
+    to: GridPosition,
+    promotion: Option<PieceType>,
+) -> Option<Move> {
+    let (from, to) = (Square::from(from), Square::from(to));
+    position
+        .legal_moves()
+        .into_iter()
+        .find(|mv| mv.from == from && mv.to == to && mv.promotion == promotion)
 }
 
-fn check_game_status_system(
+/// Applies each move to the game and sets the piece entities moving to
+/// match: the mover and a castling rook glide to their new squares, and a
+/// captured piece, en passant included, fades out.
+fn move_execution_system(
+    mut commands: Commands,
+    mut move_events: MessageReader<MoveMade>,
     mut game_state: ResMut<GameState>,
-    pieces: Query<(&GridPosition, &Piece)>,
-    move_events: MessageReader<MoveMade>,
+    orientation: Res<BoardOrientation>,
+    theme: Res<ActiveTheme>,
+    mut pieces: Query<(Entity, &mut GridPosition, &mut Piece, &Transform)>,
 ) {
-    if !move_events.is_empty() {
-        let all_pieces: Vec<(GridPosition, Piece)> =
-            pieces.iter().map(|(pos, piece)| (*pos, *piece)).collect();
+    for event in move_events.read() {
+        let MoveMade {
+            entity,
+            from,
+            to,
+            promotion,
+        } = *event;
+
+        let Some(mv) = find_legal_move(&game_state.position, from, to, promotion) else {
+            continue;
+        };
+        if pieces.get(entity).is_err() {
+            continue;
+        }
 
-        if is_checkmate(game_state.current_turn, &all_pieces) {
-            let king_pos = all_pieces
+        game_state.record_move(mv);
+
+        // Mirror the move onto the piece entities
+        if let Some(captured) = mv.captured_square().map(GridPosition::from) {
+            let captured_entity = pieces
                 .iter()
-                .find(|(_, p)| p.kind == PieceType::King && p.color == game_state.current_turn)
-                .map(|(pos, _)| *pos);
-
-            let mut is_check = false;
-            if let Some(k_pos) = king_pos {
-                let opponent = match game_state.current_turn {
-                    PieceColor::White => PieceColor::Black,
-                    PieceColor::Black => PieceColor::White,
-                };
-                if is_square_under_attack(k_pos, &all_pieces, opponent) {
-                    is_check = true;
-                }
+                .find(|(e, pos, _, _)| **pos == captured && *e != entity)
+                .map(|(e, _, _, _)| e);
+            if let Some(e) = captured_entity {
+                commands
+                    .entity(e)
+                    .remove::<(Piece, GridPosition)>()
+                    .insert(FadeOut::default());
             }
+        }
 
-            if is_check {
-                let winner = match game_state.current_turn {
-                    PieceColor::White => PieceColor::Black,
-                    PieceColor::Black => PieceColor::White,
-                };
-                game_state.status = format!("Checkmate! {winner:?} wins.");
-            } else {
-                game_state.status = "Stalemate!".to_string();
+        if let Some((rook_from, rook_to)) = mv.castling_rook() {
+            let (rook_from, rook_to) = (GridPosition::from(rook_from), GridPosition::from(rook_to));
+            for (r_entity, mut r_pos, _, r_transform) in pieces.iter_mut() {
+                if *r_pos == rook_from {
+                    *r_pos = rook_to;
+                    commands.entity(r_entity).insert(MoveTween::new(
+                        r_transform.translation,
+                        orientation.grid_to_world(rook_to),
+                        None,
+                    ));
+                    break;
+                }
             }
-        } else {
-            game_state.status = format!("{:?}'s Turn", game_state.current_turn);
         }
-    }
-}
 
-fn highlight_moves_system(
-    game_state: Res<GameState>,
-    pieces: Query<(&GridPosition, &Piece)>,
-    mut cells: Query<(&BoardCell, &Transform, &mut Sprite)>,
-) {
-    const DARK_TILE: Color = Color::srgb_u8(32, 32, 35);
-    const DARK_RED_TILE: Color = Color::srgb_u8(100, 16, 16);
-    const LIGHT_TILE: Color = Color::srgb_u8(235, 235, 235);
-    const LIGHT_RED_TILE: Color = Color::srgb_u8(235, 120, 120);
-
-    let mut valid_moves = Vec::new();
-
-    if let Some(selected_entity) = game_state.selected_entity {
-        if let Ok((selected_pos, selected_piece)) = pieces.get(selected_entity) {
-            let all_pieces: Vec<(GridPosition, Piece)> =
-                pieces.iter().map(|(pos, piece)| (*pos, *piece)).collect();
-            valid_moves = get_valid_moves(*selected_pos, selected_piece, &all_pieces);
-            valid_moves.retain(|to| {
-                !is_self_check(
-                    *selected_pos,
-                    *to,
-                    selected_piece,
-                    &all_pieces,
-                    game_state.current_turn,
-                )
+        if let Ok((_, mut pos, mut piece, transform)) = pieces.get_mut(entity) {
+            *pos = to;
+
+            let promotion = mv.promotion.map(|kind| {
+                piece.kind = kind;
+                theme.0.pieces.sprite(kind, piece.color)
             });
-            valid_moves.push(*selected_pos);
-        }
-    }
 
-    for (_cell, transform, mut sprite) in cells.iter_mut() {
-        let x = ((transform.translation.x - TILE_SIZE / 2.0 + SCREEN_WIDTH / 2.0) / TILE_SIZE)
-            .round() as u32;
-        let y = ((transform.translation.y - TILE_SIZE / 2.0 + SCREEN_HEIGHT / 2.0) / TILE_SIZE)
-            .round() as u32;
-        let pos = GridPosition::new(x, y);
+            commands
+                .entity(entity)
+                .remove::<SnapBack>()
+                .insert(MoveTween::new(
+                    transform.translation,
+                    orientation.grid_to_world(to),
+                    promotion,
+                ));
+        }
 
-        let is_highlighted = valid_moves.contains(&pos);
+        game_state.selected_entity = None;
+        game_state.selected_position = None;
+    }
+}
 
-        if (pos.x + pos.y) % 2 == 0 {
-            sprite.color = if is_highlighted {
-                DARK_RED_TILE
-            } else {
-                DARK_TILE
-            };
-        } else {
-            sprite.color = if is_highlighted {
-                LIGHT_RED_TILE
-            } else {
-                LIGHT_TILE
-            };
+/// Applies resignations, aborts and draw offers to the game.
+fn game_action_system(mut game_state: ResMut<GameState>, mut actions: MessageReader<GameAction>) {
+    for action in actions.read() {
+        match *action {
+            GameAction::Resign(color) => game_state.resign(color),
+            GameAction::Abort(color) => game_state.abort(color),
+            GameAction::OfferDraw(color) => game_state.offer_draw(color),
+            GameAction::AcceptDraw(color) => game_state.accept_draw(color),
+            GameAction::DeclineDraw(color) => game_state.decline_draw(color),
+            GameAction::ClaimDraw(_) => game_state.claim_draw(),
         }
     }
 }
 
+fn check_game_status_system(
+    mut game_state: ResMut<GameState>,
+    mut move_events: MessageReader<MoveMade>,
+) {
+    // Reading marks the moves as seen, so each one is only handled once.
+    if move_events.read().count() > 0 {
+        game_state.update_status();
+    }
+}
+
 fn update_ui_system(
     game_state: Res<GameState>,
     mut status_query: Query<&mut Text, (With<StatusText>, Without<MoveHistoryText>)>,
@@ -448,17 +933,13 @@ fn update_ui_system(
             text.0 = format!("Status: {}", game_state.status);
         }
 
+        let lines = game_state.numbered_moves();
+        let start_index = lines.len().saturating_sub(20);
+        let history_str = lines[start_index..]
+            .iter()
+            .fold(String::from("Move History:\n"), |acc, m| acc + m + "\n");
         for mut text in history_query.iter_mut() {
-            let start_index = if game_state.move_history.len() > 20 {
-                game_state.move_history.len() - 20
-            } else {
-                0
-            };
-
-            let history_str = game_state.move_history[start_index..]
-                .iter()
-                .fold(String::from("Move History:\n"), |acc, m| acc + m + "\n");
-            text.0 = history_str;
+            text.0 = history_str.clone();
         }
     }
 }
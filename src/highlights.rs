@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+
+use crate::{
+    chess_board_plugin::{BoardOrientation, TILE_SIZE},
+    components::GridPosition,
+    resources::{GameState, Premove},
+    theme::{ActiveTheme, HighlightColors},
+};
+
+/// One kind of board highlight. Each is drawn as its own overlay above the
+/// tiles and below the pieces, so several can mark the same square.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Highlight {
+    /// The squares the last move left and reached.
+    LastMove,
+    /// The selected piece's square.
+    Selected,
+    /// The squares of a queued premove.
+    Premove,
+    /// A glow under the king in check.
+    Check,
+    /// A dot on an empty square the selected piece can move to, which
+    /// includes the square a pawn lands on when capturing en passant.
+    QuietMove,
+    /// A ring around a piece the selected piece can capture, which for en
+    /// passant is the pawn beside it.
+    Capture,
+}
+
+impl Highlight {
+    const ALL: [Highlight; 6] = [
+        Highlight::LastMove,
+        Highlight::Selected,
+        Highlight::Premove,
+        Highlight::Check,
+        Highlight::QuietMove,
+        Highlight::Capture,
+    ];
+
+    fn color(self, colors: &HighlightColors) -> Color {
+        match self {
+            Highlight::LastMove => colors.last_move,
+            Highlight::Selected => colors.selected,
+            Highlight::Premove => colors.premove,
+            Highlight::Check => colors.check,
+            Highlight::QuietMove | Highlight::Capture => colors.move_hint,
+        }
+    }
+
+    fn mesh(self) -> Mesh {
+        match self {
+            Highlight::LastMove | Highlight::Selected | Highlight::Premove => {
+                Rectangle::from_length(TILE_SIZE).into()
+            }
+            Highlight::Check => Circle::new(TILE_SIZE * 0.45).into(),
+            Highlight::QuietMove => Circle::new(TILE_SIZE * 0.15).into(),
+            Highlight::Capture => Annulus::new(TILE_SIZE * 0.4, TILE_SIZE * 0.48).into(),
+        }
+    }
+
+    /// Between the tiles at 0 and the coordinate labels at 1, later layers
+    /// on top.
+    fn z(self) -> f32 {
+        0.1 + 0.1 * self as u8 as f32
+    }
+}
+
+/// Mesh and material for each [`Highlight`], in [`Highlight::ALL`] order.
+#[derive(Resource)]
+pub struct HighlightAssets {
+    layers: Vec<(Handle<Mesh>, Handle<ColorMaterial>)>,
+}
+
+/// Marks every highlight overlay entity.
+#[derive(Component)]
+pub struct HighlightOverlay;
+
+pub fn setup_highlights(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    theme: Res<ActiveTheme>,
+) {
+    let layers = Highlight::ALL
+        .iter()
+        .map(|layer| {
+            (
+                meshes.add(layer.mesh()),
+                materials.add(ColorMaterial::from_color(layer.color(&theme.0.highlights))),
+            )
+        })
+        .collect();
+    commands.insert_resource(HighlightAssets { layers });
+}
+
+/// Recolors the highlight materials when the theme changes.
+pub fn highlight_theme_system(
+    theme: Res<ActiveTheme>,
+    assets: Res<HighlightAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    for (layer, (_, material)) in Highlight::ALL.iter().zip(&assets.layers) {
+        if let Some(material) = materials.get_mut(material) {
+            material.color = layer.color(&theme.0.highlights);
+        }
+    }
+}
+
+/// The highlights for the current game: the last move, a king in check, a
+/// premove, and the selected piece with its moves.
+pub fn highlights(game_state: &GameState) -> Vec<(GridPosition, Highlight)> {
+    let mut highlights = Vec::new();
+    let position = &game_state.position;
+
+    if let Some(mv) = game_state.moves.last() {
+        highlights.push((GridPosition::from(mv.from), Highlight::LastMove));
+        highlights.push((GridPosition::from(mv.to), Highlight::LastMove));
+    }
+
+    if position.in_check()
+        && let Some(king) = position.king_square(position.side_to_move())
+    {
+        highlights.push((GridPosition::from(king), Highlight::Check));
+    }
+
+    if let Some(Premove { from, to }) = game_state.premove {
+        highlights.push((from, Highlight::Premove));
+        if let Some(to) = to {
+            highlights.push((to, Highlight::Premove));
+        }
+    }
+
+    if let Some(selected) = game_state.selected_position {
+        highlights.push((selected, Highlight::Selected));
+        let from = selected.into();
+        for mv in position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == from)
+        {
+            let to = GridPosition::from(mv.to);
+            let targets = match mv.captured_square().map(GridPosition::from) {
+                Some(captured) if captured == to => vec![(to, Highlight::Capture)],
+                Some(captured) => vec![(to, Highlight::QuietMove), (captured, Highlight::Capture)],
+                None => vec![(to, Highlight::QuietMove)],
+            };
+            // The four promotions share a target square.
+            for highlight in targets {
+                if !highlights.contains(&highlight) {
+                    highlights.push(highlight);
+                }
+            }
+        }
+    }
+    highlights
+}
+
+/// Rebuilds the highlight overlays whenever the game or the board
+/// orientation changes.
+pub fn highlight_system(
+    mut commands: Commands,
+    assets: Res<HighlightAssets>,
+    game_state: Res<GameState>,
+    orientation: Res<BoardOrientation>,
+    overlays: Query<Entity, With<HighlightOverlay>>,
+) {
+    if !game_state.is_changed() && !orientation.is_changed() {
+        return;
+    }
+
+    for entity in overlays.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (pos, layer) in highlights(&game_state) {
+        let (mesh, material) = &assets.layers[layer as usize];
+        commands.spawn((
+            Mesh2d(mesh.clone()),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(orientation.grid_to_world(pos).extend(layer.z())),
+            HighlightOverlay,
+        ));
+    }
+}
@@ -0,0 +1,25 @@
+//! The Bevy chess client: plugins for the board, the pieces and the game,
+//! and the resources and messages they share. `main.rs` assembles them into
+//! the windowed app; [`game_plugin::HeadlessGamePlugin`] runs the game logic
+//! without a window, for tests.
+
+pub mod animation;
+pub mod chess_board_plugin;
+pub mod chess_piece_plugin;
+mod clipboard;
+pub mod components;
+pub mod computer_player;
+pub mod drag_and_drop;
+pub mod events;
+pub mod game_actions;
+pub mod game_clock;
+pub mod game_over;
+pub mod game_plugin;
+pub mod highlights;
+pub mod network;
+pub mod promotion_picker;
+pub mod resources;
+pub mod server_game;
+pub mod theme;
+pub mod uci_engine;
+pub mod uci_player;
@@ -1,33 +1,314 @@
-mod chess_board_plugin;
-mod chess_piece_plugin;
-mod components;
-mod events;
-mod game_plugin;
-mod resources;
-mod rules;
+use std::time::Duration;
 
 use bevy::prelude::*;
-use chess_board_plugin::ChessBoardPlugin;
-use chess_piece_plugin::ChessPiecePlugin;
-use game_plugin::GamePlugin;
+use chess_core::{DEFAULT_PORT, PgnGame, PieceColor, Position, TimeControl};
+use rust_bevy::{
+    animation::AnimationSettings,
+    chess_board_plugin::{BoardOrientation, ChessBoardPlugin, CoordinateLabels},
+    chess_piece_plugin::ChessPiecePlugin,
+    computer_player::ComputerPlayer,
+    game_clock::GameClock,
+    game_plugin::GamePlugin,
+    network::NetworkGame,
+    resources::GameState,
+    server_game::ServerGame,
+    theme::{ThemePlugin, Themes},
+    uci_engine::UciEngine,
+    uci_player::UciPlayer,
+};
 
 fn main() {
-    App::new()
-        .add_plugins(
-            DefaultPlugins
-                .set(WindowPlugin {
-                    primary_window: Some(Window {
-                        title: "Chess Game in Bevy".into(),
-                        resolution: (1200, 800).into(),
-                        resizable: false,
-                        ..Default::default()
-                    }),
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut app = App::new();
+    if let Some(engine) = options.engine {
+        app.insert_resource(engine);
+    }
+    if let Some(network) = options.network {
+        app.insert_resource(network);
+    }
+    if let Some(server) = options.server {
+        app.insert_resource(server);
+    }
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Chess Game in Bevy".into(),
+                    resolution: (1200, 800).into(),
+                    resize_constraints: WindowResizeConstraints {
+                        min_width: 480.0,
+                        min_height: 400.0,
+                        ..default()
+                    },
                     ..Default::default()
-                })
-                .set(ImagePlugin::default_nearest()),
-        )
-        .add_plugins(ChessBoardPlugin)
-        .add_plugins(ChessPiecePlugin)
-        .add_plugins(GamePlugin)
-        .run();
+                }),
+                ..Default::default()
+            })
+            .set(ImagePlugin::default_nearest()),
+    )
+    .insert_resource(options.game_state)
+    .insert_resource(options.computer)
+    .insert_resource(options.clock)
+    .insert_resource(options.animation)
+    .insert_resource(options.orientation)
+    .insert_resource(options.coordinates)
+    .insert_resource(options.themes)
+    .add_plugins(ThemePlugin)
+    .add_plugins(ChessBoardPlugin)
+    .add_plugins(ChessPiecePlugin)
+    .add_plugins(GamePlugin)
+    .run();
+}
+
+struct Options {
+    game_state: GameState,
+    computer: ComputerPlayer,
+    engine: Option<UciPlayer>,
+    network: Option<NetworkGame>,
+    server: Option<ServerGame>,
+    clock: GameClock,
+    animation: AnimationSettings,
+    orientation: BoardOrientation,
+    coordinates: CoordinateLabels,
+    themes: Themes,
+}
+
+/// What to ask a game server for.
+enum ServerRequest {
+    Create,
+    Join(String),
+    Watch(String),
+}
+
+/// Builds the opening game from `--fen "<FEN>"` or `--pgn <file>` (with
+/// `--game <n>` to pick a game from a multi-game file), but not both,
+/// defaulting to the standard initial position. `--computer white|black` lets the engine play
+/// a side, thinking for `--depth <plies>` or `--movetime <ms>` per move.
+/// `--engine <path>` has an external UCI engine play it instead of the
+/// built-in one, taking Black unless `--computer` says otherwise.
+/// `--time <control>` starts a timed game, e.g. `5+3` for five minutes plus
+/// three seconds a move, `15d5` with a five-second delay, or `25b10` with a
+/// ten-second Bronstein delay. `--animation <ms>` sets how long a move takes
+/// on screen, 0 for instant moves. The board faces the player's side,
+/// White unless the computer plays it; `--orientation white|black` picks a
+/// side, and `--auto-flip` turns the board to the side to move in a
+/// two-player game. `--no-coordinates` starts with the file and rank labels
+/// hidden. `--theme <file>` starts with a theme file from the assets folder,
+/// such as `themes/walnut.ron`. `--host <port>` waits for another player
+/// to connect over the network, playing White or the side given with
+/// `--color white|black`; `--join <address>` plays the host at `address`,
+/// such as `192.168.1.20` or `192.168.1.20:7878`. `--server <address>`
+/// plays on a game server instead: `--create` opens a room, with the side
+/// from `--color` and the time control from `--time`, `--room <code>`
+/// joins one and `--watch <code>` follows one without playing. Anything
+/// else is an error.
+fn parse_args() -> Result<Options, String> {
+    let mut fen = None;
+    let mut pgn_path = None;
+    let mut game_number = 1;
+    let mut computer = ComputerPlayer::default();
+    let mut engine_path = None;
+    let mut time_control = None;
+    let mut animation = AnimationSettings::default();
+    let mut bottom = None;
+    let mut auto_flip = false;
+    let mut coordinates = CoordinateLabels::default();
+    let mut themes = Themes::default();
+    let mut host_port = None;
+    let mut join_address = None;
+    let mut color = PieceColor::White;
+    let mut server_address = None;
+    let mut server_request = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fen" => fen = Some(args.next().ok_or("--fen needs a FEN string argument")?),
+            "--pgn" => pgn_path = Some(args.next().ok_or("--pgn needs a file argument")?),
+            "--game" => {
+                game_number = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&n: &usize| n > 0)
+                    .ok_or("--game needs a game number starting at 1")?;
+            }
+            "--computer" => {
+                computer.color = match args.next().as_deref() {
+                    Some("white") => Some(PieceColor::White),
+                    Some("black") => Some(PieceColor::Black),
+                    _ => return Err("--computer needs 'white' or 'black'".to_string()),
+                };
+            }
+            "--depth" => {
+                let depth = args.next().and_then(|n| n.parse().ok());
+                computer.limits.depth = Some(depth.ok_or("--depth needs a number of plies")?);
+            }
+            "--movetime" => {
+                let millis = args.next().and_then(|n| n.parse().ok());
+                let millis = millis.ok_or("--movetime needs a number of milliseconds")?;
+                computer.limits.movetime = Some(Duration::from_millis(millis));
+            }
+            "--time" => {
+                let control = args
+                    .next()
+                    .ok_or("--time needs a time control such as 5+3")?;
+                time_control = Some(
+                    control
+                        .parse::<TimeControl>()
+                        .map_err(|err| err.to_string())?,
+                );
+            }
+            "--animation" => {
+                let millis = args.next().and_then(|n| n.parse().ok());
+                let millis = millis.ok_or("--animation needs a number of milliseconds")?;
+                animation.duration = Duration::from_millis(millis);
+            }
+            "--orientation" => {
+                bottom = match args.next().as_deref() {
+                    Some("white") => Some(PieceColor::White),
+                    Some("black") => Some(PieceColor::Black),
+                    _ => return Err("--orientation needs 'white' or 'black'".to_string()),
+                };
+            }
+            "--auto-flip" => auto_flip = true,
+            "--no-coordinates" => coordinates.visible = false,
+            "--theme" => {
+                let path = args.next().ok_or("--theme needs a theme file")?;
+                themes = Themes::starting_with(&path);
+            }
+            "--host" => {
+                let port = args.next().and_then(|n| n.parse().ok());
+                host_port = Some(port.ok_or("--host needs a port number")?);
+            }
+            "--join" => {
+                let address = args.next().ok_or("--join needs the host's address")?;
+                join_address = Some(if address.contains(':') {
+                    address
+                } else {
+                    format!("{address}:{DEFAULT_PORT}")
+                });
+            }
+            "--server" => {
+                let address = args.next().ok_or("--server needs the server's address")?;
+                server_address = Some(if address.contains(':') {
+                    address
+                } else {
+                    format!("{address}:{}", chess_server::DEFAULT_PORT)
+                });
+            }
+            "--create" => server_request = Some(ServerRequest::Create),
+            "--room" => {
+                let code = args.next().ok_or("--room needs a room code")?;
+                server_request = Some(ServerRequest::Join(code));
+            }
+            "--watch" => {
+                let code = args.next().ok_or("--watch needs a room code")?;
+                server_request = Some(ServerRequest::Watch(code));
+            }
+            "--color" => {
+                color = match args.next().as_deref() {
+                    Some("white") => PieceColor::White,
+                    Some("black") => PieceColor::Black,
+                    _ => return Err("--color needs 'white' or 'black'".to_string()),
+                };
+            }
+            "--engine" => {
+                engine_path = Some(args.next().ok_or("--engine needs a path to a UCI engine")?);
+            }
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+
+    let game_state = match (fen, pgn_path) {
+        (Some(_), Some(_)) => return Err("--fen and --pgn cannot be used together".to_string()),
+        (Some(fen), None) => Position::from_fen(&fen)
+            .map(GameState::new)
+            .map_err(|err| format!("Invalid FEN: {err}"))?,
+        (None, Some(path)) => {
+            let text = std::fs::read_to_string(&path).map_err(|err| format!("{path}: {err}"))?;
+            let games = PgnGame::parse_all(&text).map_err(|err| format!("{path}: {err}"))?;
+            let game = games
+                .get(game_number - 1)
+                .ok_or_else(|| format!("{path} holds {} game(s)", games.len()))?;
+            GameState::from_pgn(game)
+        }
+        (None, None) => GameState::default(),
+    };
+
+    if engine_path.is_some() {
+        computer.color = computer.color.or(Some(PieceColor::Black));
+    }
+
+    let network = match (host_port, join_address) {
+        (Some(_), Some(_)) => return Err("--host and --join cannot be combined".to_string()),
+        (None, None) => None,
+        _ if computer.color.is_some() => {
+            return Err("a network game cannot have the computer play".to_string());
+        }
+        (Some(port), None) => Some(
+            NetworkGame::host(("0.0.0.0", port), color)
+                .map_err(|err| format!("Could not listen on port {port}: {err}"))?,
+        ),
+        (None, Some(address)) => Some(NetworkGame::join(address)),
+    };
+
+    let server = match (server_address, server_request) {
+        (None, None) => None,
+        (None, Some(_)) => return Err("--create, --room and --watch need --server".to_string()),
+        (Some(_), None) => return Err("--server needs --create, --room or --watch".to_string()),
+        (Some(_), Some(_)) if network.is_some() => {
+            return Err("--server cannot be combined with --host or --join".to_string());
+        }
+        (Some(_), Some(_)) if computer.color.is_some() => {
+            return Err("a server game cannot have the computer play".to_string());
+        }
+        (Some(address), Some(ServerRequest::Create)) => {
+            Some(ServerGame::create(address, color, time_control))
+        }
+        (Some(address), Some(ServerRequest::Join(code))) => Some(ServerGame::join(address, &code)),
+        (Some(address), Some(ServerRequest::Watch(code))) => {
+            Some(ServerGame::watch(address, &code))
+        }
+    };
+
+    let bottom = bottom.unwrap_or(match (computer.color, &network) {
+        (Some(color), _) => color.opposite(),
+        (None, Some(network)) => network.local_color().unwrap_or(PieceColor::White),
+        (None, None) => match &server {
+            Some(server) => server.local_color().unwrap_or(PieceColor::White),
+            None => PieceColor::White,
+        },
+    });
+    let orientation = BoardOrientation { bottom, auto_flip };
+
+    // Started last, so that no engine is left running when another
+    // argument turns out to be wrong.
+    let engine = match engine_path {
+        Some(path) => {
+            let engine = UciEngine::spawn(&path, std::iter::empty::<&str>())
+                .map_err(|err| format!("{path}: {err}"))?;
+            Some(UciPlayer::new(engine))
+        }
+        None => None,
+    };
+
+    Ok(Options {
+        game_state,
+        computer,
+        engine,
+        network,
+        server,
+        clock: GameClock::new(time_control),
+        animation,
+        orientation,
+        coordinates,
+        themes,
+    })
 }
@@ -0,0 +1,447 @@
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use bevy::platform::cell::SyncCell;
+use bevy::prelude::*;
+use chess_core::{Move, NetError, PROTOCOL_VERSION, PeerConnection, PeerListener, PeerMessage};
+
+use crate::{
+    chess_board_plugin::BoardOrientation,
+    components::{GridPosition, Piece, PieceColor},
+    events::{GameAction, MoveMade},
+    game_clock::GameClock,
+    game_plugin::replace_game,
+    resources::GameState,
+    theme::ActiveTheme,
+};
+
+/// How long a guest waits before trying to reach the host again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A game against a player on another machine. The host listens for the
+/// guest and picks the colors; the guest keeps trying to reach the host
+/// until it gets through, and again whenever the connection drops. Only
+/// present when one was asked for with `--host` or `--join`.
+#[derive(Resource)]
+pub struct NetworkGame {
+    role: Role,
+    /// The color played on this machine; a guest learns it from the host.
+    local: Option<PieceColor>,
+    peer: Option<Peer>,
+    /// The game both boards last agreed on: its starting FEN and the moves
+    /// played since. A guest has none while it waits for a `sync`.
+    agreed: Option<(String, Vec<Move>)>,
+    /// Why the last connection failed or ended, shown while waiting.
+    problem: Option<String>,
+}
+
+enum Role {
+    Host(PeerListener),
+    Guest {
+        address: String,
+        /// Time left until the next attempt to connect.
+        retry_in: Duration,
+        connecting: Option<JoinHandle<Result<PeerConnection, NetError>>>,
+    },
+}
+
+struct Peer {
+    connection: SyncCell<PeerConnection>,
+    /// Set once the other side has said hello.
+    ready: bool,
+}
+
+impl Peer {
+    fn new(connection: PeerConnection) -> Self {
+        Self {
+            connection: SyncCell::new(connection),
+            ready: false,
+        }
+    }
+
+    fn send(&mut self, message: PeerMessage) -> Result<(), NetError> {
+        self.connection.get().send(&message)
+    }
+}
+
+impl NetworkGame {
+    /// Hosts a game at `address`, such as `0.0.0.0:7878`, with this machine
+    /// playing `color`.
+    pub fn host(address: impl ToSocketAddrs, color: PieceColor) -> Result<Self, NetError> {
+        Ok(Self {
+            role: Role::Host(PeerListener::bind(address)?),
+            local: Some(color),
+            peer: None,
+            agreed: None,
+            problem: None,
+        })
+    }
+
+    /// Joins the game hosted at `address`, such as `192.168.1.20:7878`.
+    pub fn join(address: impl Into<String>) -> Self {
+        Self {
+            role: Role::Guest {
+                address: address.into(),
+                retry_in: Duration::ZERO,
+                connecting: None,
+            },
+            local: None,
+            peer: None,
+            agreed: None,
+            problem: None,
+        }
+    }
+
+    /// The address a host listens at.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match &self.role {
+            Role::Host(listener) => listener.local_addr(),
+            Role::Guest { .. } => None,
+        }
+    }
+
+    /// The color played on this machine, once known.
+    pub fn local_color(&self) -> Option<PieceColor> {
+        self.local
+    }
+
+    /// Whether both players are connected and know their colors.
+    pub fn is_connected(&self) -> bool {
+        self.local.is_some() && self.peer.as_ref().is_some_and(|peer| peer.ready)
+    }
+
+    /// Whether a move for `color` has to come from the other player, or
+    /// cannot be made at all because nobody is connected.
+    pub fn waits_for(&self, color: PieceColor) -> bool {
+        !self.is_connected() || self.local != Some(color)
+    }
+
+    fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host(_))
+    }
+
+    /// Accepts a guest, or starts, finishes or schedules an attempt to
+    /// reach the host.
+    fn connect(&mut self, delta: Duration) {
+        match &mut self.role {
+            Role::Host(listener) => match listener.accept() {
+                Ok(Some(mut connection)) => {
+                    if self.peer.is_some() {
+                        let busy = PeerMessage::Error("a game is already in progress".into());
+                        let _ = connection.send(&busy);
+                    } else {
+                        self.peer = Some(Peer::new(connection));
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => warn!("Could not accept a connection: {err}"),
+            },
+            Role::Guest {
+                address,
+                retry_in,
+                connecting,
+            } => {
+                if self.peer.is_some() {
+                    return;
+                }
+                if let Some(attempt) = connecting.take_if(|attempt| attempt.is_finished()) {
+                    let result = attempt
+                        .join()
+                        .unwrap_or(Err(NetError::Disconnected))
+                        .and_then(|mut connection| {
+                            let hello = PeerMessage::Hello {
+                                version: PROTOCOL_VERSION,
+                            };
+                            connection.send(&hello).map(|()| connection)
+                        });
+                    match result {
+                        Ok(connection) => self.peer = Some(Peer::new(connection)),
+                        Err(err) => {
+                            self.problem = Some(format!("Could not reach {address}: {err}"));
+                            *retry_in = RETRY_INTERVAL;
+                        }
+                    }
+                } else if connecting.is_none() {
+                    *retry_in = retry_in.saturating_sub(delta);
+                    if retry_in.is_zero() {
+                        let address = address.clone();
+                        *connecting = Some(thread::spawn(move || PeerConnection::connect(address)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The status line while the game cannot go on.
+    fn waiting_status(&self) -> String {
+        let waiting = match &self.role {
+            Role::Host(listener) => match listener.local_addr() {
+                Some(address) => format!("Waiting for an opponent on port {}...", address.port()),
+                None => "Waiting for an opponent...".to_string(),
+            },
+            Role::Guest { address, .. } => format!("Connecting to {address}..."),
+        };
+        match &self.problem {
+            Some(problem) => format!("{problem}. {waiting}"),
+            None => waiting,
+        }
+    }
+}
+
+/// The game as a `sync` message, and as the agreed game once it is sent.
+fn sync_message(game_state: &GameState) -> (PeerMessage, (String, Vec<Move>)) {
+    let message = PeerMessage::Sync {
+        initial: Box::new(game_state.initial_position.clone()),
+        moves: game_state.moves.clone(),
+    };
+    let agreed = (
+        game_state.initial_position.to_fen(),
+        game_state.moves.clone(),
+    );
+    (message, agreed)
+}
+
+/// The color that played the move at `index` in a game that started with
+/// `first` to move.
+pub(crate) fn mover(first: PieceColor, index: usize) -> PieceColor {
+    if index.is_multiple_of(2) {
+        first
+    } else {
+        first.opposite()
+    }
+}
+
+/// Connects to the other player, sends them the moves and actions made on
+/// this board, and plays theirs through `MoveMade` once they check out
+/// against the rules. An undo or a loaded game breaks the agreement between
+/// the boards; the host then sends its game, and the guest asks for it.
+pub fn network_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut network: ResMut<NetworkGame>,
+    mut game_state: ResMut<GameState>,
+    mut game_clock: ResMut<GameClock>,
+    mut orientation: ResMut<BoardOrientation>,
+    theme: Res<ActiveTheme>,
+    pieces: Query<(Entity, &GridPosition), With<Piece>>,
+    piece_entities: Query<Entity, With<Piece>>,
+    mut actions: MessageReader<GameAction>,
+    mut move_made_events: MessageWriter<MoveMade>,
+) {
+    let network = &mut *network;
+    network.connect(time.delta());
+
+    let was_connected = network.is_connected();
+    let result = exchange(
+        &mut commands,
+        network,
+        &mut game_state,
+        &mut game_clock,
+        &mut orientation,
+        &theme,
+        &pieces,
+        &piece_entities,
+        &mut actions,
+        &mut move_made_events,
+    );
+    if let Err(err) = result {
+        network.peer = None;
+        network.problem = Some(format!("Connection lost: {err}"));
+    }
+
+    if network.is_connected() {
+        if !was_connected {
+            network.problem = None;
+            game_state.update_status();
+        }
+    } else if !game_state.is_over() {
+        let status = network.waiting_status();
+        if game_state.status != status {
+            game_state.status = status;
+        }
+    }
+}
+
+/// Everything sent and received this frame. Fails when the connection has
+/// to be dropped.
+fn exchange(
+    commands: &mut Commands,
+    network: &mut NetworkGame,
+    game_state: &mut GameState,
+    game_clock: &mut GameClock,
+    orientation: &mut BoardOrientation,
+    theme: &ActiveTheme,
+    pieces: &Query<(Entity, &GridPosition), With<Piece>>,
+    piece_entities: &Query<Entity, With<Piece>>,
+    actions: &mut MessageReader<GameAction>,
+    move_made_events: &mut MessageWriter<MoveMade>,
+) -> Result<(), NetError> {
+    let is_host = network.is_host();
+    let connected = network.is_connected();
+    let Some(peer) = network.peer.as_mut() else {
+        actions.clear();
+        return Ok(());
+    };
+
+    if connected && let Some(local) = network.local {
+        // Moves played here since the boards last agreed.
+        if let Some((fen, agreed)) = &mut network.agreed {
+            let first = game_state.initial_position.side_to_move();
+            let extends = *fen == game_state.initial_position.to_fen()
+                && game_state.moves.starts_with(agreed)
+                && (agreed.len()..game_state.moves.len()).all(|i| mover(first, i) == local);
+            if extends {
+                for &mv in &game_state.moves[agreed.len()..] {
+                    peer.send(PeerMessage::Move(mv.to_string()))?;
+                }
+                agreed.clone_from(&game_state.moves);
+            } else if is_host {
+                let (sync, game) = sync_message(game_state);
+                peer.send(sync)?;
+                network.agreed = Some(game);
+            } else {
+                peer.send(PeerMessage::RequestSync)?;
+                network.agreed = None;
+            }
+        }
+
+        for action in actions.read() {
+            let message = match *action {
+                GameAction::Resign(color) if color == local => PeerMessage::Resign,
+                GameAction::Abort(color) if color == local => PeerMessage::Abort,
+                GameAction::OfferDraw(color) if color == local => PeerMessage::OfferDraw,
+                GameAction::AcceptDraw(color) if color == local => PeerMessage::AcceptDraw,
+                GameAction::DeclineDraw(color) if color == local => PeerMessage::DeclineDraw,
+                GameAction::ClaimDraw(color) if color == local => PeerMessage::ClaimDraw,
+                _ => continue,
+            };
+            peer.send(message)?;
+        }
+    } else {
+        actions.clear();
+    }
+
+    loop {
+        let message = match peer.connection.get().try_recv() {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(NetError::Disconnected) => return Err(NetError::Disconnected),
+            Err(err) => {
+                warn!("Ignoring a message from the other player: {err}");
+                continue;
+            }
+        };
+
+        match message {
+            PeerMessage::Hello { version } => {
+                if version != PROTOCOL_VERSION {
+                    let refusal = format!("protocol version {PROTOCOL_VERSION} required");
+                    let _ = peer.send(PeerMessage::Error(refusal));
+                    return Err(NetError::Version(version));
+                }
+                peer.ready = true;
+                if is_host && let Some(local) = network.local {
+                    peer.send(PeerMessage::Hello {
+                        version: PROTOCOL_VERSION,
+                    })?;
+                    peer.send(PeerMessage::Color(local.opposite()))?;
+                    let (sync, game) = sync_message(game_state);
+                    peer.send(sync)?;
+                    network.agreed = Some(game);
+                }
+            }
+            PeerMessage::Color(color) if !is_host => {
+                network.local = Some(color);
+                orientation.bottom = color;
+                orientation.auto_flip = false;
+            }
+            PeerMessage::Sync { initial, moves } if !is_host => {
+                let mut new_state = GameState::new(*initial);
+                for &mv in &moves {
+                    new_state.record_move(mv);
+                }
+                new_state.update_status();
+                network.agreed = Some((new_state.initial_position.to_fen(), moves));
+                replace_game(
+                    commands,
+                    piece_entities,
+                    &theme.0.pieces,
+                    orientation,
+                    game_state,
+                    game_clock,
+                    new_state,
+                );
+                // The new pieces are spawned at the end of the frame; later
+                // messages wait for them.
+                return Ok(());
+            }
+            PeerMessage::RequestSync if is_host => {
+                let (sync, game) = sync_message(game_state);
+                peer.send(sync)?;
+                network.agreed = Some(game);
+            }
+            PeerMessage::Move(uci) => {
+                let Some(remote) = network.local.map(PieceColor::opposite) else {
+                    continue;
+                };
+                let Some((_, agreed)) = &mut network.agreed else {
+                    // A guest waiting for a `sync`, which replaces the game.
+                    continue;
+                };
+                let legal = (!game_state.is_over()
+                    && game_state.position.side_to_move() == remote
+                    && game_state.moves == *agreed)
+                    .then(|| game_state.position.parse_uci(&uci))
+                    .flatten();
+                let from = legal.map(|mv| GridPosition::from(mv.from));
+                match legal.zip(pieces.iter().find(|(_, pos)| Some(**pos) == from)) {
+                    Some((mv, (entity, _))) => {
+                        agreed.push(mv);
+                        move_made_events.write(MoveMade {
+                            entity,
+                            from: GridPosition::from(mv.from),
+                            to: GridPosition::from(mv.to),
+                            promotion: mv.promotion,
+                        });
+                        // The next move is checked against the position
+                        // this one leads to.
+                        return Ok(());
+                    }
+                    None if is_host => {
+                        warn!("The other player sent {uci}, which does not fit this game");
+                        let (sync, game) = sync_message(game_state);
+                        peer.send(sync)?;
+                        network.agreed = Some(game);
+                    }
+                    None => {
+                        warn!("The other player sent {uci}, which does not fit this game");
+                        peer.send(PeerMessage::RequestSync)?;
+                        network.agreed = None;
+                    }
+                }
+            }
+            PeerMessage::Resign
+            | PeerMessage::Abort
+            | PeerMessage::OfferDraw
+            | PeerMessage::AcceptDraw
+            | PeerMessage::DeclineDraw
+            | PeerMessage::ClaimDraw => {
+                let Some(remote) = network.local.map(PieceColor::opposite) else {
+                    continue;
+                };
+                match message {
+                    PeerMessage::Resign => game_state.resign(remote),
+                    PeerMessage::Abort => game_state.abort(remote),
+                    PeerMessage::OfferDraw => game_state.offer_draw(remote),
+                    PeerMessage::AcceptDraw => game_state.accept_draw(remote),
+                    PeerMessage::ClaimDraw => game_state.claim_draw(),
+                    _ => game_state.decline_draw(remote),
+                }
+            }
+            PeerMessage::Error(reason) => return Err(NetError::Refused(reason)),
+            // Messages only the other role sends.
+            PeerMessage::Color(_) | PeerMessage::Sync { .. } | PeerMessage::RequestSync => {}
+        }
+    }
+}
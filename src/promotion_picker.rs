@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+
+use crate::{
+    chess_board_plugin::{BoardOrientation, TILE_SIZE},
+    components::{GridPosition, PieceColor, PieceType},
+    events::{MoveMade, PieceDeselected, PromotionRequested, TileClicked},
+    resources::GameState,
+    theme::{ActiveTheme, PieceSprites},
+};
+
+const BACKDROP_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.5);
+const OPTION_COLOR: Color = Color::srgb_u8(200, 200, 190);
+
+/// The promotion waiting for the player to pick a piece. While it is set,
+/// board clicks go to the picker instead of `selection_logic_system`.
+#[derive(Resource, Default)]
+pub struct PromotionPicker {
+    pending: Option<PendingPromotion>,
+}
+
+impl PromotionPicker {
+    pub fn is_open(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PendingPromotion {
+    entity: Entity,
+    from: GridPosition,
+    to: GridPosition,
+    color: PieceColor,
+}
+
+/// Every entity that makes up the picker overlay.
+#[derive(Component)]
+pub struct PromotionPickerUi;
+
+/// One of the four choices, stacked from the promotion square towards the
+/// middle of the board.
+fn option_squares(to: GridPosition, color: PieceColor) -> [(GridPosition, PieceType); 4] {
+    let mut step = 0;
+    PieceType::PROMOTIONS.map(|kind| {
+        let y = match color {
+            PieceColor::White => to.y - step,
+            PieceColor::Black => to.y + step,
+        };
+        step += 1;
+        (GridPosition::new(to.x, y), kind)
+    })
+}
+
+/// Resolves clicks and Escape while the picker is open, then opens it for a
+/// newly requested promotion. Clicking one of the pieces emits the move;
+/// Escape or a click anywhere else cancels it.
+pub fn promotion_picker_system(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut picker: ResMut<PromotionPicker>,
+    mut game_state: ResMut<GameState>,
+    theme: Res<ActiveTheme>,
+    orientation: Res<BoardOrientation>,
+    overlay: Query<Entity, With<PromotionPickerUi>>,
+    mut tile_clicked_events: MessageReader<TileClicked>,
+    mut promotion_requests: MessageReader<PromotionRequested>,
+    mut move_made_events: MessageWriter<MoveMade>,
+    mut piece_deselected_events: MessageWriter<PieceDeselected>,
+) {
+    if let Some(pending) = picker.pending {
+        let mut choice = None;
+        let mut cancelled = keys.just_pressed(KeyCode::Escape);
+        for event in tile_clicked_events.read() {
+            match option_squares(pending.to, pending.color)
+                .into_iter()
+                .find(|(square, _)| *square == event.position)
+            {
+                Some((_, kind)) => choice = Some(kind),
+                None => cancelled = true,
+            }
+        }
+
+        if let Some(kind) = choice {
+            move_made_events.write(MoveMade {
+                entity: pending.entity,
+                from: pending.from,
+                to: pending.to,
+                promotion: Some(kind),
+            });
+        } else if cancelled {
+            game_state.selected_entity = None;
+            game_state.selected_position = None;
+            piece_deselected_events.write(PieceDeselected);
+        }
+
+        if choice.is_some() || cancelled {
+            picker.pending = None;
+            for entity in overlay.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    } else {
+        // Clicks that arrive while the picker is closed belong to the board.
+        tile_clicked_events.clear();
+    }
+
+    if let Some(request) = promotion_requests.read().last() {
+        let color = game_state.position.side_to_move();
+        picker.pending = Some(PendingPromotion {
+            entity: request.entity,
+            from: request.from,
+            to: request.to,
+            color,
+        });
+        spawn_picker(
+            &mut commands,
+            &theme.0.pieces,
+            &orientation,
+            request.to,
+            color,
+        );
+    }
+}
+
+fn spawn_picker(
+    commands: &mut Commands,
+    sprites: &PieceSprites,
+    orientation: &BoardOrientation,
+    to: GridPosition,
+    color: PieceColor,
+) {
+    commands.spawn((
+        Sprite {
+            color: BACKDROP_COLOR,
+            custom_size: Some(Vec2::splat(8.0 * TILE_SIZE)),
+            ..default()
+        },
+        // The board is centred on the world origin.
+        Transform::from_xyz(0.0, 0.0, 4.0),
+        PromotionPickerUi,
+    ));
+
+    for (square, kind) in option_squares(to, color) {
+        let centre = orientation.grid_to_world(square);
+
+        commands.spawn((
+            Sprite {
+                color: OPTION_COLOR,
+                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                ..default()
+            },
+            Transform::from_translation(centre.extend(5.0)),
+            PromotionPickerUi,
+        ));
+        commands.spawn((
+            sprites.sprite(kind, color),
+            Transform::from_translation(centre.extend(6.0)),
+            PromotionPickerUi,
+        ));
+    }
+}
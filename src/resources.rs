@@ -1,23 +1,378 @@
 use bevy::prelude::*;
+use chess_core::{DrawReason, GameResult, Move, PgnGame, Position, Termination};
+
 use crate::components::{GridPosition, PieceColor};
 
+/// A move picked on the board while the opponent is to move: the piece,
+/// then its target once one is clicked. It is played as soon as it is the
+/// player's turn, if it is legal by then.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Premove {
+    pub from: GridPosition,
+    pub to: Option<GridPosition>,
+}
+
 #[derive(Resource)]
 pub struct GameState {
     pub selected_entity: Option<Entity>,
     pub selected_position: Option<GridPosition>,
-    pub current_turn: PieceColor,
+    /// A move queued while the opponent is to move.
+    pub premove: Option<Premove>,
+    /// Position the game started from, used to number the move history.
+    pub initial_position: Position,
+    pub position: Position,
     pub status: String,
+    /// How the game ended, once it has; the board then ignores input.
+    pub result: Option<GameResult>,
+    /// Set when the game was called off before it got going; it ends
+    /// without a result.
+    pub aborted: bool,
+    /// The side whose clock ran out, if one did.
+    pub flagged: Option<PieceColor>,
+    /// The side that resigned, if one did.
+    pub resigned: Option<PieceColor>,
+    /// The side that left the game part way through, if one did.
+    pub abandoned: Option<PieceColor>,
+    /// The side offering a draw, until the other accepts, declines or moves.
+    pub draw_offer: Option<PieceColor>,
+    /// Set once a draw offer has been accepted.
+    pub draw_agreed: bool,
+    /// The draw claimed by threefold repetition or the fifty-move rule, if
+    /// one was.
+    pub draw_claimed: Option<DrawReason>,
+    /// Moves played so far, in SAN.
     pub move_history: Vec<String>,
+    /// The same moves, for replaying and export.
+    pub moves: Vec<Move>,
+    /// Moves taken back by [`GameState::undo`], most recent last.
+    pub redo_stack: Vec<Move>,
+    /// Tags of a game loaded from PGN, kept for saving it again.
+    pub pgn_tags: Vec<(String, String)>,
+    /// Counts the games started this session, so that an engine can tell a
+    /// new game from the last one even when both start from the same
+    /// position. See [`GameState::start_new_game`].
+    pub game_number: u64,
 }
 
 impl Default for GameState {
     fn default() -> Self {
-        Self {
+        Self::new(Position::new())
+    }
+}
+
+impl GameState {
+    pub fn new(position: Position) -> Self {
+        let mut game_state = Self {
             selected_entity: None,
             selected_position: None,
-            current_turn: PieceColor::White,
-            status: "White's Turn".to_string(),
+            premove: None,
+            initial_position: position.clone(),
+            position,
+            status: String::new(),
+            result: None,
+            aborted: false,
+            flagged: None,
+            resigned: None,
+            abandoned: None,
+            draw_offer: None,
+            draw_agreed: false,
+            draw_claimed: None,
             move_history: Vec::new(),
+            moves: Vec::new(),
+            redo_stack: Vec::new(),
+            pgn_tags: Vec::new(),
+            game_number: 0,
+        };
+        game_state.update_status();
+        game_state
+    }
+
+    /// Replays the main line of a PGN game.
+    pub fn from_pgn(game: &PgnGame) -> Self {
+        let mut game_state = Self::new(game.initial_position().clone());
+        game_state.pgn_tags = game.tags().to_vec();
+        for pgn_move in game.moves() {
+            game_state.record_move(pgn_move.mv);
+        }
+        game_state.update_status();
+        game_state
+    }
+
+    /// The same game without the resignation, abort, flag fall or agreed
+    /// draw that ended it, so that play can go on from the final position.
+    pub fn for_analysis(&self) -> Self {
+        let mut game_state = Self::new(self.initial_position.clone());
+        game_state.pgn_tags.clone_from(&self.pgn_tags);
+        game_state.game_number = self.game_number;
+        for &mv in &self.moves {
+            game_state.record_move(mv);
+        }
+        game_state.update_status();
+        game_state
+    }
+
+    /// Replaces this game with `next`, counted as the following game.
+    pub fn start_new_game(&mut self, next: Self) {
+        let game_number = self.game_number + 1;
+        *self = next;
+        self.game_number = game_number;
+    }
+
+    /// Whether the game has ended, with a result or aborted.
+    pub fn is_over(&self) -> bool {
+        self.result.is_some() || self.aborted
+    }
+
+    /// The game so far as PGN, with the result and, for anything but a
+    /// normal ending, the `Termination` tag.
+    pub fn to_pgn(&self) -> PgnGame {
+        let mut game = PgnGame::new(self.initial_position.clone());
+        for (name, value) in &self.pgn_tags {
+            game.set_tag(name, value);
+        }
+        for &mv in &self.moves {
+            game.push(mv);
+        }
+
+        match self.result {
+            Some(result) => {
+                let termination = result.termination().pgn_tag();
+                if termination != "normal" {
+                    game.set_tag("Termination", termination);
+                }
+                game.set_result(result.pgn_result());
+            }
+            None => game.set_result("*"),
+        }
+        game
+    }
+
+    /// Ends the game because `color` ran out of time. It loses, unless the
+    /// opponent could not have mated it, which makes the game a draw.
+    pub fn flag_fall(&mut self, color: PieceColor) {
+        self.flagged = Some(color);
+        self.selected_entity = None;
+        self.selected_position = None;
+        self.update_status();
+    }
+
+    /// Ends the game with `color` resigning.
+    pub fn resign(&mut self, color: PieceColor) {
+        if self.is_over() {
+            return;
+        }
+        self.resigned = Some(color);
+        self.draw_offer = None;
+        self.selected_entity = None;
+        self.selected_position = None;
+        self.premove = None;
+        self.update_status();
+    }
+
+    /// Has `color` offer a draw. Only one offer can stand at a time.
+    pub fn offer_draw(&mut self, color: PieceColor) {
+        if self.is_over() || self.draw_offer.is_some() {
+            return;
+        }
+        self.draw_offer = Some(color);
+        self.update_status();
+    }
+
+    /// Has `color` accept its opponent's draw offer, ending the game.
+    pub fn accept_draw(&mut self, color: PieceColor) {
+        if self.is_over() || self.draw_offer != Some(color.opposite()) {
+            return;
+        }
+        self.draw_offer = None;
+        self.draw_agreed = true;
+        self.selected_entity = None;
+        self.selected_position = None;
+        self.premove = None;
+        self.update_status();
+    }
+
+    /// Has `color` call the game off. Before each side has moved, the game
+    /// is aborted without a result; after that, `color` has abandoned it and
+    /// loses.
+    pub fn abort(&mut self, color: PieceColor) {
+        if self.is_over() {
+            return;
+        }
+        if self.moves.len() < 2 {
+            self.aborted = true;
+        } else {
+            self.abandoned = Some(color);
+        }
+        self.draw_offer = None;
+        self.selected_entity = None;
+        self.selected_position = None;
+        self.premove = None;
+        self.update_status();
+    }
+
+    /// Claims a draw by threefold repetition or the fifty-move rule, ending
+    /// the game, if the current position allows one. Either player may
+    /// claim it.
+    pub fn claim_draw(&mut self) {
+        if self.is_over() {
+            return;
+        }
+        let Some(reason) = self.position.claimable_draw() else {
+            return;
+        };
+        self.draw_claimed = Some(reason);
+        self.draw_offer = None;
+        self.selected_entity = None;
+        self.selected_position = None;
+        self.premove = None;
+        self.update_status();
+    }
+
+    /// Has `color` turn down its opponent's draw offer.
+    pub fn decline_draw(&mut self, color: PieceColor) {
+        if self.draw_offer == Some(color.opposite()) {
+            self.draw_offer = None;
+            self.update_status();
+        }
+    }
+
+    /// Plays `mv`, which must be legal, and records it in the history.
+    /// Replaying the next undone move keeps the rest of the redo stack; any
+    /// other move discards it. Moving declines a draw offered by the
+    /// opponent.
+    pub fn record_move(&mut self, mv: Move) {
+        let mover = self.position.side_to_move();
+        if self.draw_offer == Some(mover.opposite()) {
+            self.draw_offer = None;
+        }
+        if self.redo_stack.last() == Some(&mv) {
+            self.redo_stack.pop();
+        } else {
+            self.redo_stack.clear();
+        }
+        self.move_history.push(self.position.san(mv));
+        self.moves.push(mv);
+        self.position.make_move(mv);
+    }
+
+    /// Takes back the last move, returning it. This reopens a finished game
+    /// however it ended: a resignation, abort, flag fall or agreed or claimed
+    /// draw is
+    /// taken back along with the move, just as a checkmate is.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        self.flagged = None;
+        self.resigned = None;
+        self.abandoned = None;
+        self.aborted = false;
+        self.draw_agreed = false;
+        self.draw_claimed = None;
+        self.move_history.pop();
+        self.position.unmake_move();
+        self.redo_stack.push(mv);
+        self.selected_entity = None;
+        self.selected_position = None;
+        self.premove = None;
+        self.update_status();
+        Some(mv)
+    }
+
+    /// Replays the most recently undone move, returning it.
+    pub fn redo(&mut self) -> Option<Move> {
+        let mv = *self.redo_stack.last()?;
+        self.record_move(mv);
+        self.selected_entity = None;
+        self.selected_position = None;
+        self.premove = None;
+        self.update_status();
+        Some(mv)
+    }
+
+    /// The move history as numbered lines such as `1. e4 e5`, starting with
+    /// `1... e5` when Black moved first.
+    pub fn numbered_moves(&self) -> Vec<String> {
+        let mut number = self.initial_position.fullmove_number();
+        let mut moves = self.move_history.iter();
+        let mut lines = Vec::new();
+
+        if self.initial_position.side_to_move() == PieceColor::Black
+            && let Some(first) = moves.next()
+        {
+            lines.push(format!("{number}... {first}"));
+            number += 1;
+        }
+
+        let moves: Vec<&String> = moves.collect();
+        for pair in moves.chunks(2) {
+            lines.push(match pair {
+                [white, black] => format!("{number}. {white} {black}"),
+                [white] => format!("{number}. {white}"),
+                _ => unreachable!(),
+            });
+            number += 1;
+        }
+        lines
+    }
+
+    /// Recomputes the status line and the result from the current position,
+    /// the clock, a resignation, an abandoned or aborted game, or an agreed
+    /// or claimed draw. Threefold repetition and the fifty-move rule only
+    /// end the game when claimed; until then the status says a draw can be
+    /// claimed. Fivefold repetition and the seventy-five-move rule end it
+    /// on their own.
+    pub fn update_status(&mut self) {
+        let side_to_move = self.position.side_to_move();
+        self.result = None;
+
+        if self.aborted {
+            self.status = "Game aborted.".to_string();
+        } else if let Some(loser) = self.flagged {
+            let winner = loser.opposite();
+            if self.position.has_mating_material(winner) {
+                self.result = Some(GameResult::win(winner, Termination::Timeout));
+                self.status = format!("{loser:?} ran out of time. {winner:?} wins.");
+            } else {
+                let reason = DrawReason::TimeoutVsInsufficientMaterial;
+                self.result = Some(GameResult::Draw(reason.into()));
+                self.status = format!("Draw by {reason}.");
+            }
+        } else if let Some(loser) = self.resigned {
+            let winner = loser.opposite();
+            self.result = Some(GameResult::win(winner, Termination::Resignation));
+            self.status = format!("{loser:?} resigned. {winner:?} wins.");
+        } else if let Some(loser) = self.abandoned {
+            let winner = loser.opposite();
+            self.result = Some(GameResult::win(winner, Termination::Abandonment));
+            self.status = format!("{loser:?} abandoned the game. {winner:?} wins.");
+        } else if self.draw_agreed {
+            let reason = DrawReason::Agreement;
+            self.result = Some(GameResult::Draw(reason.into()));
+            self.status = format!("Draw by {reason}.");
+        } else if let Some(reason) = self.draw_claimed {
+            self.result = Some(GameResult::Draw(reason.into()));
+            self.status = format!("Draw by {reason}.");
+        } else if self.position.is_checkmate() {
+            let winner = side_to_move.opposite();
+            self.result = Some(GameResult::win(winner, Termination::Checkmate));
+            self.status = format!("Checkmate! {winner:?} wins.");
+        } else if let Some(reason) = self.position.automatic_draw() {
+            self.result = Some(GameResult::Draw(reason.into()));
+            self.status = match reason {
+                DrawReason::Stalemate => "Stalemate!".to_string(),
+                _ => format!("Draw by {reason}."),
+            };
+        } else {
+            let mut notes = Vec::new();
+            if let Some(offer) = self.draw_offer {
+                notes.push(format!("{offer:?} offers a draw"));
+            }
+            if let Some(reason) = self.position.claimable_draw() {
+                notes.push(format!("draw by {reason} can be claimed"));
+            }
+            self.status = format!("{side_to_move:?}'s Turn");
+            if !notes.is_empty() {
+                self.status += &format!(" ({})", notes.join("; "));
+            }
         }
     }
 }
@@ -0,0 +1,395 @@
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use bevy::platform::cell::SyncCell;
+use bevy::prelude::*;
+use chess_core::{Move, TimeControl};
+use chess_server::{ClientMessage, Seat, ServerConnection, ServerError, ServerMessage};
+
+use crate::{
+    chess_board_plugin::BoardOrientation,
+    components::{GridPosition, Piece, PieceColor},
+    events::{GameAction, MoveMade},
+    game_clock::GameClock,
+    game_plugin::replace_game,
+    network::mover,
+    resources::GameState,
+    theme::ActiveTheme,
+};
+
+/// How long the client waits before trying to reach the server again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A game on a `chess-server`, played or watched. The server keeps the
+/// game: moves made here are sent for it to check, and whatever it
+/// announces is played here. The client keeps trying to reach the server
+/// until it gets through, and rejoins its room whenever the connection
+/// drops. Only present when one was asked for with `--server`.
+#[derive(Resource)]
+pub struct ServerGame {
+    address: String,
+    /// What to ask for once connected: the room to create, join or watch.
+    /// After the first `joined` it is the room this client is in.
+    request: ClientMessage,
+    /// Time left until the next attempt to connect.
+    retry_in: Duration,
+    connecting: Option<JoinHandle<Result<ServerConnection, ServerError>>>,
+    connection: Option<SyncCell<ServerConnection>>,
+    room: Option<String>,
+    seat: Option<Seat>,
+    /// Whether a player sits on the other side.
+    opponent_present: bool,
+    /// The moves the server's game had when this board last matched it;
+    /// `None` while waiting for the server to send its game.
+    agreed: Option<Vec<Move>>,
+    /// Why the last connection failed or the server turned a request down,
+    /// shown while waiting.
+    problem: Option<String>,
+}
+
+impl ServerGame {
+    fn new(address: String, request: ClientMessage) -> Self {
+        Self {
+            address,
+            request,
+            retry_in: Duration::ZERO,
+            connecting: None,
+            connection: None,
+            room: None,
+            seat: None,
+            opponent_present: false,
+            agreed: None,
+            problem: None,
+        }
+    }
+
+    /// Opens a room on the server at `address`, playing `color`.
+    pub fn create(
+        address: impl Into<String>,
+        color: PieceColor,
+        control: Option<TimeControl>,
+    ) -> Self {
+        Self::new(address.into(), ClientMessage::Create { color, control })
+    }
+
+    /// Takes the free seat in room `code`, or watches if both are taken.
+    pub fn join(address: impl Into<String>, code: &str) -> Self {
+        Self::new(address.into(), ClientMessage::Join(code.to_string()))
+    }
+
+    /// Watches the game in room `code`.
+    pub fn watch(address: impl Into<String>, code: &str) -> Self {
+        Self::new(address.into(), ClientMessage::Watch(code.to_string()))
+    }
+
+    /// The code of the room this client is in, to share with the opponent.
+    pub fn room(&self) -> Option<&str> {
+        self.room.as_deref()
+    }
+
+    pub fn seat(&self) -> Option<Seat> {
+        self.seat
+    }
+
+    /// The color played on this machine: the one asked for when creating a
+    /// room, and otherwise the seat the server gave, once known.
+    pub fn local_color(&self) -> Option<PieceColor> {
+        match (self.seat, &self.request) {
+            (Some(Seat::Player(color)), _) => Some(color),
+            (None, ClientMessage::Create { color, .. }) => Some(*color),
+            _ => None,
+        }
+    }
+
+    /// Whether this client is connected and seated in a room.
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some() && self.seat.is_some()
+    }
+
+    /// Whether a move for `color` has to come from the server: it is the
+    /// opponent's, this client only watches, the opponent is away or the
+    /// server's game has not arrived yet.
+    pub fn waits_for(&self, color: PieceColor) -> bool {
+        !self.is_connected()
+            || self.agreed.is_none()
+            || self.seat != Some(Seat::Player(color))
+            || !self.opponent_present
+    }
+
+    /// Whether the game can go on: both players are there, or this client
+    /// only watches it.
+    fn is_active(&self) -> bool {
+        self.is_connected() && (self.opponent_present || self.seat == Some(Seat::Spectator))
+    }
+
+    /// Starts, finishes or schedules an attempt to reach the server.
+    fn connect(&mut self, delta: Duration) {
+        if self.connection.is_some() {
+            return;
+        }
+        if let Some(attempt) = self.connecting.take_if(|attempt| attempt.is_finished()) {
+            let result = attempt
+                .join()
+                .unwrap_or(Err(ServerError::Disconnected))
+                .and_then(|mut connection| {
+                    connection.send(self.request.clone()).map(|()| connection)
+                });
+            match result {
+                Ok(connection) => self.connection = Some(SyncCell::new(connection)),
+                Err(err) => {
+                    self.problem = Some(format!("Could not reach {}: {err}", self.address));
+                    self.retry_in = RETRY_INTERVAL;
+                }
+            }
+        } else if self.connecting.is_none() {
+            self.retry_in = self.retry_in.saturating_sub(delta);
+            if self.retry_in.is_zero() {
+                let address = self.address.clone();
+                self.connecting = Some(thread::spawn(move || ServerConnection::connect(&address)));
+            }
+        }
+    }
+
+    /// Forgets the connection after it failed; the next attempt rejoins.
+    fn disconnect(&mut self, err: &ServerError) {
+        self.connection = None;
+        self.seat = None;
+        self.agreed = None;
+        self.opponent_present = false;
+        self.problem = Some(format!("Connection lost: {err}"));
+    }
+
+    /// The status line while the game cannot go on.
+    fn waiting_status(&self) -> String {
+        let waiting = match (&self.room, self.connection.is_some()) {
+            (Some(room), true) => format!("Room {room}: waiting for an opponent..."),
+            _ => format!("Connecting to {}...", self.address),
+        };
+        match &self.problem {
+            Some(problem) => format!("{problem}. {waiting}"),
+            None => waiting,
+        }
+    }
+
+    fn send(&mut self, message: ClientMessage) -> Result<(), ServerError> {
+        match &mut self.connection {
+            Some(connection) => connection.get().send(message),
+            None => Err(ServerError::Disconnected),
+        }
+    }
+}
+
+/// Connects to the server, sends it the moves and actions made on this
+/// board, and plays the ones it announces through `MoveMade`. When the
+/// boards stop matching, after an undo, a loaded game or a move the server
+/// turned down, the client asks for the server's game again.
+pub fn server_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut server: ResMut<ServerGame>,
+    mut game_state: ResMut<GameState>,
+    mut game_clock: ResMut<GameClock>,
+    mut orientation: ResMut<BoardOrientation>,
+    theme: Res<ActiveTheme>,
+    pieces: Query<(Entity, &GridPosition), With<Piece>>,
+    piece_entities: Query<Entity, With<Piece>>,
+    mut actions: MessageReader<GameAction>,
+    mut move_made_events: MessageWriter<MoveMade>,
+) {
+    let server = &mut *server;
+    server.connect(time.delta());
+
+    let was_active = server.is_active();
+    let result = exchange(
+        &mut commands,
+        server,
+        &mut game_state,
+        &mut game_clock,
+        &mut orientation,
+        &theme,
+        &pieces,
+        &piece_entities,
+        &mut actions,
+        &mut move_made_events,
+    );
+    if let Err(err) = result {
+        server.disconnect(&err);
+    }
+
+    if server.is_active() {
+        if !was_active {
+            server.problem = None;
+            game_state.update_status();
+        }
+    } else if !game_state.is_over() {
+        let status = server.waiting_status();
+        if game_state.status != status {
+            game_state.status = status;
+        }
+    }
+}
+
+/// Everything sent and received this frame. Fails when the connection has
+/// to be dropped.
+fn exchange(
+    commands: &mut Commands,
+    server: &mut ServerGame,
+    game_state: &mut GameState,
+    game_clock: &mut GameClock,
+    orientation: &mut BoardOrientation,
+    theme: &ActiveTheme,
+    pieces: &Query<(Entity, &GridPosition), With<Piece>>,
+    piece_entities: &Query<Entity, With<Piece>>,
+    actions: &mut MessageReader<GameAction>,
+    move_made_events: &mut MessageWriter<MoveMade>,
+) -> Result<(), ServerError> {
+    if server.connection.is_none() {
+        actions.clear();
+        return Ok(());
+    }
+
+    // Moves played here since the boards last matched.
+    let local = match server.seat {
+        Some(Seat::Player(color)) => Some(color),
+        _ => None,
+    };
+    if let Some(agreed) = &mut server.agreed {
+        let first = game_state.initial_position.side_to_move();
+        let extends = game_state.moves.starts_with(agreed)
+            && (agreed.len()..game_state.moves.len()).all(|i| Some(mover(first, i)) == local);
+        if extends {
+            let new_moves = game_state.moves[agreed.len()..].to_vec();
+            agreed.clone_from(&game_state.moves);
+            for mv in new_moves {
+                server.send(ClientMessage::Move(mv.to_string()))?;
+            }
+        } else {
+            server.agreed = None;
+            server.send(ClientMessage::RequestSync)?;
+        }
+    }
+
+    for action in actions.read() {
+        let message = match *action {
+            GameAction::Resign(color) if Some(color) == local => ClientMessage::Resign,
+            GameAction::Abort(color) if Some(color) == local => ClientMessage::Abort,
+            GameAction::OfferDraw(color) if Some(color) == local => ClientMessage::OfferDraw,
+            GameAction::AcceptDraw(color) if Some(color) == local => ClientMessage::AcceptDraw,
+            GameAction::DeclineDraw(color) if Some(color) == local => ClientMessage::DeclineDraw,
+            GameAction::ClaimDraw(color) if Some(color) == local => ClientMessage::ClaimDraw,
+            _ => continue,
+        };
+        server.send(message)?;
+    }
+
+    loop {
+        let Some(connection) = &mut server.connection else {
+            return Ok(());
+        };
+        let message = match connection.get().try_recv() {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(ServerError::Malformed(text)) => {
+                warn!("Ignoring a message from the server: {text}");
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        match message {
+            ServerMessage::Joined {
+                room,
+                seat,
+                control,
+            } => {
+                server.request = match seat {
+                    Seat::Player(_) => ClientMessage::Join(room.clone()),
+                    Seat::Spectator => ClientMessage::Watch(room.clone()),
+                };
+                server.room = Some(room);
+                server.seat = Some(seat);
+                server.opponent_present = false;
+                server.problem = None;
+                if let Seat::Player(color) = seat {
+                    orientation.bottom = color;
+                    orientation.auto_flip = false;
+                }
+                if game_clock.control() != control {
+                    *game_clock = GameClock::new(control);
+                }
+            }
+            ServerMessage::Presence { color, present } => {
+                if server.seat == Some(Seat::Player(color.opposite())) {
+                    server.opponent_present = present;
+                }
+            }
+            ServerMessage::Game { initial, moves } => {
+                let mut new_state = GameState::new(*initial);
+                for &mv in &moves {
+                    new_state.record_move(mv);
+                }
+                new_state.update_status();
+                server.agreed = Some(moves);
+                replace_game(
+                    commands,
+                    piece_entities,
+                    &theme.0.pieces,
+                    orientation,
+                    game_state,
+                    game_clock,
+                    new_state,
+                );
+                // The new pieces are spawned at the end of the frame; later
+                // messages wait for them.
+                return Ok(());
+            }
+            ServerMessage::Move(uci) => {
+                let Some(agreed) = &mut server.agreed else {
+                    // Waiting for the game, which replaces this one.
+                    continue;
+                };
+                let legal = (game_state.moves == *agreed)
+                    .then(|| game_state.position.parse_uci(&uci))
+                    .flatten();
+                let from = legal.map(|mv| GridPosition::from(mv.from));
+                match legal.zip(pieces.iter().find(|(_, pos)| Some(**pos) == from)) {
+                    Some((mv, (entity, _))) => {
+                        agreed.push(mv);
+                        move_made_events.write(MoveMade {
+                            entity,
+                            from: GridPosition::from(mv.from),
+                            to: GridPosition::from(mv.to),
+                            promotion: mv.promotion,
+                        });
+                        // The next move is checked against the position
+                        // this one leads to.
+                        return Ok(());
+                    }
+                    None => {
+                        warn!("The server sent {uci}, which does not fit this game");
+                        server.agreed = None;
+                        server.send(ClientMessage::RequestSync)?;
+                    }
+                }
+            }
+            ServerMessage::Clock { white, black } => game_clock.sync(white, black),
+            ServerMessage::Resign(color) => game_state.resign(color),
+            ServerMessage::Abort(color) => game_state.abort(color),
+            ServerMessage::OfferDraw(color) => game_state.offer_draw(color),
+            ServerMessage::AcceptDraw(color) => game_state.accept_draw(color),
+            ServerMessage::DeclineDraw(color) => game_state.decline_draw(color),
+            ServerMessage::ClaimDraw(_) => game_state.claim_draw(),
+            ServerMessage::Flag(color) => {
+                if game_state.flagged.is_none() {
+                    game_state.flag_fall(color);
+                }
+            }
+            ServerMessage::Error(reason) => {
+                warn!("The server refused: {reason}");
+                if server.room.is_none() {
+                    server.problem = Some(format!("The server refused: {reason}"));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use std::fmt;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    chess_board_plugin::TILE_SIZE,
+    components::{GridPosition, PieceColor, PieceType},
+};
+
+/// Theme files bundled in `assets/themes`, in the order T cycles through
+/// them.
+const THEME_FILES: [&str; 3] = [
+    "themes/classic.ron",
+    "themes/walnut.ron",
+    "themes/ocean.ron",
+];
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Theme>()
+            .register_asset_loader(ThemeLoader)
+            .init_resource::<Themes>()
+            .init_resource::<ActiveTheme>()
+            .add_systems(Startup, load_themes)
+            .add_systems(Update, (theme_switch_system, apply_theme_system).chain());
+    }
+}
+
+/// Colors, highlights and piece set for the board, loaded from a RON file.
+/// With the `hot-reload` feature, edits to the file show up immediately.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub board: BoardColors,
+    pub highlights: HighlightColors,
+    pub pieces: PieceSprites,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoardColors {
+    pub dark: Color,
+    pub light: Color,
+}
+
+impl BoardColors {
+    /// The tile color of `pos`; a1 is dark.
+    pub fn tile(&self, pos: GridPosition) -> Color {
+        if (pos.x + pos.y).is_multiple_of(2) {
+            self.dark
+        } else {
+            self.light
+        }
+    }
+
+    /// The other tile color, for text that has to stand out on `pos`.
+    pub fn contrast(&self, pos: GridPosition) -> Color {
+        if (pos.x + pos.y).is_multiple_of(2) {
+            self.light
+        } else {
+            self.dark
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct HighlightColors {
+    pub last_move: Color,
+    pub selected: Color,
+    pub premove: Color,
+    pub check: Color,
+    /// Dots on quiet moves and rings around captures.
+    pub move_hint: Color,
+}
+
+/// Where the piece sprites come from: cells of one sprite sheet, or one
+/// image per piece. Both list pieces in [`PieceType`] order.
+#[derive(Clone, Debug)]
+pub enum PieceSprites {
+    Atlas {
+        texture: Handle<Image>,
+        layout: Handle<TextureAtlasLayout>,
+        white: [usize; 6],
+        black: [usize; 6],
+    },
+    Images {
+        white: [Handle<Image>; 6],
+        black: [Handle<Image>; 6],
+    },
+}
+
+impl PieceSprites {
+    /// A tile-sized sprite of the piece.
+    pub fn sprite(&self, kind: PieceType, color: PieceColor) -> Sprite {
+        let sprite = match self {
+            PieceSprites::Atlas {
+                texture,
+                layout,
+                white,
+                black,
+            } => {
+                let cells = match color {
+                    PieceColor::White => white,
+                    PieceColor::Black => black,
+                };
+                Sprite::from_atlas_image(
+                    texture.clone(),
+                    TextureAtlas {
+                        layout: layout.clone(),
+                        index: cells[kind as usize],
+                    },
+                )
+            }
+            PieceSprites::Images { white, black } => {
+                let images = match color {
+                    PieceColor::White => white,
+                    PieceColor::Black => black,
+                };
+                Sprite::from_image(images[kind as usize].clone())
+            }
+        };
+        Sprite {
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
+            ..sprite
+        }
+    }
+}
+
+impl Theme {
+    /// The built-in look, the same as `themes/classic.ron`, drawn from the
+    /// 8-Bit Chess sheet in `texture` cut up by `layout`.
+    pub fn classic(texture: Handle<Image>, layout: Handle<TextureAtlasLayout>) -> Self {
+        Theme {
+            name: "Classic".to_string(),
+            board: BoardColors {
+                dark: Color::srgb_u8(32, 32, 35),
+                light: Color::srgb_u8(235, 235, 235),
+            },
+            highlights: HighlightColors {
+                last_move: Color::srgba(0.85, 0.75, 0.2, 0.45),
+                selected: Color::srgba(0.2, 0.6, 0.3, 0.5),
+                premove: Color::srgba(0.25, 0.4, 0.85, 0.5),
+                check: Color::srgba(0.9, 0.1, 0.1, 0.6),
+                move_hint: Color::srgba(0.1, 0.1, 0.1, 0.35),
+            },
+            pieces: PieceSprites::Atlas {
+                texture,
+                layout,
+                white: [7, 8, 9, 10, 11, 12],
+                black: [14, 15, 16, 17, 18, 19],
+            },
+        }
+    }
+}
+
+/// The theme the board is drawn with. It starts as the built-in classic
+/// theme, which stays in use if no theme file can be loaded.
+#[derive(Resource)]
+pub struct ActiveTheme(pub Theme);
+
+impl FromWorld for ActiveTheme {
+    fn from_world(world: &mut World) -> Self {
+        let texture = world.resource::<AssetServer>().load("pieces.png");
+        let layout = TextureAtlasLayout::from_grid(UVec2::new(10, 10), 7, 4, None, None);
+        let layout = world
+            .resource_mut::<Assets<TextureAtlasLayout>>()
+            .add(layout);
+        Self(Theme::classic(texture, layout))
+    }
+}
+
+/// The theme files to choose from and the one in use.
+#[derive(Resource)]
+pub struct Themes {
+    paths: Vec<String>,
+    handles: Vec<Handle<Theme>>,
+    current: usize,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self {
+            paths: THEME_FILES.map(String::from).to_vec(),
+            handles: Vec::new(),
+            current: 0,
+        }
+    }
+}
+
+impl Themes {
+    /// The bundled themes, starting with the file at `path`, given relative
+    /// to the assets folder. A file that is not bundled is added to the list.
+    pub fn starting_with(path: &str) -> Self {
+        let mut themes = Self::default();
+        themes.current = match themes.paths.iter().position(|p| p == path) {
+            Some(i) => i,
+            None => {
+                themes.paths.push(path.to_string());
+                themes.paths.len() - 1
+            }
+        };
+        themes
+    }
+
+    fn current(&self) -> Option<&Handle<Theme>> {
+        self.handles.get(self.current)
+    }
+}
+
+fn load_themes(asset_server: Res<AssetServer>, mut themes: ResMut<Themes>) {
+    themes.handles = themes
+        .paths
+        .iter()
+        .map(|path| asset_server.load(path.clone()))
+        .collect();
+}
+
+/// The button that switches to the next theme, like the T key.
+#[derive(Component)]
+pub struct ThemeButton;
+
+#[derive(Component)]
+pub struct ThemeButtonText;
+
+pub fn theme_label(theme: &ActiveTheme) -> String {
+    format!("Theme: {}", theme.0.name)
+}
+
+/// T or the theme button switches to the next theme file.
+fn theme_switch_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ThemeButton>)>,
+    mut themes: ResMut<Themes>,
+) {
+    let pressed = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+    if (pressed || keys.just_pressed(KeyCode::KeyT)) && !themes.paths.is_empty() {
+        themes.current = (themes.current + 1) % themes.paths.len();
+    }
+}
+
+/// Makes the current theme active once it has loaded, when it is switched
+/// to, and whenever its file changes on disk.
+fn apply_theme_system(
+    themes: Res<Themes>,
+    assets: Res<Assets<Theme>>,
+    mut events: MessageReader<AssetEvent<Theme>>,
+    mut active: ResMut<ActiveTheme>,
+    mut labels: Query<&mut Text, With<ThemeButtonText>>,
+) {
+    let Some(handle) = themes.current() else {
+        return;
+    };
+    let reloaded = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == handle.id()
+        }
+        _ => false,
+    });
+    if !reloaded && !themes.is_changed() {
+        return;
+    }
+
+    // A theme that is still loading is applied by its load event.
+    if let Some(theme) = assets.get(handle) {
+        active.0 = theme.clone();
+        for mut text in labels.iter_mut() {
+            text.0 = theme_label(&active);
+        }
+    }
+}
+
+/// A theme file as written: colors as `#RRGGBB` or `#RRGGBBAA` hex strings
+/// and image paths relative to the assets folder.
+#[derive(Deserialize)]
+struct ThemeFile {
+    name: String,
+    board: BoardFile,
+    highlights: HighlightFile,
+    pieces: PieceSetFile,
+}
+
+#[derive(Deserialize)]
+struct BoardFile {
+    dark: String,
+    light: String,
+}
+
+#[derive(Deserialize)]
+struct HighlightFile {
+    last_move: String,
+    selected: String,
+    premove: String,
+    check: String,
+    move_hint: String,
+}
+
+#[derive(Deserialize)]
+enum PieceSetFile {
+    /// A sprite sheet of `columns` by `rows` cells of `cell_size` pixels,
+    /// with the cell of each piece.
+    Atlas {
+        image: String,
+        cell_size: (u32, u32),
+        columns: u32,
+        rows: u32,
+        white: Vec<usize>,
+        black: Vec<usize>,
+    },
+    Images {
+        white: Vec<String>,
+        black: Vec<String>,
+    },
+}
+
+/// Why a theme file could not be loaded.
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(std::io::Error),
+    Syntax(ron::error::SpannedError),
+    InvalidColor(String),
+    /// A piece list without exactly one entry per piece type.
+    PieceCount(usize),
+    /// An atlas cell index past the end of the sheet.
+    InvalidCell(usize),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(err) => write!(f, "could not read theme: {err}"),
+            ThemeError::Syntax(err) => write!(f, "invalid theme: {err}"),
+            ThemeError::InvalidColor(color) => write!(f, "invalid color '{color}'"),
+            ThemeError::PieceCount(count) => {
+                write!(f, "expected 6 pieces, pawn to king, found {count}")
+            }
+            ThemeError::InvalidCell(index) => {
+                write!(f, "atlas cell {index} is outside the sheet")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+fn parse_color(hex: &str) -> Result<Color, ThemeError> {
+    Srgba::hex(hex)
+        .map(Color::from)
+        .map_err(|_| ThemeError::InvalidColor(hex.to_string()))
+}
+
+/// A list of pieces from pawn to king.
+fn per_piece<T>(list: Vec<T>) -> Result<[T; 6], ThemeError> {
+    let count = list.len();
+    list.try_into().map_err(|_| ThemeError::PieceCount(count))
+}
+
+#[derive(Default)]
+struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    type Asset = Theme;
+    type Settings = ();
+    type Error = ThemeError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Theme, ThemeError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(ThemeError::Io)?;
+        let file: ThemeFile = ron::de::from_bytes(&bytes).map_err(ThemeError::Syntax)?;
+
+        let pieces = match file.pieces {
+            PieceSetFile::Atlas {
+                image,
+                cell_size,
+                columns,
+                rows,
+                white,
+                black,
+            } => {
+                let (white, black) = (per_piece(white)?, per_piece(black)?);
+                let cells = (columns * rows) as usize;
+                if let Some(&index) = white.iter().chain(&black).find(|&&index| index >= cells) {
+                    return Err(ThemeError::InvalidCell(index));
+                }
+                let layout = TextureAtlasLayout::from_grid(
+                    UVec2::new(cell_size.0, cell_size.1),
+                    columns,
+                    rows,
+                    None,
+                    None,
+                );
+                PieceSprites::Atlas {
+                    texture: load_context.load(image),
+                    layout: load_context.add_labeled_asset("layout".to_string(), layout),
+                    white,
+                    black,
+                }
+            }
+            PieceSetFile::Images { white, black } => PieceSprites::Images {
+                white: per_piece(white)?.map(|path| load_context.load(path)),
+                black: per_piece(black)?.map(|path| load_context.load(path)),
+            },
+        };
+
+        Ok(Theme {
+            name: file.name,
+            board: BoardColors {
+                dark: parse_color(&file.board.dark)?,
+                light: parse_color(&file.board.light)?,
+            },
+            highlights: HighlightColors {
+                last_move: parse_color(&file.highlights.last_move)?,
+                selected: parse_color(&file.highlights.selected)?,
+                premove: parse_color(&file.highlights.premove)?,
+                check: parse_color(&file.highlights.check)?,
+                move_hint: parse_color(&file.highlights.move_hint)?,
+            },
+            pieces,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
@@ -0,0 +1,185 @@
+//! Runs a UCI engine executable as a child process and talks to it over its
+//! standard input and output, for [`crate::uci_player::UciPlayer`].
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess_core::{EngineMessage, Move, Position, SearchLimits, go_command, position_command};
+
+/// How long an engine may take to answer `uci` and `isready`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long an engine gets to exit after `quit` before it is killed.
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Why talking to an engine failed.
+#[derive(Debug)]
+pub enum UciError {
+    /// The executable could not be started.
+    Spawn(io::Error),
+    /// Writing a command to the engine failed.
+    Io(io::Error),
+    /// The engine did not answer with this in time.
+    Timeout(&'static str),
+    /// The engine closed its output, usually because it exited.
+    Disconnected,
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciError::Spawn(err) => write!(f, "could not start the engine: {err}"),
+            UciError::Io(err) => write!(f, "could not write to the engine: {err}"),
+            UciError::Timeout(expected) => write!(f, "the engine did not send '{expected}'"),
+            UciError::Disconnected => write!(f, "the engine exited"),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
+
+/// A running engine process. Dropping it sends `quit` and, if the engine
+/// does not exit promptly, kills it.
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    name: Option<String>,
+}
+
+impl UciEngine {
+    /// Starts the engine at `program` with `args` and waits for `uciok` and
+    /// `readyok`.
+    pub fn spawn<I, S>(program: impl AsRef<OsStr>, args: I) -> Result<Self, UciError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(UciError::Spawn)?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            name: None,
+        };
+        engine.send("uci")?;
+        loop {
+            match engine.recv_timeout(HANDSHAKE_TIMEOUT) {
+                Ok(EngineMessage::IdName(name)) => engine.name = Some(name),
+                Ok(EngineMessage::UciOk) => break,
+                Ok(_) => {}
+                Err(UciError::Timeout(_)) => return Err(UciError::Timeout("uciok")),
+                Err(err) => return Err(err),
+            }
+        }
+        engine.sync()?;
+        Ok(engine)
+    }
+
+    /// The name from the engine's `id name` line.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Writes one command line to the engine.
+    pub fn send(&mut self, command: &str) -> Result<(), UciError> {
+        writeln!(self.stdin, "{command}")
+            .and_then(|()| self.stdin.flush())
+            .map_err(UciError::Io)
+    }
+
+    /// Sends `isready` and waits for `readyok`, discarding what comes before.
+    pub fn sync(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.recv_timeout(remaining) {
+                Ok(EngineMessage::ReadyOk) => return Ok(()),
+                Ok(_) => {}
+                Err(UciError::Timeout(_)) => return Err(UciError::Timeout("readyok")),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Tells the engine that the next search belongs to a different game.
+    pub fn new_game(&mut self) -> Result<(), UciError> {
+        self.send("ucinewgame")?;
+        self.sync()
+    }
+
+    /// Starts a search of the position reached from `initial` by `moves`.
+    /// The result arrives later as [`EngineMessage::BestMove`].
+    pub fn go(
+        &mut self,
+        initial: &Position,
+        moves: &[Move],
+        limits: SearchLimits,
+    ) -> Result<(), UciError> {
+        self.send(&position_command(initial, moves))?;
+        self.send(&go_command(limits))
+    }
+
+    /// Asks the engine to end its search; it still answers with `bestmove`.
+    pub fn stop(&mut self) -> Result<(), UciError> {
+        self.send("stop")
+    }
+
+    /// The next message if one has arrived, without waiting.
+    pub fn try_recv(&mut self) -> Result<Option<EngineMessage>, UciError> {
+        match self.lines.try_recv() {
+            Ok(line) => Ok(Some(EngineMessage::parse(&line))),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(UciError::Disconnected),
+        }
+    }
+
+    /// The next message, waiting at most `timeout` for it.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<EngineMessage, UciError> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(EngineMessage::parse(&line)),
+            Err(RecvTimeoutError::Timeout) => Err(UciError::Timeout("a reply")),
+            Err(RecvTimeoutError::Disconnected) => Err(UciError::Disconnected),
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + QUIT_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,157 @@
+use bevy::platform::cell::SyncCell;
+use bevy::prelude::*;
+use chess_core::{EngineMessage, UciInfo};
+
+use crate::{
+    components::{GridPosition, Piece},
+    computer_player::ComputerPlayer,
+    events::MoveMade,
+    promotion_picker::PromotionPicker,
+    resources::GameState,
+    uci_engine::UciEngine,
+};
+
+/// An external UCI engine that plays the computer's side in place of the
+/// built-in search. Only present when one was given with `--engine`.
+#[derive(Resource)]
+pub struct UciPlayer {
+    engine: SyncCell<UciEngine>,
+    name: String,
+    /// [`GameState::game_number`] of the game the engine last searched, to
+    /// send `ucinewgame` when another game starts.
+    game: Option<u64>,
+    pending: Option<PendingReply>,
+    /// `bestmove` replies still owed for searches that were stopped because
+    /// the game moved on; they are read and thrown away.
+    stale_replies: usize,
+    /// Set once the engine has exited or misbehaved; it is not asked again.
+    failed: bool,
+}
+
+/// The search the engine is working on: number of moves played and position
+/// key when it was started.
+#[derive(Clone, Copy)]
+struct PendingReply {
+    ply: usize,
+    key: u64,
+}
+
+impl UciPlayer {
+    pub fn new(engine: UciEngine) -> Self {
+        let name = engine.name().unwrap_or("Engine").to_string();
+        Self {
+            engine: SyncCell::new(engine),
+            name,
+            game: None,
+            pending: None,
+            stale_replies: 0,
+            failed: false,
+        }
+    }
+
+    fn fail(&mut self, game_state: &mut GameState, error: impl std::fmt::Display) {
+        self.failed = true;
+        self.pending = None;
+        game_state.status = format!("{}: {error}", self.name);
+    }
+
+    fn thinking(&self, game_state: &GameState, info: Option<&UciInfo>) -> String {
+        let side = game_state.position.side_to_move();
+        let mut status = format!("{side:?} ({}) is thinking...", self.name);
+        if let Some(UciInfo {
+            depth: Some(depth),
+            score: Some(score),
+            ..
+        }) = info
+        {
+            status.push_str(&format!(" depth {depth}, {score}"));
+        }
+        status
+    }
+}
+
+/// Sends the game to the engine when it is the computer's turn and applies
+/// its `bestmove` through `MoveMade`, like a click on the board would. While
+/// it searches, its `info` lines keep the status text up to date.
+pub fn uci_move_system(
+    computer: Res<ComputerPlayer>,
+    mut uci: ResMut<UciPlayer>,
+    mut game_state: ResMut<GameState>,
+    picker: Res<PromotionPicker>,
+    pieces: Query<(Entity, &GridPosition), With<Piece>>,
+    mut move_made_events: MessageWriter<MoveMade>,
+) {
+    let uci = &mut *uci;
+    if uci.failed {
+        return;
+    }
+
+    let ply = game_state.moves.len();
+    let key = game_state.position.zobrist_key();
+    let our_turn = computer.plays(game_state.position.side_to_move()) && !game_state.is_over();
+
+    if let Some(pending) = uci.pending
+        && (pending.ply != ply || pending.key != key || !our_turn)
+    {
+        uci.pending = None;
+        uci.stale_replies += 1;
+        if let Err(err) = uci.engine.get().stop() {
+            return uci.fail(&mut game_state, err);
+        }
+        game_state.update_status();
+    }
+
+    loop {
+        let message = match uci.engine.get().try_recv() {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(err) => return uci.fail(&mut game_state, err),
+        };
+        match message {
+            EngineMessage::Info(info) if uci.pending.is_some() && uci.stale_replies == 0 => {
+                game_state.status = uci.thinking(&game_state, Some(&info));
+            }
+            EngineMessage::BestMove { .. } if uci.stale_replies > 0 => uci.stale_replies -= 1,
+            EngineMessage::BestMove { mv, .. } if uci.pending.take().is_some() => {
+                let Some(mv) = mv
+                    .as_deref()
+                    .and_then(|mv| game_state.position.parse_uci(mv))
+                else {
+                    let mv = mv.unwrap_or_else(|| "(none)".to_string());
+                    return uci.fail(&mut game_state, format!("sent illegal move {mv}"));
+                };
+                let from = GridPosition::from(mv.from);
+                if let Some((entity, _)) = pieces.iter().find(|(_, pos)| **pos == from) {
+                    move_made_events.write(MoveMade {
+                        entity,
+                        from,
+                        to: GridPosition::from(mv.to),
+                        promotion: mv.promotion,
+                    });
+                }
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    if uci.pending.is_none() && our_turn && !picker.is_open() {
+        let game = game_state.game_number;
+        if uci.game != Some(game) {
+            if let Err(err) = uci.engine.get().new_game() {
+                return uci.fail(&mut game_state, err);
+            }
+            uci.game = Some(game);
+        }
+        let started = uci.engine.get().go(
+            &game_state.initial_position,
+            &game_state.moves,
+            computer.limits,
+        );
+        if let Err(err) = started {
+            return uci.fail(&mut game_state, err);
+        }
+        uci.pending = Some(PendingReply { ply, key });
+        game_state.status = uci.thinking(&game_state, None);
+    }
+}
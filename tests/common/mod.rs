@@ -0,0 +1,106 @@
+//! Fixtures shared by the headless game tests. Each test binary uses a
+//! different subset of them.
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use chess_core::Square;
+use rust_bevy::{
+    components::{GridPosition, Piece},
+    events::TileClicked,
+    game_plugin::HeadlessGamePlugin,
+    resources::GameState,
+};
+
+/// How long [`run_until`] waits before failing the test.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn square(name: &str) -> GridPosition {
+    GridPosition::from(Square::from_algebraic(name).unwrap())
+}
+
+/// A headless game app, after its first frame. `setup` runs before the
+/// plugin is added, to insert a `GameState`, `NetworkGame` or `ServerGame`.
+pub fn app_with(setup: impl FnOnce(&mut App)) -> App {
+    let mut app = App::new();
+    setup(&mut app);
+    app.add_plugins((MinimalPlugins, HeadlessGamePlugin));
+    app.update();
+    app
+}
+
+pub fn game_state(app: &App) -> &GameState {
+    app.world().resource::<GameState>()
+}
+
+pub fn history(app: &App) -> Vec<String> {
+    game_state(app).move_history.clone()
+}
+
+/// Runs the apps until `done` holds.
+pub fn run_until(apps: &mut [&mut App], done: impl Fn(&[&mut App]) -> bool) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !done(apps) {
+        assert!(Instant::now() < deadline, "timed out");
+        for app in apps.iter_mut() {
+            app.update();
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    // One more frame for the last move to reach the pieces.
+    for app in apps.iter_mut() {
+        app.update();
+    }
+}
+
+/// Clicks a square and runs one frame.
+pub fn click(app: &mut App, name: &str) {
+    app.world_mut().write_message(TileClicked {
+        position: square(name),
+    });
+    app.update();
+}
+
+/// Plays a move as two clicks: the piece, then its target.
+pub fn play(app: &mut App, from: &str, to: &str) {
+    click(app, from);
+    click(app, to);
+}
+
+/// Holds Ctrl and presses `key` for one frame, as in Ctrl+Z.
+pub fn shortcut(app: &mut App, key: KeyCode) {
+    let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keys.press(KeyCode::ControlLeft);
+    keys.press(key);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .reset_all();
+}
+
+/// The pieces on the board, as the ECS sees them.
+pub fn pieces(app: &mut App) -> Vec<(Entity, GridPosition, Piece)> {
+    app.world_mut()
+        .query::<(Entity, &GridPosition, &Piece)>()
+        .iter(app.world())
+        .map(|(entity, pos, piece)| (entity, *pos, *piece))
+        .collect()
+}
+
+pub fn piece_at(app: &mut App, name: &str) -> Option<Piece> {
+    let pos = square(name);
+    pieces(app)
+        .into_iter()
+        .find(|(_, at, _)| *at == pos)
+        .map(|(_, _, piece)| piece)
+}
+
+/// The entity of the piece on `name`.
+pub fn entity_at(app: &mut App, name: &str) -> Option<Entity> {
+    let pos = square(name);
+    pieces(app)
+        .into_iter()
+        .find(|(_, at, _)| *at == pos)
+        .map(|(entity, _, _)| entity)
+}
@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use chess_core::{GameResult, PieceType, Position, Termination, TimeControl};
+use rust_bevy::{
+    chess_board_plugin::BoardOrientation,
+    components::PieceColor,
+    computer_player::ComputerPlayer,
+    events::{GameAction, MoveMade, NewGame},
+    game_clock::GameClock,
+    resources::GameState,
+};
+
+mod common;
+use common::*;
+
+fn app(fen: Option<&str>) -> App {
+    app_with(|app| {
+        if let Some(fen) = fen {
+            app.insert_resource(GameState::new(Position::from_fen(fen).unwrap()));
+        }
+    })
+}
+
+#[test]
+fn spawns_the_starting_position() {
+    let mut app = app(None);
+    assert_eq!(pieces(&mut app).len(), 32);
+    let king = piece_at(&mut app, "e1").unwrap();
+    assert_eq!(king.kind, PieceType::King);
+}
+
+#[test]
+fn castling_moves_the_rook() {
+    let mut app = app(Some("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"));
+    play(&mut app, "e1", "g1");
+
+    assert_eq!(piece_at(&mut app, "g1").unwrap().kind, PieceType::King);
+    assert_eq!(piece_at(&mut app, "f1").unwrap().kind, PieceType::Rook);
+    assert!(piece_at(&mut app, "e1").is_none());
+    assert!(piece_at(&mut app, "h1").is_none());
+    assert_eq!(game_state(&app).move_history, ["O-O"]);
+}
+
+#[test]
+fn en_passant_despawns_the_captured_pawn() {
+    let mut app = app(Some("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"));
+    let captured = entity_at(&mut app, "d5").unwrap();
+    play(&mut app, "e5", "d6");
+
+    assert_eq!(piece_at(&mut app, "d6").unwrap().kind, PieceType::Pawn);
+    assert!(piece_at(&mut app, "d5").is_none());
+    assert!(app.world().get_entity(captured).is_err());
+    assert_eq!(pieces(&mut app).len(), 3);
+}
+
+#[test]
+fn promotion_waits_for_the_picker() {
+    let mut app = app(Some("4k3/P7/8/8/8/8/8/4K3 w - - 0 1"));
+    play(&mut app, "a7", "a8");
+
+    // Nothing has moved until a piece is picked.
+    assert!(piece_at(&mut app, "a7").is_some());
+    assert!(game_state(&app).moves.is_empty());
+
+    // The choices stack down from a8: queen, rook, bishop, knight.
+    click(&mut app, "a5");
+    let knight = piece_at(&mut app, "a8").unwrap();
+    assert_eq!(knight.kind, PieceType::Knight);
+    assert!(piece_at(&mut app, "a7").is_none());
+    assert_eq!(game_state(&app).move_history, ["a8=N"]);
+}
+
+#[test]
+fn cancelled_promotion_keeps_the_pawn() {
+    let mut app = app(Some("4k3/P7/8/8/8/8/8/4K3 w - - 0 1"));
+    play(&mut app, "a7", "a8");
+    click(&mut app, "h1");
+
+    assert_eq!(piece_at(&mut app, "a7").unwrap().kind, PieceType::Pawn);
+    assert!(game_state(&app).moves.is_empty());
+    assert!(game_state(&app).selected_position.is_none());
+}
+
+#[test]
+fn illegal_move_is_ignored() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e5");
+
+    assert!(piece_at(&mut app, "e2").is_some());
+    assert!(game_state(&app).moves.is_empty());
+}
+
+#[test]
+fn fools_mate_ends_the_game() {
+    let mut app = app(None);
+    play(&mut app, "f2", "f3");
+    play(&mut app, "e7", "e5");
+    play(&mut app, "g2", "g4");
+    play(&mut app, "d8", "h4");
+
+    let state = game_state(&app);
+    assert!(state.is_over());
+    assert_eq!(state.status, "Checkmate! Black wins.");
+    assert_eq!(state.move_history, ["f3", "e5", "g4", "Qh4#"]);
+    assert_eq!(
+        state.result,
+        Some(GameResult::BlackWins(Termination::Checkmate))
+    );
+    assert_eq!(state.to_pgn().result(), "0-1");
+
+    // The board no longer takes moves.
+    play(&mut app, "e2", "e4");
+    assert!(piece_at(&mut app, "e2").is_some());
+}
+
+/// Sends a resignation, draw offer or abort and runs one frame.
+fn act(app: &mut App, action: GameAction) {
+    app.world_mut().write_message(action);
+    app.update();
+}
+
+#[test]
+fn resignation_ends_the_game() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    act(&mut app, GameAction::Resign(PieceColor::Black));
+
+    let state = game_state(&app);
+    assert_eq!(
+        state.result,
+        Some(GameResult::WhiteWins(Termination::Resignation))
+    );
+    assert_eq!(state.status, "Black resigned. White wins.");
+    assert_eq!(state.to_pgn().result(), "1-0");
+
+    play(&mut app, "e7", "e5");
+    assert_eq!(game_state(&app).moves.len(), 1);
+}
+
+#[test]
+fn draws_are_offered_and_agreed() {
+    let mut app = app(None);
+    act(&mut app, GameAction::OfferDraw(PieceColor::White));
+    // Only the other side can accept.
+    act(&mut app, GameAction::AcceptDraw(PieceColor::White));
+    assert!(!game_state(&app).is_over());
+
+    act(&mut app, GameAction::AcceptDraw(PieceColor::Black));
+    let state = game_state(&app);
+    assert_eq!(state.result, Some(GameResult::Draw(Termination::Agreement)));
+    assert_eq!(state.to_pgn().result(), "1/2-1/2");
+}
+
+#[test]
+fn aborting_before_both_sides_move_leaves_no_result() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    act(&mut app, GameAction::Abort(PieceColor::Black));
+
+    let state = game_state(&app);
+    assert!(state.is_over());
+    assert_eq!(state.result, None);
+    assert_eq!(state.status, "Game aborted.");
+    assert_eq!(state.to_pgn().result(), "*");
+}
+
+#[test]
+fn aborting_later_abandons_the_game() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    play(&mut app, "e7", "e5");
+    act(&mut app, GameAction::Abort(PieceColor::White));
+
+    let state = game_state(&app);
+    assert_eq!(
+        state.result,
+        Some(GameResult::BlackWins(Termination::Abandonment))
+    );
+    let pgn = state.to_pgn();
+    assert_eq!(pgn.result(), "0-1");
+    assert_eq!(pgn.tag("Termination"), Some("abandoned"));
+}
+
+/// Both knights out and back, repeating the position the shuffle began in.
+fn shuffle_knights(app: &mut App) {
+    play(app, "g1", "f3");
+    play(app, "g8", "f6");
+    play(app, "f3", "g1");
+    play(app, "f6", "g8");
+}
+
+#[test]
+fn repetition_waits_for_a_claim_until_fivefold() {
+    let mut app = app(None);
+    shuffle_knights(&mut app);
+    shuffle_knights(&mut app);
+    let state = game_state(&app);
+    assert!(!state.is_over());
+    assert_eq!(
+        state.status,
+        "White's Turn (draw by threefold repetition can be claimed)"
+    );
+
+    // Nobody claims it, so play goes on.
+    shuffle_knights(&mut app);
+    shuffle_knights(&mut app);
+    let state = game_state(&app);
+    assert_eq!(
+        state.result,
+        Some(GameResult::Draw(Termination::Repetition))
+    );
+    assert_eq!(state.status, "Draw by fivefold repetition.");
+    assert_eq!(state.moves.len(), 16);
+}
+
+#[test]
+fn threefold_repetition_can_be_claimed() {
+    let mut app = app(None);
+    // Too early: the position has only come up twice.
+    shuffle_knights(&mut app);
+    act(&mut app, GameAction::ClaimDraw(PieceColor::White));
+    assert!(!game_state(&app).is_over());
+
+    shuffle_knights(&mut app);
+    act(&mut app, GameAction::ClaimDraw(PieceColor::White));
+    let state = game_state(&app);
+    assert_eq!(
+        state.result,
+        Some(GameResult::Draw(Termination::Repetition))
+    );
+    assert_eq!(state.status, "Draw by threefold repetition.");
+    assert_eq!(state.to_pgn().result(), "1/2-1/2");
+}
+
+#[test]
+fn new_game_sets_the_board_up_again() {
+    let mut app = app(None);
+    play(&mut app, "f2", "f3");
+    play(&mut app, "e7", "e5");
+    play(&mut app, "g2", "g4");
+    play(&mut app, "d8", "h4");
+    assert!(game_state(&app).is_over());
+
+    app.world_mut().write_message(NewGame::Fresh);
+    app.update();
+
+    let state = game_state(&app);
+    assert!(!state.is_over());
+    assert!(state.moves.is_empty());
+    assert_eq!(state.position.to_fen(), Position::new().to_fen());
+    assert_eq!(pieces(&mut app).len(), 32);
+    assert!(piece_at(&mut app, "h4").is_none());
+    assert_eq!(piece_at(&mut app, "d8").unwrap().kind, PieceType::Queen);
+
+    play(&mut app, "e2", "e4");
+    assert_eq!(game_state(&app).move_history, ["e4"]);
+}
+
+#[test]
+fn rematch_swaps_colors() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    let mut app = app(Some(fen));
+    app.world_mut().resource_mut::<ComputerPlayer>().color = Some(PieceColor::Black);
+    play(&mut app, "e2", "e4");
+    act(&mut app, GameAction::Resign(PieceColor::White));
+
+    app.world_mut().write_message(NewGame::Rematch);
+    app.update();
+
+    // The same starting position, with the computer now playing White.
+    assert_eq!(game_state(&app).position.to_fen(), fen);
+    assert_eq!(pieces(&mut app).len(), 3);
+    assert_eq!(
+        app.world().resource::<ComputerPlayer>().color,
+        Some(PieceColor::White)
+    );
+    assert_eq!(
+        app.world().resource::<BoardOrientation>().bottom,
+        PieceColor::Black
+    );
+}
+
+#[test]
+fn analysis_continues_from_the_final_position() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    play(&mut app, "e7", "e5");
+    act(&mut app, GameAction::Resign(PieceColor::Black));
+
+    let analysis = game_state(&app).for_analysis();
+    assert_eq!(analysis.result, None);
+    assert_eq!(analysis.move_history, ["e4", "e5"]);
+    app.insert_resource(analysis);
+    play(&mut app, "g1", "f3");
+    assert_eq!(game_state(&app).move_history, ["e4", "e5", "Nf3"]);
+}
+
+#[test]
+fn only_played_moves_press_the_clock() {
+    let mut app = app_with(|app| {
+        app.insert_resource(GameClock::new(Some(TimeControl::fischer(5, 10))));
+    });
+    play(&mut app, "e2", "e4");
+    play(&mut app, "e7", "e5");
+    let clock = app.world().resource::<GameClock>();
+    let white = clock.remaining(PieceColor::White).unwrap();
+    let black = clock.remaining(PieceColor::Black).unwrap();
+
+    // A move that does not fit the position is dropped, so it earns no
+    // increment and leaves White's clock running.
+    let pawn = entity_at(&mut app, "e4").unwrap();
+    app.world_mut().write_message(MoveMade {
+        entity: pawn,
+        from: square("e4"),
+        to: square("e6"),
+        promotion: None,
+    });
+    app.update();
+    let clock = app.world().resource::<GameClock>();
+    assert!(clock.remaining(PieceColor::White).unwrap() <= white);
+    assert_eq!(clock.remaining(PieceColor::Black).unwrap(), black);
+    assert_eq!(game_state(&app).moves.len(), 2);
+}
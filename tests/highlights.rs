@@ -0,0 +1,33 @@
+use chess_core::{Position, Square};
+use rust_bevy::{
+    components::GridPosition,
+    highlights::{Highlight, highlights},
+    resources::GameState,
+};
+
+fn square(name: &str) -> GridPosition {
+    GridPosition::from(Square::from_algebraic(name).unwrap())
+}
+
+/// The highlights with `selected` picked up in the position `fen`.
+fn selecting(fen: &str, selected: &str) -> Vec<(GridPosition, Highlight)> {
+    let mut game_state = GameState::new(Position::from_fen(fen).unwrap());
+    game_state.selected_position = Some(square(selected));
+    highlights(&game_state)
+}
+
+#[test]
+fn captures_ring_the_piece_taken() {
+    let marks = selecting("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4");
+    assert!(marks.contains(&(square("d5"), Highlight::Capture)));
+    assert!(marks.contains(&(square("e5"), Highlight::QuietMove)));
+    assert!(!marks.contains(&(square("d5"), Highlight::QuietMove)));
+}
+
+#[test]
+fn en_passant_rings_the_pawn_beside() {
+    let marks = selecting("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5");
+    assert!(marks.contains(&(square("d5"), Highlight::Capture)));
+    assert!(marks.contains(&(square("d6"), Highlight::QuietMove)));
+    assert!(!marks.contains(&(square("d6"), Highlight::Capture)));
+}
@@ -0,0 +1,274 @@
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use chess_core::{PROTOCOL_VERSION, PeerConnection, PeerMessage, PieceColor, PieceType, Position};
+use rust_bevy::{
+    chess_board_plugin::BoardOrientation,
+    events::{GameAction, TileClicked},
+    network::NetworkGame,
+    resources::GameState,
+};
+
+mod common;
+use common::*;
+
+fn host_app(game_state: Option<GameState>) -> App {
+    let network = NetworkGame::host("127.0.0.1:0", PieceColor::White).unwrap();
+    app_with(|app| {
+        if let Some(game_state) = game_state {
+            app.insert_resource(game_state);
+        }
+        app.insert_resource(network);
+    })
+}
+
+fn guest_app(host: &App) -> App {
+    let address = host.world().resource::<NetworkGame>().local_addr().unwrap();
+    app_with(|app| {
+        app.insert_resource(NetworkGame::join(address.to_string()));
+    })
+}
+
+fn connected(app: &App) -> bool {
+    app.world().resource::<NetworkGame>().is_connected()
+}
+
+/// A host playing White and a guest connected to it.
+fn connected_pair(game_state: Option<GameState>) -> (App, App) {
+    let mut host = host_app(game_state);
+    let mut guest = guest_app(&host);
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        apps.iter().all(|app| connected(app))
+    });
+    (host, guest)
+}
+
+/// Plays a move on `mover` and waits for it to arrive on `other`.
+fn play_and_wait(mover: &mut App, other: &mut App, from: &str, to: &str) {
+    let expected = game_state(other).moves.len() + 1;
+    play(mover, from, to);
+    run_until(&mut [mover, other], |apps| {
+        game_state(apps[1]).moves.len() == expected
+    });
+}
+
+#[test]
+fn guest_is_given_the_other_color() {
+    let (host, guest) = connected_pair(None);
+    let guest_network = guest.world().resource::<NetworkGame>();
+    assert_eq!(guest_network.local_color(), Some(PieceColor::Black));
+    assert_eq!(
+        guest.world().resource::<BoardOrientation>().bottom,
+        PieceColor::Black
+    );
+    assert_eq!(game_state(&host).status, "White's Turn");
+    assert_eq!(game_state(&guest).status, "White's Turn");
+}
+
+#[test]
+fn moves_travel_both_ways() {
+    let (mut host, mut guest) = connected_pair(None);
+    play_and_wait(&mut host, &mut guest, "e2", "e4");
+    play_and_wait(&mut guest, &mut host, "e7", "e5");
+    play_and_wait(&mut host, &mut guest, "g1", "f3");
+
+    assert_eq!(history(&host), ["e4", "e5", "Nf3"]);
+    assert_eq!(history(&guest), history(&host));
+    let knight = piece_at(&mut guest, "f3").unwrap();
+    assert_eq!(knight.kind, PieceType::Knight);
+    assert!(piece_at(&mut guest, "g1").is_none());
+}
+
+#[test]
+fn only_the_local_side_can_be_moved() {
+    let (mut host, mut guest) = connected_pair(None);
+    // The guest plays Black, so White's pawn does not move.
+    play(&mut guest, "e2", "e4");
+    assert!(game_state(&guest).moves.is_empty());
+    assert!(piece_at(&mut guest, "e2").is_some());
+
+    play_and_wait(&mut host, &mut guest, "d2", "d4");
+    // Nor does the host's black pawn.
+    play(&mut host, "d7", "d5");
+    assert_eq!(game_state(&host).moves.len(), 1);
+}
+
+#[test]
+fn guest_starts_from_the_host_position() {
+    let fen = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+    let game_state_from_fen = GameState::new(Position::from_fen(fen).unwrap());
+    let (mut host, mut guest) = connected_pair(Some(game_state_from_fen));
+    assert_eq!(game_state(&guest).position.to_fen(), fen);
+
+    // A promotion picked on the host arrives as one.
+    play(&mut host, "a7", "a8");
+    host.world_mut().write_message(TileClicked {
+        position: square("a7"),
+    });
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        game_state(apps[1]).moves.len() == 1
+    });
+    assert_eq!(history(&guest), ["a8=R+"]);
+    assert_eq!(piece_at(&mut guest, "a8").unwrap().kind, PieceType::Rook);
+}
+
+#[test]
+fn resignation_ends_both_games() {
+    let (mut host, mut guest) = connected_pair(None);
+    guest
+        .world_mut()
+        .write_message(GameAction::Resign(PieceColor::Black));
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        apps.iter().all(|app| game_state(app).is_over())
+    });
+    assert_eq!(game_state(&host).status, "Black resigned. White wins.");
+    assert_eq!(game_state(&guest).status, game_state(&host).status);
+}
+
+#[test]
+fn draw_by_agreement() {
+    let (mut host, mut guest) = connected_pair(None);
+    host.world_mut()
+        .write_message(GameAction::OfferDraw(PieceColor::White));
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        game_state(apps[1]).draw_offer.is_some()
+    });
+    assert_eq!(
+        game_state(&guest).status,
+        "White's Turn (White offers a draw)"
+    );
+
+    guest
+        .world_mut()
+        .write_message(GameAction::AcceptDraw(PieceColor::Black));
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        apps.iter().all(|app| game_state(app).is_over())
+    });
+    assert_eq!(game_state(&host).status, "Draw by agreement.");
+    assert_eq!(game_state(&guest).status, "Draw by agreement.");
+}
+
+#[test]
+fn declined_draw_keeps_playing() {
+    let (mut host, mut guest) = connected_pair(None);
+    guest
+        .world_mut()
+        .write_message(GameAction::OfferDraw(PieceColor::Black));
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        game_state(apps[0]).draw_offer.is_some()
+    });
+    host.world_mut()
+        .write_message(GameAction::DeclineDraw(PieceColor::White));
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        game_state(apps[1]).draw_offer.is_none()
+    });
+    assert!(!game_state(&guest).is_over());
+    play_and_wait(&mut host, &mut guest, "e2", "e4");
+}
+
+#[test]
+fn reconnecting_guest_gets_the_game_back() {
+    let (mut host, mut guest) = connected_pair(None);
+    play_and_wait(&mut host, &mut guest, "e2", "e4");
+    play_and_wait(&mut guest, &mut host, "c7", "c5");
+    drop(guest);
+
+    run_until(&mut [&mut host], |apps| !connected(apps[0]));
+    assert!(
+        game_state(&host).status.starts_with("Connection lost"),
+        "{}",
+        game_state(&host).status
+    );
+    // Nothing can be played while the opponent is away.
+    play(&mut host, "g1", "f3");
+    assert_eq!(game_state(&host).moves.len(), 2);
+
+    let mut guest = guest_app(&host);
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        connected(apps[0]) && game_state(apps[1]).moves.len() == 2
+    });
+    assert_eq!(history(&guest), ["e4", "c5"]);
+    assert_eq!(piece_at(&mut guest, "c5").unwrap().kind, PieceType::Pawn);
+    play_and_wait(&mut host, &mut guest, "g1", "f3");
+}
+
+#[test]
+fn guest_undo_is_reverted() {
+    let (mut host, mut guest) = connected_pair(None);
+    play_and_wait(&mut host, &mut guest, "e2", "e4");
+    guest.world_mut().resource_mut::<GameState>().undo();
+    run_until(&mut [&mut host, &mut guest], |apps| {
+        game_state(apps[1]).moves.len() == 1
+    });
+    assert_eq!(history(&guest), ["e4"]);
+    assert!(piece_at(&mut guest, "e4").is_some());
+    assert!(piece_at(&mut guest, "e2").is_none());
+}
+
+#[test]
+fn host_cannot_take_back_moves() {
+    let (mut host, mut guest) = connected_pair(None);
+    play_and_wait(&mut host, &mut guest, "e2", "e4");
+    play_and_wait(&mut guest, &mut host, "e7", "e5");
+
+    shortcut(&mut host, KeyCode::KeyZ);
+    for _ in 0..10 {
+        host.update();
+        guest.update();
+    }
+    assert_eq!(history(&host), ["e4", "e5"]);
+    assert_eq!(history(&guest), ["e4", "e5"]);
+    assert!(piece_at(&mut guest, "e5").is_some());
+}
+
+/// Talks to a host app directly: says hello and reads the handshake.
+fn raw_guest(host: &mut App, version: u32) -> (PeerConnection, Vec<PeerMessage>) {
+    let address = host.world().resource::<NetworkGame>().local_addr().unwrap();
+    let mut connection = PeerConnection::connect(address).unwrap();
+    connection.send(&PeerMessage::Hello { version }).unwrap();
+
+    let mut received = Vec::new();
+    let deadline = Instant::now() + TIMEOUT;
+    while received.len() < 3 && Instant::now() < deadline {
+        host.update();
+        match connection.recv_timeout(Duration::from_millis(10)) {
+            Ok(Some(message)) => received.push(message),
+            Ok(None) => {}
+            Err(_) => break,
+        }
+    }
+    (connection, received)
+}
+
+#[test]
+fn host_rejects_moves_that_do_not_fit() {
+    let mut host = host_app(None);
+    let (mut connection, handshake) = raw_guest(&mut host, PROTOCOL_VERSION);
+    assert!(matches!(handshake[0], PeerMessage::Hello { .. }));
+    assert!(matches!(
+        handshake[1],
+        PeerMessage::Color(PieceColor::Black)
+    ));
+    assert!(matches!(&handshake[2], PeerMessage::Sync { moves, .. } if moves.is_empty()));
+
+    // White's move from the player of Black is answered with the game.
+    connection.send(&PeerMessage::Move("e2e4".into())).unwrap();
+    let deadline = Instant::now() + TIMEOUT;
+    let reply = loop {
+        host.update();
+        if let Some(message) = connection.recv_timeout(Duration::from_millis(10)).unwrap() {
+            break message;
+        }
+        assert!(Instant::now() < deadline, "no reply");
+    };
+    assert!(matches!(reply, PeerMessage::Sync { .. }));
+    assert!(game_state(&host).moves.is_empty());
+}
+
+#[test]
+fn host_turns_away_other_versions() {
+    let mut host = host_app(None);
+    let (_, handshake) = raw_guest(&mut host, PROTOCOL_VERSION + 1);
+    assert!(matches!(handshake.as_slice(), [PeerMessage::Error(_)]));
+    assert!(!connected(&host));
+}
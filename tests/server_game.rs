@@ -0,0 +1,203 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use chess_core::{PieceColor, PieceType, TimeControl};
+use chess_server::{Seat, Server};
+use rust_bevy::{
+    chess_board_plugin::BoardOrientation, events::GameAction, game_clock::GameClock,
+    resources::GameState, server_game::ServerGame,
+};
+
+mod common;
+use common::*;
+
+/// A server on a free localhost port, running until the test ends.
+fn start_server() -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let address = server.local_addr().unwrap();
+    std::thread::spawn(move || server.run());
+    address
+}
+
+fn client_app(server: ServerGame) -> App {
+    app_with(|app| {
+        app.insert_resource(server);
+    })
+}
+
+fn server_game(app: &App) -> &ServerGame {
+    app.world().resource::<ServerGame>()
+}
+
+/// A room created by White with `control`, joined by Black, and watched.
+fn full_room(control: Option<TimeControl>) -> (App, App, App) {
+    let address = start_server().to_string();
+    let mut white = client_app(ServerGame::create(&address, PieceColor::White, control));
+    run_until(&mut [&mut white], |apps| {
+        server_game(apps[0]).room().is_some()
+    });
+    let code = server_game(&white).room().unwrap().to_string();
+    assert!(
+        game_state(&white)
+            .status
+            .starts_with(&format!("Room {code}: waiting")),
+        "{}",
+        game_state(&white).status
+    );
+
+    let mut black = client_app(ServerGame::join(&address, &code));
+    let mut spectator = client_app(ServerGame::watch(&address, &code));
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        apps.iter()
+            .all(|app| game_state(app).status == "White's Turn")
+    });
+    (white, black, spectator)
+}
+
+#[test]
+fn players_are_seated_and_spectators_watch() {
+    let (white, black, spectator) = full_room(None);
+    assert_eq!(
+        server_game(&white).seat(),
+        Some(Seat::Player(PieceColor::White))
+    );
+    assert_eq!(
+        server_game(&black).seat(),
+        Some(Seat::Player(PieceColor::Black))
+    );
+    assert_eq!(server_game(&spectator).seat(), Some(Seat::Spectator));
+    assert_eq!(
+        black.world().resource::<BoardOrientation>().bottom,
+        PieceColor::Black
+    );
+}
+
+#[test]
+fn moves_reach_the_opponent_and_spectators() {
+    let (mut white, mut black, mut spectator) = full_room(None);
+    let mut apps = [&mut white, &mut black, &mut spectator];
+    play(apps[0], "e2", "e4");
+    run_until(&mut apps, |apps| {
+        game_state(apps[1]).moves.len() == 1 && game_state(apps[2]).moves.len() == 1
+    });
+    play(apps[1], "c7", "c5");
+    run_until(&mut apps, |apps| {
+        apps.iter().all(|app| game_state(app).moves.len() == 2)
+    });
+
+    assert_eq!(history(&spectator), ["e4", "c5"]);
+    assert_eq!(history(&white), history(&spectator));
+    assert_eq!(
+        piece_at(&mut spectator, "c5").unwrap().kind,
+        PieceType::Pawn
+    );
+    assert!(piece_at(&mut spectator, "c7").is_none());
+}
+
+#[test]
+fn only_the_seated_side_can_move() {
+    let (mut white, mut black, mut spectator) = full_room(None);
+    // Neither Black nor a spectator can move White's pieces.
+    play(&mut black, "e2", "e4");
+    play(&mut spectator, "e2", "e4");
+    assert!(game_state(&black).moves.is_empty());
+    assert!(game_state(&spectator).moves.is_empty());
+
+    play(&mut white, "d2", "d4");
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        game_state(apps[2]).moves.len() == 1
+    });
+    play(&mut spectator, "d7", "d5");
+    assert_eq!(game_state(&spectator).moves.len(), 1);
+}
+
+#[test]
+fn undone_moves_come_back_from_the_server() {
+    let (mut white, mut black, mut spectator) = full_room(None);
+    play(&mut white, "e2", "e4");
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        game_state(apps[1]).moves.len() == 1
+    });
+    white.world_mut().resource_mut::<GameState>().undo();
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        game_state(apps[0]).moves.len() == 1
+    });
+    assert_eq!(history(&white), ["e4"]);
+    assert!(piece_at(&mut white, "e4").is_some());
+}
+
+#[test]
+fn clocks_follow_the_server() {
+    let control = "5+3".parse().unwrap();
+    let (mut white, mut black, mut spectator) = full_room(Some(control));
+    assert_eq!(
+        black.world().resource::<GameClock>().control(),
+        Some(control)
+    );
+
+    play(&mut white, "e2", "e4");
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        game_state(apps[1]).moves.len() == 1
+    });
+    play(&mut black, "e7", "e5");
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        apps.iter().all(|app| {
+            let clock = app.world().resource::<GameClock>();
+            clock.remaining(PieceColor::Black) > Some(Duration::from_secs(300))
+        })
+    });
+    // Black's clock ran from White's first move, and the increment was
+    // added on top; the server's times reached every board.
+    for app in [&white, &black, &spectator] {
+        let remaining = app
+            .world()
+            .resource::<GameClock>()
+            .remaining(PieceColor::Black);
+        assert!(remaining < Some(Duration::from_secs(303)), "{remaining:?}");
+    }
+}
+
+#[test]
+fn resignation_reaches_everyone() {
+    let (mut white, mut black, mut spectator) = full_room(None);
+    black
+        .world_mut()
+        .write_message(GameAction::Resign(PieceColor::Black));
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        apps.iter().all(|app| game_state(app).is_over())
+    });
+    for app in [&white, &black, &spectator] {
+        assert_eq!(game_state(app).status, "Black resigned. White wins.");
+    }
+}
+
+#[test]
+fn aborts_reach_everyone() {
+    let (mut white, mut black, mut spectator) = full_room(None);
+    play(&mut white, "e2", "e4");
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        apps.iter().all(|app| game_state(app).moves.len() == 1)
+    });
+    black
+        .world_mut()
+        .write_message(GameAction::Abort(PieceColor::Black));
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        apps.iter().all(|app| game_state(app).is_over())
+    });
+    for app in [&white, &black, &spectator] {
+        assert_eq!(game_state(app).status, "Game aborted.");
+        assert_eq!(game_state(app).result, None);
+    }
+}
+
+#[test]
+fn players_cannot_take_back_moves() {
+    let (mut white, mut black, mut spectator) = full_room(None);
+    play(&mut white, "e2", "e4");
+    run_until(&mut [&mut white, &mut black, &mut spectator], |apps| {
+        apps.iter().all(|app| game_state(app).moves.len() == 1)
+    });
+    shortcut(&mut white, KeyCode::KeyZ);
+    assert_eq!(history(&white), ["e4"]);
+}
@@ -0,0 +1,56 @@
+//! A scripted UCI engine for tests. Each `go` is answered with the next move
+//! given on the command line (`(none)` once they run out); `go infinite`
+//! waits for `stop`. Every `position` and `go` command is echoed back as an
+//! `info string` line so tests can check what the client sent. With
+//! `--log=<file>` as the first argument, every command received is also
+//! appended to that file, for tests that cannot read the engine's output.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let mut replies = std::env::args().skip(1).peekable();
+    let mut log: Option<File> = replies.next_if(|arg| arg.starts_with("--log=")).map(|arg| {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&arg["--log=".len()..])
+            .unwrap()
+    });
+    let mut searching = false;
+    let mut out = io::stdout().lock();
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if let Some(log) = &mut log {
+            writeln!(log, "{line}").unwrap();
+        }
+        let command = line.split_whitespace().next().unwrap_or("");
+        let mut answer = false;
+        match command {
+            "uci" => {
+                writeln!(out, "id name Fake Engine").unwrap();
+                writeln!(out, "id author rust-bevy tests").unwrap();
+                writeln!(out, "option name Hash type spin default 16 min 1 max 64").unwrap();
+                writeln!(out, "uciok").unwrap();
+            }
+            "isready" => writeln!(out, "readyok").unwrap(),
+            "position" => writeln!(out, "info string {line}").unwrap(),
+            "go" => {
+                writeln!(out, "info string {line}").unwrap();
+                searching = true;
+                answer = !line.contains("infinite");
+            }
+            "stop" => answer = searching,
+            "quit" => break,
+            _ => {}
+        }
+        if answer {
+            searching = false;
+            let mv = replies.next().unwrap_or_else(|| "(none)".to_string());
+            writeln!(out, "info depth 1 score cp 12 nodes 20 time 3 pv {mv}").unwrap();
+            writeln!(out, "bestmove {mv}").unwrap();
+        }
+        out.flush().unwrap();
+    }
+}
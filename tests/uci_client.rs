@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use chess_core::{EngineMessage, PieceColor, Position, SearchLimits, UciInfo};
+use rust_bevy::{
+    computer_player::ComputerPlayer,
+    events::{GameAction, NewGame},
+    uci_engine::{UciEngine, UciError},
+    uci_player::UciPlayer,
+};
+
+mod common;
+use common::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The scripted engine in `tests/support`, which `cargo test` builds as an
+/// example next to the test binaries.
+fn fake_engine_path() -> PathBuf {
+    let mut path = std::env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.join("examples")
+        .join(format!("fake_uci_engine{}", std::env::consts::EXE_SUFFIX))
+}
+
+fn fake_engine(replies: &[&str]) -> UciEngine {
+    let path = fake_engine_path();
+    assert!(
+        path.exists(),
+        "{} is missing; build it with `cargo build --examples`",
+        path.display()
+    );
+    UciEngine::spawn(path, replies).unwrap()
+}
+
+/// Collects `info string` echoes into `echoes` and returns the next other
+/// message.
+fn next_message(engine: &mut UciEngine, echoes: &mut Vec<String>) -> EngineMessage {
+    loop {
+        match engine.recv_timeout(TIMEOUT).unwrap() {
+            EngineMessage::Info(UciInfo {
+                string: Some(text), ..
+            }) => echoes.push(text),
+            message => return message,
+        }
+    }
+}
+
+#[test]
+fn handshake_reads_engine_name() {
+    let engine = fake_engine(&[]);
+    assert_eq!(engine.name(), Some("Fake Engine"));
+}
+
+#[test]
+fn sends_game_and_reads_best_move() {
+    let mut engine = fake_engine(&["e7e5"]);
+    engine.new_game().unwrap();
+
+    let start = Position::new();
+    let e4 = start.parse_uci("e2e4").unwrap();
+    engine.go(&start, &[e4], SearchLimits::depth(3)).unwrap();
+
+    let mut echoes = Vec::new();
+    let EngineMessage::Info(info) = next_message(&mut engine, &mut echoes) else {
+        panic!("expected search info");
+    };
+    assert_eq!(info.depth, Some(1));
+    assert_eq!(info.pv, ["e7e5"]);
+    assert_eq!(
+        next_message(&mut engine, &mut echoes),
+        EngineMessage::BestMove {
+            mv: Some("e7e5".into()),
+            ponder: None,
+        }
+    );
+    assert_eq!(echoes, ["position startpos moves e2e4", "go depth 3"]);
+}
+
+#[test]
+fn stop_ends_an_infinite_search() {
+    let mut engine = fake_engine(&[]);
+    engine
+        .go(&Position::new(), &[], SearchLimits::default())
+        .unwrap();
+
+    let mut echoes = Vec::new();
+    for _ in 0..2 {
+        if let EngineMessage::Info(info) = engine.recv_timeout(TIMEOUT).unwrap() {
+            echoes.extend(info.string);
+        }
+    }
+    assert_eq!(echoes, ["position startpos", "go infinite"]);
+    assert!(engine.try_recv().unwrap().is_none());
+
+    engine.stop().unwrap();
+    loop {
+        if let EngineMessage::BestMove { mv, .. } = next_message(&mut engine, &mut echoes) {
+            assert_eq!(mv, None);
+            break;
+        }
+    }
+}
+
+#[test]
+fn missing_executable_fails_to_spawn() {
+    assert!(matches!(
+        UciEngine::spawn("./no-such-engine", std::iter::empty::<&str>()),
+        Err(UciError::Spawn(_))
+    ));
+}
+
+#[test]
+fn a_rematch_is_a_new_game_for_the_engine() {
+    let log = std::env::temp_dir().join(format!("fake-uci-{}.log", std::process::id()));
+    let _ = std::fs::remove_file(&log);
+    let engine = UciEngine::spawn(
+        fake_engine_path(),
+        [
+            format!("--log={}", log.display()),
+            "e7e5".into(),
+            "e2e4".into(),
+        ],
+    )
+    .unwrap();
+    let new_games = || {
+        std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .filter(|&line| line == "ucinewgame")
+            .count()
+    };
+
+    let mut app = app_with(|app| {
+        app.insert_resource(UciPlayer::new(engine));
+        app.insert_resource(ComputerPlayer {
+            color: Some(PieceColor::Black),
+            ..default()
+        });
+    });
+    play(&mut app, "e2", "e4");
+    run_until(&mut [&mut app], |apps| game_state(apps[0]).moves.len() == 2);
+    assert_eq!(new_games(), 1);
+
+    // The rematch starts from the same position, with the engine as White.
+    app.world_mut()
+        .write_message(GameAction::Resign(PieceColor::White));
+    app.world_mut().write_message(NewGame::Rematch);
+    run_until(&mut [&mut app], |apps| game_state(apps[0]).moves.len() == 1);
+    assert_eq!(history(&app), ["e4"]);
+    assert_eq!(new_games(), 2);
+    let _ = std::fs::remove_file(&log);
+}
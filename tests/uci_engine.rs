@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use chess_core::{EngineMessage, Position, SearchLimits, UciInfo, UciScore};
+use rust_bevy::uci_engine::UciEngine;
+
+const ENGINE: &str = env!("CARGO_BIN_EXE_uci");
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn engine() -> UciEngine {
+    UciEngine::spawn(ENGINE, std::iter::empty::<&str>()).unwrap()
+}
+
+/// Reads until `bestmove`, returning it with the last `info` line before it.
+fn best_move(engine: &mut UciEngine) -> (Option<String>, Option<UciInfo>) {
+    let mut last_info = None;
+    loop {
+        match engine.recv_timeout(TIMEOUT).unwrap() {
+            EngineMessage::Info(info) => last_info = Some(info),
+            EngineMessage::BestMove { mv, .. } => return (mv, last_info),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn identifies_itself() {
+    let engine = engine();
+    assert!(engine.name().unwrap().starts_with("Bevy Chess"));
+}
+
+#[test]
+fn finds_mate_from_fen() {
+    let mut engine = engine();
+    engine.new_game().unwrap();
+    let position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    engine.go(&position, &[], SearchLimits::depth(3)).unwrap();
+
+    let (mv, info) = best_move(&mut engine);
+    assert_eq!(mv.as_deref(), Some("a1a8"));
+    assert_eq!(info.unwrap().score, Some(UciScore::Mate(1)));
+}
+
+#[test]
+fn plays_after_moves_with_a_clock() {
+    let mut engine = engine();
+    engine
+        .send("position startpos moves e2e4 e7e5 g1f3")
+        .unwrap();
+    engine.send("go wtime 1000 btime 1000").unwrap();
+
+    let (mv, _) = best_move(&mut engine);
+    let mut position = Position::new();
+    for uci in ["e2e4", "e7e5", "g1f3"] {
+        position.make_move(position.parse_uci(uci).unwrap());
+    }
+    assert!(position.parse_uci(&mv.unwrap()).is_some());
+}
+
+#[test]
+fn stop_ends_an_infinite_search() {
+    let mut engine = engine();
+    engine
+        .go(&Position::new(), &[], SearchLimits::default())
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    engine.sync().unwrap();
+    engine.stop().unwrap();
+
+    let (mv, _) = best_move(&mut engine);
+    assert!(Position::new().parse_uci(&mv.unwrap()).is_some());
+}
+
+#[test]
+fn reports_no_move_when_mated() {
+    let mut engine = engine();
+    engine.send("setoption name Hash value 1").unwrap();
+    let mated = Position::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+    engine.go(&mated, &[], SearchLimits::depth(2)).unwrap();
+    assert_eq!(best_move(&mut engine).0, None);
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use chess_core::{PieceType, Position, TimeControl};
+use rust_bevy::{
+    components::PieceColor, computer_player::ComputerPlayer, events::GameAction,
+    game_clock::GameClock, resources::GameState,
+};
+
+mod common;
+use common::*;
+
+fn app(fen: Option<&str>) -> App {
+    app_with(|app| {
+        if let Some(fen) = fen {
+            app.insert_resource(GameState::new(Position::from_fen(fen).unwrap()));
+        }
+    })
+}
+
+fn undo(app: &mut App) {
+    shortcut(app, KeyCode::KeyZ);
+}
+
+fn redo(app: &mut App) {
+    shortcut(app, KeyCode::KeyY);
+}
+
+fn fen(app: &App) -> String {
+    game_state(app).position.to_fen()
+}
+
+#[test]
+fn undo_brings_captured_pieces_back() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    play(&mut app, "d7", "d5");
+    let before = fen(&app);
+    play(&mut app, "e4", "d5");
+    assert_eq!(pieces(&mut app).len(), 31);
+
+    undo(&mut app);
+    assert_eq!(fen(&app), before);
+    assert_eq!(pieces(&mut app).len(), 32);
+    let pawn = piece_at(&mut app, "d5").unwrap();
+    assert_eq!(
+        (pawn.kind, pawn.color),
+        (PieceType::Pawn, PieceColor::Black)
+    );
+    assert_eq!(piece_at(&mut app, "e4").unwrap().color, PieceColor::White);
+    assert_eq!(history(&app), ["e4", "d5"]);
+}
+
+#[test]
+fn undo_uncastles_the_rook() {
+    let start = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let mut app = app(Some(start));
+    play(&mut app, "e1", "g1");
+    undo(&mut app);
+
+    assert_eq!(fen(&app), start);
+    assert_eq!(piece_at(&mut app, "e1").unwrap().kind, PieceType::King);
+    assert_eq!(piece_at(&mut app, "h1").unwrap().kind, PieceType::Rook);
+    assert!(piece_at(&mut app, "f1").is_none());
+
+    // Castling rights came back with it: the other side is open too.
+    play(&mut app, "e1", "c1");
+    assert_eq!(history(&app), ["O-O-O"]);
+}
+
+#[test]
+fn undo_restores_en_passant_and_first_moves() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    play(&mut app, "a7", "a6");
+    play(&mut app, "e4", "e5");
+    play(&mut app, "d7", "d5");
+    let before = fen(&app);
+    play(&mut app, "e5", "d6");
+    assert!(piece_at(&mut app, "d5").is_none());
+
+    undo(&mut app);
+    assert_eq!(fen(&app), before);
+    assert_eq!(piece_at(&mut app, "d5").unwrap().kind, PieceType::Pawn);
+    // The capture is still there to be made.
+    play(&mut app, "e5", "d6");
+    assert_eq!(history(&app).last().unwrap(), "exd6");
+
+    // Back to the start, where the pawn may advance two squares again.
+    for _ in 0..5 {
+        undo(&mut app);
+    }
+    assert!(game_state(&app).moves.is_empty());
+    play(&mut app, "e2", "e4");
+    assert_eq!(history(&app), ["e4"]);
+}
+
+#[test]
+fn redo_replays_until_a_different_move() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    play(&mut app, "e7", "e5");
+    undo(&mut app);
+    undo(&mut app);
+    redo(&mut app);
+    assert_eq!(history(&app), ["e4"]);
+    assert_eq!(game_state(&app).redo_stack.len(), 1);
+
+    // Replaying the undone move by hand keeps nothing stale either.
+    play(&mut app, "e7", "e5");
+    assert!(game_state(&app).redo_stack.is_empty());
+
+    undo(&mut app);
+    play(&mut app, "c7", "c5");
+    assert!(game_state(&app).redo_stack.is_empty());
+    redo(&mut app);
+    assert_eq!(history(&app), ["e4", "c5"]);
+    assert!(piece_at(&mut app, "e5").is_none());
+}
+
+#[test]
+fn computer_replies_are_taken_back_with_the_move() {
+    let mut app = app(None);
+    play(&mut app, "e2", "e4");
+    play(&mut app, "e7", "e5");
+    // The headless app has no engine; the computer only decides what a
+    // take-back steps over.
+    app.world_mut().resource_mut::<ComputerPlayer>().color = Some(PieceColor::Black);
+
+    undo(&mut app);
+    assert!(game_state(&app).moves.is_empty());
+    assert_eq!(pieces(&mut app).len(), 32);
+    assert!(piece_at(&mut app, "e2").is_some());
+
+    redo(&mut app);
+    assert_eq!(history(&app), ["e4", "e5"]);
+    assert!(piece_at(&mut app, "e5").is_some());
+}
+
+#[test]
+fn undo_puts_the_clock_back() {
+    let mut app = app_with(|app| {
+        app.insert_resource(GameClock::new(Some(TimeControl::fischer(5, 10))));
+    });
+    let remaining = |app: &App, color| {
+        app.world()
+            .resource::<GameClock>()
+            .remaining(color)
+            .unwrap()
+    };
+    let base = Duration::from_secs(5 * 60);
+    play(&mut app, "e2", "e4");
+    play(&mut app, "e7", "e5");
+    // Black's move earned it the increment.
+    let black = remaining(&app, PieceColor::Black);
+    assert!(black > base);
+    play(&mut app, "g1", "f3");
+    assert!(remaining(&app, PieceColor::White) > base);
+
+    // White is back to its time before Nf3, and its clock runs again.
+    undo(&mut app);
+    assert_eq!(history(&app), ["e4", "e5"]);
+    let white = remaining(&app, PieceColor::White);
+    assert!(white <= base && white > base - Duration::from_secs(1));
+    assert_eq!(remaining(&app, PieceColor::Black), black);
+    std::thread::sleep(Duration::from_millis(20));
+    app.update();
+    assert!(remaining(&app, PieceColor::White) < white);
+
+    // All the way back, neither side has used any time.
+    undo(&mut app);
+    undo(&mut app);
+    assert!(game_state(&app).moves.is_empty());
+    assert_eq!(remaining(&app, PieceColor::White), base);
+    assert_eq!(remaining(&app, PieceColor::Black), base);
+}
+
+#[test]
+fn undo_reopens_a_finished_game() {
+    let mut app = app(None);
+    play(&mut app, "f2", "f3");
+    play(&mut app, "e7", "e5");
+    play(&mut app, "g2", "g4");
+    play(&mut app, "d8", "h4");
+    assert!(game_state(&app).is_over());
+    undo(&mut app);
+    assert!(!game_state(&app).is_over());
+    assert_eq!(game_state(&app).status, "Black's Turn");
+
+    // A resignation goes the same way as the checkmate did.
+    app.world_mut()
+        .write_message(GameAction::Resign(PieceColor::Black));
+    app.update();
+    assert!(game_state(&app).is_over());
+    undo(&mut app);
+    let state = game_state(&app);
+    assert_eq!(state.result, None);
+    assert_eq!(state.status, "White's Turn");
+    play(&mut app, "e2", "e4");
+    assert_eq!(history(&app), ["f3", "e5", "e4"]);
+}